@@ -0,0 +1,351 @@
+//! `grieg-cli batch`: parallel, order-preserving JSONL evaluation for large inputs.
+//!
+//! Lines are read in chunks, evaluated across all cores, and written back in input order.
+//! After every chunk the output is flushed and (optionally) a checkpoint records the last
+//! committed line, so an interrupted run can `--resume` without duplicating output.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use flate2::read::MultiGzDecoder;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use grieg_engine::Logic;
+use grieg_output::{columns, Format, RowWriter};
+
+use crate::eval_jsonl_line;
+use crate::summary::Summary;
+
+pub struct Options {
+    /// Input path, or `-` for stdin. Gzip input is detected by magic bytes.
+    pub input: String,
+    /// Output path (stdout if `None`).
+    pub out: Option<PathBuf>,
+    pub mem: bool,
+    pub logic: Logic,
+    pub ast: bool,
+    /// Output format; `table` is rejected because it buffers the whole run.
+    pub format: Format,
+    /// Worker threads (0 = all cores).
+    pub threads: usize,
+    /// Lines per parallel chunk; also the checkpoint granularity.
+    pub chunk: usize,
+    pub checkpoint: Option<PathBuf>,
+    /// Continue from the checkpoint instead of starting over.
+    pub resume: bool,
+}
+
+/// Progress committed so far; written atomically after each chunk.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Checkpoint {
+    input: String,
+    /// Input lines (including blank ones) fully evaluated and written.
+    line: u64,
+    /// Output bytes written up to `line` (used to trim a torn tail on resume).
+    out_bytes: u64,
+}
+
+/// Throughput statistics, printed to stderr as one JSON object when the run ends.
+#[derive(Debug, Default, Serialize)]
+pub struct Stats {
+    pub lines: u64,
+    pub evaluated: u64,
+    pub errors: u64,
+    pub skipped_on_resume: u64,
+    pub input_bytes: u64,
+    pub elapsed_s: f64,
+    pub lines_per_s: f64,
+    pub mib_per_s: f64,
+    pub threads: usize,
+}
+
+/// Run the batch; the returned `Summary` covers the lines evaluated by this run only.
+pub fn run(opts: &Options) -> io::Result<(Stats, Summary)> {
+    if opts.format == Format::Table {
+        return Err(io::Error::other("table output is not supported for batch; use csv or ndjson"));
+    }
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(opts.threads)
+        .build()
+        .map_err(io::Error::other)?;
+
+    let ckpt = match (&opts.checkpoint, opts.resume) {
+        (Some(p), true) if p.exists() => {
+            let c: Checkpoint = serde_json::from_str(&fs::read_to_string(p)?)?;
+            if c.input != opts.input {
+                return Err(io::Error::other(format!(
+                    "checkpoint is for '{}', not '{}'",
+                    c.input, opts.input
+                )));
+            }
+            c
+        }
+        _ => Checkpoint { input: opts.input.clone(), ..Default::default() },
+    };
+
+    // A second handle on the output file, to make each chunk durable before its checkpoint.
+    let mut durable: Option<File> = None;
+    let sink: Box<dyn Write> = match &opts.out {
+        Some(p) => {
+            let f = open_output(p, opts.resume, ckpt.out_bytes)?;
+            durable = Some(f.try_clone()?);
+            Box::new(BufWriter::new(f))
+        }
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+    let mut out = RowWriter::new(
+        Counting { inner: sink, bytes: ckpt.out_bytes },
+        opts.format,
+        columns(opts.ast, true),
+    );
+    if ckpt.out_bytes > 0 {
+        out.skip_header();
+    }
+    let mut reader = open_input(&opts.input)?;
+
+    let started = Instant::now();
+    let mut stats = Stats { threads: pool.current_num_threads(), ..Default::default() };
+    let mut state = ckpt;
+    let mut summary = Summary::default();
+
+    // Skip what a previous run already committed.
+    let mut buf = String::new();
+    while stats.skipped_on_resume < state.line {
+        buf.clear();
+        let n = reader.read_line(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        stats.skipped_on_resume += 1;
+        stats.input_bytes += n as u64;
+    }
+
+    let chunk = opts.chunk.max(1);
+    let mut lines: Vec<(u64, String)> = Vec::with_capacity(chunk);
+    loop {
+        lines.clear();
+        let mut eof = false;
+        while lines.len() < chunk {
+            buf.clear();
+            let n = reader.read_line(&mut buf)?;
+            if n == 0 {
+                eof = true;
+                break;
+            }
+            stats.input_bytes += n as u64;
+            state.line += 1;
+            lines.push((state.line, std::mem::take(&mut buf)));
+        }
+        if lines.is_empty() {
+            break;
+        }
+
+        let results: Vec<_> = pool.install(|| {
+            lines
+                .par_iter()
+                .map(|(lineno, raw)| {
+                    let line = raw.trim();
+                    (!line.is_empty()).then(|| {
+                        eval_jsonl_line(line, *lineno as usize, opts.mem, opts.logic, opts.ast)
+                    })
+                })
+                .collect()
+        });
+
+        for (row, record) in results.iter().flatten() {
+            stats.evaluated += 1;
+            match record {
+                Some(r) => summary.add(r),
+                None => {
+                    stats.errors += 1;
+                    summary.add_error();
+                }
+            }
+            out.write(row)?;
+        }
+        stats.lines = state.line;
+        out.finish()?;
+        state.out_bytes = out.get_mut().bytes;
+        if let Some(f) = &durable {
+            f.sync_data()?;
+        }
+        if let Some(p) = &opts.checkpoint {
+            write_checkpoint(p, &state)?;
+        }
+        if eof {
+            break;
+        }
+    }
+
+    stats.lines = state.line;
+    finish(&mut stats, started.elapsed());
+    Ok((stats, summary))
+}
+
+/// Open the input, transparently un-gzipping when it starts with the gzip magic.
+fn open_input(path: &str) -> io::Result<Box<dyn BufRead>> {
+    let raw: Box<dyn Read> =
+        if path == "-" { Box::new(io::stdin().lock()) } else { Box::new(File::open(path)?) };
+    let mut r = BufReader::with_capacity(1 << 16, raw);
+    if r.fill_buf()?.starts_with(&[0x1f, 0x8b]) {
+        Ok(Box::new(BufReader::with_capacity(1 << 16, MultiGzDecoder::new(r))))
+    } else {
+        Ok(Box::new(r))
+    }
+}
+
+/// Fresh runs truncate; resumed runs cut back to the committed length and append. An output
+/// shorter than that lost committed rows, and resuming would leave a gap.
+fn open_output(path: &Path, resume: bool, committed: u64) -> io::Result<File> {
+    if !resume {
+        return File::create(path);
+    }
+    let f = OpenOptions::new().create(true).append(true).open(path)?;
+    let len = f.metadata()?.len();
+    if len < committed {
+        return Err(io::Error::other(format!(
+            "'{}' has {len} bytes but the checkpoint committed {committed}; start over without \
+             --resume",
+            path.display()
+        )));
+    }
+    f.set_len(committed)?;
+    Ok(f)
+}
+
+/// Counts bytes written so checkpoints can record the committed output length.
+struct Counting<W> {
+    inner: W,
+    bytes: u64,
+}
+
+impl<W: Write> Write for Counting<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.bytes += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Write to a sibling temp file, sync it, then rename over `path`: a crash leaves either the
+/// old checkpoint or the new one, never a torn one.
+fn write_checkpoint(path: &Path, c: &Checkpoint) -> io::Result<()> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    let tmp = path.with_file_name(name);
+    let mut f = File::create(&tmp)?;
+    f.write_all(&serde_json::to_vec(c)?)?;
+    f.sync_all()?;
+    fs::rename(tmp, path)
+}
+
+fn finish(stats: &mut Stats, elapsed: Duration) {
+    let secs = elapsed.as_secs_f64();
+    stats.elapsed_s = secs;
+    if secs > 0.0 {
+        stats.lines_per_s = (stats.lines - stats.skipped_on_resume) as f64 / secs;
+        stats.mib_per_s = stats.input_bytes as f64 / (1024.0 * 1024.0) / secs;
+    }
+}
+
+impl Stats {
+    pub fn to_json(&self) -> Value {
+        json!({ "batch_stats": self })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    fn opts(input: &Path, out: &Path, ckpt: &Path) -> Options {
+        Options {
+            input: input.display().to_string(),
+            out: Some(out.to_path_buf()),
+            mem: false,
+            logic: Logic::default(),
+            ast: false,
+            format: Format::Ndjson,
+            threads: 4,
+            chunk: 3,
+            checkpoint: Some(ckpt.to_path_buf()),
+            resume: false,
+        }
+    }
+
+    fn tmpdir(name: &str) -> PathBuf {
+        let d = std::env::temp_dir().join(format!("grieg-batch-{name}-{}", std::process::id()));
+        fs::create_dir_all(&d).unwrap();
+        d
+    }
+
+    #[test]
+    fn preserves_order_and_resumes_from_checkpoint() {
+        let d = tmpdir("order");
+        let input = d.join("in.jsonl");
+        let exprs: Vec<String> =
+            (0..20).map(|i| if i % 2 == 0 { format!("v{i}") } else { "true".into() }).collect();
+        fs::write(&input, exprs.join("\n")).unwrap();
+        let (out, ckpt) = (d.join("out.jsonl"), d.join("ckpt.json"));
+
+        let (stats, _) = run(&opts(&input, &out, &ckpt)).unwrap();
+        assert_eq!((stats.lines, stats.evaluated), (20, 20));
+        let full = fs::read_to_string(&out).unwrap();
+        let inputs: Vec<String> = full
+            .lines()
+            .map(|l| serde_json::from_str::<Value>(l).unwrap()["input"].as_str().unwrap().into())
+            .collect();
+        assert_eq!(inputs, exprs);
+
+        // Simulate a crash after 2 chunks with a torn partial line on disk.
+        let two_chunks: usize = full.lines().take(6).map(|l| l.len() + 1).sum();
+        let c = Checkpoint {
+            input: input.display().to_string(),
+            line: 6,
+            out_bytes: two_chunks as u64,
+        };
+        write_checkpoint(&ckpt, &c).unwrap();
+        fs::write(&out, format!("{}{{\"torn", &full[..two_chunks])).unwrap();
+
+        let (stats, _) = run(&Options { resume: true, ..opts(&input, &out, &ckpt) }).unwrap();
+        assert_eq!(stats.skipped_on_resume, 6);
+        assert_eq!(fs::read_to_string(&out).unwrap(), full);
+        assert!(!d.join("ckpt.json.tmp").exists());
+
+        // An output that lost committed rows is not silently extended.
+        write_checkpoint(&ckpt, &c).unwrap();
+        fs::write(&out, &full[..two_chunks - 1]).unwrap();
+        let err = run(&Options { resume: true, ..opts(&input, &out, &ckpt) }).unwrap_err();
+        assert!(err.to_string().contains("checkpoint committed"), "{err}");
+        fs::remove_dir_all(d).ok();
+    }
+
+    #[test]
+    fn reads_gzip_input() {
+        let d = tmpdir("gz");
+        let input = d.join("in.jsonl.gz");
+        let mut enc = GzEncoder::new(File::create(&input).unwrap(), Compression::fast());
+        enc.write_all(b"{\"expr\":\"A -> B\"}\n@jam(true)\n").unwrap();
+        enc.finish().unwrap();
+        let (out, ckpt) = (d.join("out.jsonl"), d.join("ckpt.json"));
+
+        run(&opts(&input, &out, &ckpt)).unwrap();
+        let phases: Vec<String> = fs::read_to_string(&out)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str::<Value>(l).unwrap()["phase"].as_str().unwrap().into())
+            .collect();
+        assert_eq!(phases, ["VAC", "JAM"]);
+        fs::remove_dir_all(d).ok();
+    }
+}
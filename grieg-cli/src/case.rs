@@ -0,0 +1,153 @@
+//! JSONL case schemas shared by `--jsonl` and `conformance`.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use grieg_engine::mem::Fact;
+use grieg_engine::{Evaluator, Logic};
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+
+/// Fields every case kind may carry.
+#[derive(Debug, Default, Deserialize)]
+pub struct CaseMeta {
+    /// Stable case identifier for reports (defaults to the line number).
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Do not run the case.
+    #[serde(default)]
+    pub skip: bool,
+    /// Run the case but expect it to fail; an unexpected pass is reported as a failure.
+    #[serde(default)]
+    pub xfail: bool,
+    #[serde(default)]
+    pub note: Option<String>,
+    /// Rule the case exercises; groups summary reports (falls back to `note`).
+    #[serde(default)]
+    pub rule_id: Option<String>,
+}
+
+impl CaseMeta {
+    /// Tag filter: must carry one of `include` (if any) and none of `exclude`.
+    pub fn selected(&self, include: &[String], exclude: &[String]) -> bool {
+        let has = |t: &String| self.tags.contains(t);
+        (include.is_empty() || include.iter().any(has)) && !exclude.iter().any(has)
+    }
+}
+
+/// JSONL input schema for conformance runs.
+#[derive(Debug, Deserialize)]
+pub struct JsonlCase {
+    pub expr: String,
+    /// MEM on/off for this case; defaults to on when `facts`, `sources` or `records` are
+    /// given.
+    #[serde(default)]
+    pub mem: Option<bool>,
+    /// MEM witnesses (identifier -> bool) loaded into the evaluator before the run.
+    #[serde(default)]
+    pub facts: HashMap<String, bool>,
+    /// MEM witnesses per source (source -> identifier -> bool); sources that disagree on an
+    /// identifier make it JAM.
+    #[serde(default)]
+    pub sources: HashMap<String, HashMap<String, bool>>,
+    /// MEM facts with provenance (`ident`, `value`, `source`, `asserted_at`, `expires_at`,
+    /// `confidence`), as in an exported MEM document.
+    #[serde(default)]
+    pub records: Vec<Fact>,
+    /// Instant the facts are judged at (expiry); the wall clock when absent.
+    #[serde(default)]
+    pub at: Option<DateTime<Utc>>,
+    /// Truth domain for this case (`k3`, `l3`, `four`, `classical`); overrides `--logic`.
+    #[serde(default)]
+    pub logic: Option<Logic>,
+    #[serde(default)]
+    pub expect_phase: Option<String>,
+    /// Expected value: `true`, `false`, `null` or `"both"` (absent = not checked).
+    #[serde(default, deserialize_with = "present")]
+    pub expect_value: Option<Value>,
+    /// Expected sheet: `F` (factual) or `C` (counterfactual).
+    #[serde(default)]
+    pub expect_sheet: Option<String>,
+    #[serde(flatten)]
+    pub meta: CaseMeta,
+}
+
+impl JsonlCase {
+    pub fn mem_enabled(&self, global_mem: bool) -> bool {
+        let witnessed =
+            !self.facts.is_empty() || !self.sources.is_empty() || !self.records.is_empty();
+        self.mem.unwrap_or(global_mem || witnessed)
+    }
+
+    /// An evaluator set up for this case: MEM, facts, sources, records, time and logic.
+    pub fn evaluator(&self, global_mem: bool, global_logic: Logic) -> Evaluator {
+        let mut ev = Evaluator::new(self.mem_enabled(global_mem))
+            .with_logic(self.logic.unwrap_or(global_logic));
+        ev.import_mem(self.facts.clone());
+        for (source, facts) in &self.sources {
+            ev.import_mem_from(source, facts.clone());
+        }
+        for fact in &self.records {
+            ev.assert_mem(fact.clone());
+        }
+        ev.at = self.at;
+        ev
+    }
+}
+
+/// λ-calculus case (`conformance/lambda.json`).
+#[derive(Debug, Deserialize)]
+pub struct LamCase {
+    pub lam: String,
+    #[serde(default)]
+    pub fuel: Option<usize>,
+    #[serde(default)]
+    pub expect_phase: Option<String>,
+    /// Expected Church numeral of the normal form, or `null` for "not a numeral".
+    #[serde(default, deserialize_with = "present")]
+    pub expect_value: Option<Value>,
+    #[serde(flatten)]
+    pub meta: CaseMeta,
+}
+
+/// Any line of a conformance file.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Case {
+    Expr(JsonlCase),
+    Lam(LamCase),
+}
+
+impl Case {
+    pub fn meta(&self) -> &CaseMeta {
+        match self {
+            Case::Expr(c) => &c.meta,
+            Case::Lam(c) => &c.meta,
+        }
+    }
+
+    pub fn into_meta(self) -> CaseMeta {
+        match self {
+            Case::Expr(c) => c.meta,
+            Case::Lam(c) => c.meta,
+        }
+    }
+
+    /// The expression or λ-term under test.
+    pub fn input(&self) -> &str {
+        match self {
+            Case::Expr(c) => &c.expr,
+            Case::Lam(c) => &c.lam,
+        }
+    }
+}
+
+/// Default β-step budget for λ cases without `fuel`.
+pub const DEFAULT_FUEL: usize = 10_000;
+
+/// Keeps an explicit JSON `null` as `Some(Value::Null)` so it can be told apart from "absent".
+fn present<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Value>, D::Error> {
+    Value::deserialize(d).map(Some)
+}
@@ -0,0 +1,513 @@
+//! `grieg-cli conformance`: run every case file, summarize, and fail loudly.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use grieg_engine::lam::eval::{church_numeral, eval_normal_order};
+use grieg_engine::Logic;
+use grieg_parser::lam::parse_lam;
+use grieg_parser::parse_expr;
+
+use crate::case::{Case, CaseMeta, DEFAULT_FUEL};
+use crate::phase_to_str;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Pass,
+    Fail,
+    Error,
+    Skip,
+    /// Failed as expected (`xfail`).
+    XFail,
+}
+
+/// Outcome of a single case.
+#[derive(Debug)]
+pub struct CaseOutcome {
+    pub line: usize,
+    pub id: Option<String>,
+    /// The expression (or λ-term) under test.
+    pub input: String,
+    pub note: Option<String>,
+    pub status: Status,
+    /// Why the case failed or errored.
+    pub message: Option<String>,
+}
+
+/// All outcomes of one case file.
+#[derive(Debug)]
+pub struct FileReport {
+    pub path: PathBuf,
+    pub cases: Vec<CaseOutcome>,
+}
+
+impl FileReport {
+    pub fn count(&self, s: Status) -> usize {
+        self.cases.iter().filter(|c| c.status == s).count()
+    }
+
+    fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+}
+
+pub struct Options {
+    pub paths: Vec<PathBuf>,
+    pub mem: bool,
+    /// Logic for cases that do not name one.
+    pub logic: Logic,
+    /// Only run cases carrying one of these tags (empty = all).
+    pub tags: Vec<String>,
+    /// Never run cases carrying one of these tags.
+    pub exclude_tags: Vec<String>,
+    pub verbose: bool,
+    pub junit: Option<PathBuf>,
+    pub markdown: Option<PathBuf>,
+}
+
+/// Run the suite and return the process exit code (0 = all passed).
+pub fn run(opts: &Options) -> io::Result<i32> {
+    let files = collect_files(&opts.paths)?;
+    if files.is_empty() {
+        eprintln!("conformance: no case files found");
+        return Ok(2);
+    }
+
+    let mut reports = Vec::with_capacity(files.len());
+    for f in files {
+        let text = fs::read_to_string(&f)?;
+        reports.push(FileReport { cases: run_file(&text, opts), path: f });
+    }
+
+    for r in &reports {
+        for c in &r.cases {
+            if matches!(c.status, Status::Fail | Status::Error) || opts.verbose {
+                println!(
+                    "{} {}:{}: {}{}",
+                    status_str(c.status),
+                    r.name(),
+                    c.line,
+                    c.input,
+                    c.message.as_deref().map(|m| format!(" — {m}")).unwrap_or_default()
+                );
+            }
+        }
+    }
+    for r in &reports {
+        println!("{}: {}", r.name(), summary(&tally(std::slice::from_ref(r))));
+    }
+    let t = tally(&reports);
+    println!("total: {}", summary(&t));
+    let (fail, err) = (t[Status::Fail as usize], t[Status::Error as usize]);
+
+    if let Some(p) = &opts.junit {
+        fs::write(p, junit_xml(&reports))?;
+    }
+    if let Some(p) = &opts.markdown {
+        fs::write(p, markdown(&reports))?;
+    }
+
+    Ok(if fail + err == 0 { 0 } else { 1 })
+}
+
+/// Expand directories to their `*.jsonl` / `*.json` files (sorted); keep explicit files.
+fn collect_files(paths: &[PathBuf]) -> io::Result<Vec<PathBuf>> {
+    let mut out = Vec::new();
+    for p in paths {
+        if p.is_dir() {
+            let mut found: Vec<PathBuf> = fs::read_dir(p)?
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|f| is_case_file(f))
+                .collect();
+            found.sort();
+            out.extend(found);
+        } else {
+            out.push(p.clone());
+        }
+    }
+    Ok(out)
+}
+
+fn is_case_file(p: &Path) -> bool {
+    p.is_file() && matches!(p.extension().and_then(|e| e.to_str()), Some("jsonl" | "json"))
+}
+
+fn run_file(text: &str, opts: &Options) -> Vec<CaseOutcome> {
+    let mut out = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let lineno = i + 1;
+        let case = match serde_json::from_str::<Case>(line) {
+            Ok(c) => c,
+            Err(e) => {
+                out.push(CaseOutcome {
+                    line: lineno,
+                    id: None,
+                    input: line.to_string(),
+                    note: None,
+                    status: Status::Error,
+                    message: Some(format!("bad case: {e}")),
+                });
+                continue;
+            }
+        };
+        if !case.meta().selected(&opts.tags, &opts.exclude_tags) {
+            continue;
+        }
+        if case.meta().skip {
+            let input = case.input().to_string();
+            let meta = case.into_meta();
+            out.push(CaseOutcome {
+                line: lineno,
+                id: meta.id,
+                input,
+                note: meta.note,
+                status: Status::Skip,
+                message: None,
+            });
+            continue;
+        }
+        let xfail = case.meta().xfail;
+        let mut outcome = match case {
+            Case::Expr(tc) => {
+                let checked = parse_expr(&tc.expr).map(|ast| {
+                    let res = tc.evaluator(opts.mem, opts.logic).eval(&ast, None);
+                    check(
+                        phase_to_str(&res.phase),
+                        &grieg_output::value(&res.value),
+                        tc.expect_phase.as_deref(),
+                        tc.expect_value.as_ref(),
+                        tc.expect_sheet.as_deref().map(|exp| (exp, res.sheet.as_str())),
+                    )
+                });
+                outcome(lineno, tc.expr, tc.meta, checked.map_err(|e| e.to_string()))
+            }
+            Case::Lam(lc) => {
+                let checked = parse_lam(&lc.lam).map(|t| {
+                    let res = eval_normal_order(t, lc.fuel.unwrap_or(DEFAULT_FUEL));
+                    let value = church_numeral(&res.term).map_or(Value::Null, |n| json!(n));
+                    check(
+                        phase_to_str(&res.phase()),
+                        &value,
+                        lc.expect_phase.as_deref(),
+                        lc.expect_value.as_ref(),
+                        None,
+                    )
+                });
+                outcome(lineno, lc.lam, lc.meta, checked.map_err(|e| e.to_string()))
+            }
+        };
+        if xfail {
+            (outcome.status, outcome.message) = match outcome.status {
+                Status::Pass => (Status::Fail, Some("xfail case passed unexpectedly".into())),
+                _ => (Status::XFail, outcome.message),
+            };
+        }
+        out.push(outcome);
+    }
+    out
+}
+
+/// Compare actual phase/value against the expectations that are present; `sheet` is the
+/// expected and the actual sheet, when one is expected.
+fn check(
+    phase: &str,
+    value: &Value,
+    expect_phase: Option<&str>,
+    expect_value: Option<&Value>,
+    sheet: Option<(&str, &str)>,
+) -> Result<(), String> {
+    let mut why = Vec::new();
+    if let Some(exp) = expect_phase {
+        if exp != phase {
+            why.push(format!("expected phase {exp}, got {phase}"));
+        }
+    }
+    if let Some(exp) = expect_value {
+        if exp != value {
+            why.push(format!("expected value {exp}, got {value}"));
+        }
+    }
+    if let Some((exp, got)) = sheet {
+        if exp != got {
+            why.push(format!("expected sheet {exp}, got {got}"));
+        }
+    }
+    if why.is_empty() {
+        Ok(())
+    } else {
+        Err(why.join("; "))
+    }
+}
+
+fn outcome(
+    line: usize,
+    input: String,
+    meta: CaseMeta,
+    res: Result<Result<(), String>, String>,
+) -> CaseOutcome {
+    let (status, message) = match res {
+        Ok(Ok(())) => (Status::Pass, None),
+        Ok(Err(why)) => (Status::Fail, Some(why)),
+        Err(e) => (Status::Error, Some(e)),
+    };
+    CaseOutcome { line, id: meta.id, input, note: meta.note, status, message }
+}
+
+const STATUSES: [Status; 5] =
+    [Status::Pass, Status::Fail, Status::Error, Status::Skip, Status::XFail];
+
+/// Per-status counts, indexed by `Status as usize`.
+fn tally(reports: &[FileReport]) -> [usize; 5] {
+    let mut t = [0; 5];
+    for r in reports {
+        for s in STATUSES {
+            t[s as usize] += r.count(s);
+        }
+    }
+    t
+}
+
+fn summary(t: &[usize; 5]) -> String {
+    format!(
+        "{} passed, {} failed, {} errors, {} skipped, {} xfail",
+        t[Status::Pass as usize],
+        t[Status::Fail as usize],
+        t[Status::Error as usize],
+        t[Status::Skip as usize],
+        t[Status::XFail as usize]
+    )
+}
+
+fn status_str(s: Status) -> &'static str {
+    match s {
+        Status::Pass => "PASS",
+        Status::Fail => "FAIL",
+        Status::Error => "ERROR",
+        Status::Skip => "SKIP",
+        Status::XFail => "XFAIL",
+    }
+}
+
+fn case_name(c: &CaseOutcome) -> String {
+    let head = match &c.id {
+        Some(id) => id.clone(),
+        None => format!("line {}", c.line),
+    };
+    match &c.note {
+        Some(n) => format!("{head}: {} ({n})", c.input),
+        None => format!("{head}: {}", c.input),
+    }
+}
+
+// ----------------------------------------------------------------
+// Reports
+// ----------------------------------------------------------------
+
+fn junit_xml(reports: &[FileReport]) -> String {
+    let t = tally(reports);
+    let mut s = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        s,
+        "<testsuites name=\"grieg-conformance\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\">",
+        t.iter().sum::<usize>(),
+        t[Status::Fail as usize],
+        t[Status::Error as usize],
+        t[Status::Skip as usize] + t[Status::XFail as usize]
+    );
+    for r in reports {
+        let name = xml_escape(&r.name());
+        let _ = writeln!(
+            s,
+            "  <testsuite name=\"{name}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\">",
+            r.cases.len(),
+            r.count(Status::Fail),
+            r.count(Status::Error),
+            r.count(Status::Skip) + r.count(Status::XFail)
+        );
+        for c in &r.cases {
+            let _ = write!(
+                s,
+                "    <testcase classname=\"{name}\" name=\"{}\"",
+                xml_escape(&case_name(c))
+            );
+            let msg = xml_escape(c.message.as_deref().unwrap_or_default());
+            match c.status {
+                Status::Pass => s.push_str("/>\n"),
+                Status::Fail => {
+                    let _ = writeln!(s, ">\n      <failure message=\"{msg}\"/>\n    </testcase>");
+                }
+                Status::Error => {
+                    let _ = writeln!(s, ">\n      <error message=\"{msg}\"/>\n    </testcase>");
+                }
+                Status::Skip => s.push_str(">\n      <skipped/>\n    </testcase>\n"),
+                Status::XFail => {
+                    let _ = writeln!(
+                        s,
+                        ">\n      <skipped message=\"xfail: {msg}\"/>\n    </testcase>"
+                    );
+                }
+            }
+        }
+        s.push_str("  </testsuite>\n");
+    }
+    s.push_str("</testsuites>\n");
+    s
+}
+
+fn markdown(reports: &[FileReport]) -> String {
+    let row = |t: [usize; 5]| t.map(|n| n.to_string()).join(" | ");
+    let mut s = String::from("# Grieg conformance report\n\n");
+    s.push_str("| File | Passed | Failed | Errors | Skipped | XFail |\n");
+    s.push_str("|---|---:|---:|---:|---:|---:|\n");
+    for r in reports {
+        let _ = writeln!(s, "| `{}` | {} |", r.name(), row(tally(std::slice::from_ref(r))));
+    }
+    let _ = writeln!(s, "| **total** | {} |", row(tally(reports)));
+
+    let bad: Vec<_> = reports
+        .iter()
+        .flat_map(|r| r.cases.iter().map(move |c| (r, c)))
+        .filter(|(_, c)| matches!(c.status, Status::Fail | Status::Error))
+        .collect();
+    if !bad.is_empty() {
+        s.push_str("\n## Failures\n\n");
+        for (r, c) in bad {
+            let _ = writeln!(
+                s,
+                "- **{}** `{}:{}` `{}` — {}",
+                status_str(c.status),
+                r.name(),
+                c.line,
+                c.input.replace('`', "'"),
+                c.message.as_deref().unwrap_or_default()
+            );
+        }
+    }
+    s
+}
+
+fn xml_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for ch in s.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opts() -> Options {
+        Options {
+            paths: Vec::new(),
+            mem: false,
+            logic: Logic::default(),
+            tags: Vec::new(),
+            exclude_tags: Vec::new(),
+            verbose: false,
+            junit: None,
+            markdown: None,
+        }
+    }
+
+    #[test]
+    fn counts_pass_fail_error() {
+        let text = r#"{"expr":"A -> B","expect_phase":"VAC"}
+{"expr":"true","expect_phase":"JAM"}
+{"expr":"true &&","expect_phase":"ALIVE"}
+{"expr":"true","expect_value":true}
+{"expr":"A","expect_value":null}"#;
+        let cases = run_file(text, &opts());
+        let st: Vec<_> = cases.iter().map(|c| c.status).collect();
+        assert_eq!(st, [Status::Pass, Status::Fail, Status::Error, Status::Pass, Status::Pass]);
+    }
+
+    #[test]
+    fn lam_cases_map_divergence_to_jam() {
+        let cases = run_file(r#"{"lam":"Y (\\x. x x)","fuel":50,"expect_phase":"JAM"}"#, &opts());
+        assert_eq!(cases[0].status, Status::Pass);
+    }
+
+    #[test]
+    fn repo_lambda_cases_pass() {
+        let cases = run_file(include_str!("../../conformance/lambda.json"), &opts());
+        assert_eq!(cases.len(), 2);
+        assert!(cases.iter().all(|c| c.status == Status::Pass), "{cases:?}");
+    }
+
+    #[test]
+    fn facts_witness_identifiers() {
+        let text = r#"{"expr":"sap_ok","facts":{"sap_ok":true},"expect_phase":"MEM","expect_value":true}
+{"expr":"sap_ok","facts":{"sap_ok":true},"mem":false,"expect_phase":"VAC"}"#;
+        let cases = run_file(text, &opts());
+        assert!(cases.iter().all(|c| c.status == Status::Pass), "{cases:?}");
+    }
+
+    #[test]
+    fn sheets_are_checked() {
+        let text = r#"{"expr":"x | true","expect_sheet":"F"}
+{"expr":"@mem(x)","mem":true,"expect_sheet":"F"}"#;
+        let cases = run_file(text, &opts());
+        assert_eq!(cases[0].status, Status::Pass);
+        assert_eq!(cases[1].message.as_deref(), Some("expected sheet F, got C"));
+    }
+
+    #[test]
+    fn case_logic_overrides_the_default() {
+        let text = r#"{"expr":"x -> x","expect_value":true}
+{"expr":"x -> x","logic":"k3","expect_value":null}
+{"expr":"@jam(x)","logic":"four","expect_phase":"JAM","expect_value":"both"}"#;
+        let l3 = Options { logic: Logic::L3, ..opts() };
+        let cases = run_file(text, &l3);
+        assert!(cases.iter().all(|c| c.status == Status::Pass), "{cases:?}");
+    }
+
+    #[test]
+    fn skip_xfail_and_tag_filter() {
+        let text = r#"{"expr":"true","expect_phase":"JAM","skip":true,"tags":["a"]}
+{"expr":"true","expect_phase":"JAM","xfail":true,"tags":["a"]}
+{"expr":"true","expect_phase":"ALIVE","xfail":true,"tags":["b"]}"#;
+        let st: Vec<_> = run_file(text, &opts()).iter().map(|c| c.status).collect();
+        assert_eq!(st, [Status::Skip, Status::XFail, Status::Fail]);
+
+        let only_b = Options { tags: vec!["b".into()], ..opts() };
+        assert_eq!(run_file(text, &only_b).len(), 1);
+        let not_b = Options { exclude_tags: vec!["b".into()], ..opts() };
+        assert_eq!(run_file(text, &not_b).len(), 2);
+    }
+
+    #[test]
+    fn junit_escapes_and_counts() {
+        let r = FileReport {
+            path: PathBuf::from("x.jsonl"),
+            cases: vec![CaseOutcome {
+                line: 1,
+                id: None,
+                input: "a & b".into(),
+                note: None,
+                status: Status::Fail,
+                message: Some("expected phase \"JAM\"".into()),
+            }],
+        };
+        let xml = junit_xml(&[r]);
+        assert!(xml.contains("failures=\"1\""));
+        assert!(xml.contains("a &amp; b"));
+        assert!(xml.contains("&quot;JAM&quot;"));
+    }
+}
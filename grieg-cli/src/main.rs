@@ -1,42 +1,23 @@
+mod batch;
+mod case;
+mod conformance;
+mod summary;
+mod validate;
+mod whatif;
+
 use std::env;
 use std::fs::File;
 use std::io::{self, BufRead, Read};
+use std::path::PathBuf;
 
-use serde::Deserialize;
-use serde_json::{json, Value};
-
-use grieg_engine::{Evaluator};
+use grieg_engine::ast::to_sexpr;
+use grieg_engine::mem::MemDoc;
 use grieg_engine::phase::Phase;
-use grieg_engine::value::V;
+use grieg_engine::{Evaluator, Logic};
+use grieg_output::{columns, Format, Row, RowWriter};
 use grieg_parser::parse_expr;
 
-/// JSONL input schema for conformance runs.
-#[derive(Debug, Deserialize)]
-struct JsonlCase {
-    expr: String,
-    #[serde(default)]
-    mem: Option<bool>,
-    #[serde(default)]
-    expect_phase: Option<String>,
-    #[serde(default)]
-    note: Option<String>,
-}
-
-#[derive(serde::Serialize)]
-struct Out<'a> {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    input: Option<&'a str>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    ast: Option<String>,
-    value: Value,
-    phase: &'a str,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    ok: Option<bool>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    expect_phase: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    note: Option<String>,
-}
+use crate::case::JsonlCase;
 
 fn phase_to_str(p: &Phase) -> &'static str {
     match p {
@@ -47,36 +28,29 @@ fn phase_to_str(p: &Phase) -> &'static str {
     }
 }
 
-fn value_to_json(v: &V) -> Value {
-    match v {
-        V::Bool(b) => json!(*b),
-        V::Unknown => Value::Null,
-    }
+fn stdout_writer(format: Format, with_ast: bool, lines: bool) -> RowWriter<io::StdoutLock<'static>> {
+    RowWriter::new(io::stdout().lock(), format, columns(with_ast, lines))
 }
 
-fn to_ast_string(ast: &impl std::fmt::Debug) -> String {
-    // Use Debug for a stable developer-readable AST print (matches earlier CLI behavior).
-    format!("{ast:?}")
-}
-
-fn emit(out: &Out, pretty: bool) {
-    if pretty {
-        println!("{}", serde_json::to_string_pretty(out).unwrap());
-    } else {
-        println!("{}", serde_json::to_string(out).unwrap());
-    }
-}
-
-fn handle_jsonl(path: &str, global_mem: bool, want_ast: bool, pretty: bool) -> io::Result<()> {
+fn handle_jsonl(
+    path: &str,
+    global_mem: bool,
+    logic: Logic,
+    want_ast: bool,
+    format: Format,
+) -> io::Result<()> {
     let f = File::open(path)?;
     let r = io::BufReader::new(f);
+    let mut out = stdout_writer(format, want_ast, true);
 
     for (lineno, line_res) in r.lines().enumerate() {
         let lineno = lineno + 1;
         let line = match line_res {
             Ok(s) => s,
             Err(e) => {
-                println!("{}", json!({ "line": lineno, "error": format!("io: {e}") }));
+                let row =
+                    Row { line: Some(lineno), error: Some(format!("io: {e}")), ..Row::default() };
+                out.write(&row)?;
                 continue;
             }
         };
@@ -84,122 +58,597 @@ fn handle_jsonl(path: &str, global_mem: bool, want_ast: bool, pretty: bool) -> i
         if line.is_empty() {
             continue;
         }
+        out.write(&eval_jsonl_line(line, lineno, global_mem, logic, want_ast).0)?;
+    }
 
-        // Try to parse JSONL case first.
-        if let Ok(tc) = serde_json::from_str::<JsonlCase>(line) {
-            // Parse Grieg expression
-            match parse_expr(&tc.expr) {
-                Ok(ast) => {
-                    let mem_enabled = tc.mem.unwrap_or(global_mem);
-                    let mut ev = Evaluator::new(mem_enabled);
-                    let res = ev.eval(&ast, None);
-
-                    let phase_str = phase_to_str(&res.phase);
-                    let ok = tc
-                        .expect_phase
-                        .as_ref()
-                        .map(|exp| exp.as_str() == phase_str);
-
-                    let out = Out {
-                        input: Some(&tc.expr),
-                        ast: if want_ast { Some(to_ast_string(&ast)) } else { None },
-                        value: value_to_json(&res.value),
-                        phase: phase_str,
-                        ok,
-                        expect_phase: tc.expect_phase,
-                        note: tc.note,
-                    };
-                    emit(&out, pretty);
-                }
-                Err(e) => {
-                    println!("{}", json!({
-                        "line": lineno,
-                        "input": tc.expr,
-                        "error": format!("parse: {e}")
-                    }));
-                }
-            }
-        } else {
-            // Fallback: treat line as a raw Grieg expression (legacy batch mode).
-            match parse_expr(line) {
-                Ok(ast) => {
-                    let mut ev = Evaluator::new(global_mem);
-                    let res = ev.eval(&ast, None);
-                    let out = Out {
-                        input: Some(line),
-                        ast: if want_ast { Some(to_ast_string(&ast)) } else { None },
-                        value: value_to_json(&res.value),
-                        phase: phase_to_str(&res.phase),
-                        ok: None,
-                        expect_phase: None,
-                        note: None,
-                    };
-                    emit(&out, pretty);
-                }
-                Err(e) => {
-                    println!("{}", json!({
-                        "line": lineno,
-                        "error": format!("parse: {e}")
-                    }));
-                }
+    out.finish()
+}
+
+/// Evaluate one non-empty JSONL line: a `JsonlCase` object, or a raw Grieg expression.
+/// Returns the output row plus a summary record (`None` on errors).
+fn eval_jsonl_line(
+    line: &str,
+    lineno: usize,
+    global_mem: bool,
+    logic: Logic,
+    want_ast: bool,
+) -> (Row, Option<summary::Record>) {
+    let ast_of = |e| if want_ast { Some(to_sexpr(e)) } else { None };
+
+    // Try to parse JSONL case first.
+    if let Ok(tc) = serde_json::from_str::<JsonlCase>(line) {
+        // Parse Grieg expression
+        return match parse_expr(&tc.expr) {
+            Ok(ast) => {
+                let mut ev = tc.evaluator(global_mem, logic);
+                let res = ev.eval(&ast, None);
+
+                let phase_str = phase_to_str(&res.phase);
+                let witnessed = ev.export_mem();
+                let record = summary::Record::analyze(
+                    phase_str,
+                    &ast,
+                    tc.meta.tags.clone(),
+                    tc.meta.rule_id.clone().or_else(|| tc.meta.note.clone()),
+                    |id| witnessed.contains_key(id),
+                    |id| res.conflicts.iter().any(|c| c.ident == id),
+                );
+                let value = grieg_output::value(&res.value);
+                let phase_ok = tc.expect_phase.as_ref().map(|exp| exp.as_str() == phase_str);
+                let value_ok = tc.expect_value.as_ref().map(|exp| *exp == value);
+                let sheet_ok = tc.expect_sheet.as_ref().map(|exp| exp == res.sheet.as_str());
+                let ok = match (phase_ok, value_ok, sheet_ok) {
+                    (None, None, None) => None,
+                    (p, v, s) => Some(p.unwrap_or(true) && v.unwrap_or(true) && s.unwrap_or(true)),
+                };
+
+                let row = Row {
+                    id: tc.meta.id,
+                    line: Some(lineno),
+                    ast: ast_of(&ast),
+                    input: Some(tc.expr),
+                    value,
+                    phase: Some(phase_str.to_string()),
+                    sheet: Some(res.sheet.to_string()),
+                    ok,
+                    expect_phase: tc.expect_phase,
+                    note: tc.meta.note,
+                    rule_id: tc.meta.rule_id,
+                    tags: tc.meta.tags,
+                    error: None,
+                };
+                (row, Some(record))
             }
-        }
+            Err(e) => (
+                Row {
+                    id: tc.meta.id,
+                    line: Some(lineno),
+                    input: Some(tc.expr),
+                    error: Some(format!("parse: {e}")),
+                    ..Row::default()
+                },
+                None,
+            ),
+        };
     }
 
-    Ok(())
+    // Fallback: treat line as a raw Grieg expression (legacy batch mode).
+    let (row, record) = eval_raw(line, global_mem, logic, want_ast);
+    (Row { line: Some(lineno), ..row }, record)
 }
 
-fn eval_single(expr: &str, mem: bool, want_ast: bool, pretty: bool) {
+/// Evaluate a bare expression; parse errors land in the row's `error` column.
+fn eval_raw(expr: &str, mem: bool, logic: Logic, want_ast: bool) -> (Row, Option<summary::Record>) {
+    let mut row = Row { input: Some(expr.to_string()), ..Row::default() };
     match parse_expr(expr) {
         Ok(ast) => {
-            let mut ev = Evaluator::new(mem);
-            let res = ev.eval(&ast, None);
-            let out = Out {
-                input: Some(expr),
-                ast: if want_ast { Some(to_ast_string(&ast)) } else { None },
-                value: value_to_json(&res.value),
-                phase: phase_to_str(&res.phase),
-                ok: None,
-                expect_phase: None,
-                note: None,
-            };
-            emit(&out, pretty);
+            let res = Evaluator::new(mem).with_logic(logic).eval(&ast, None);
+            let phase = phase_to_str(&res.phase);
+            row.ast = want_ast.then(|| to_sexpr(&ast));
+            row.value = grieg_output::value(&res.value);
+            row.phase = Some(phase.to_string());
+            row.sheet = Some(res.sheet.to_string());
+            let record = summary::Record::analyze(
+                phase,
+                &ast,
+                Vec::new(),
+                None,
+                |id| res.witnesses.iter().any(|w| w.ident == id),
+                |id| res.conflicts.iter().any(|c| c.ident == id),
+            );
+            (row, Some(record))
         }
         Err(e) => {
-            eprintln!("Parse error: {e}");
+            row.error = Some(format!("parse: {e}"));
+            (row, None)
         }
     }
 }
 
+fn eval_single(
+    expr: &str,
+    mem: bool,
+    logic: Logic,
+    want_ast: bool,
+    format: Format,
+) -> io::Result<()> {
+    let (row, _) = eval_raw(expr, mem, logic, want_ast);
+    if let Some(e) = &row.error {
+        eprintln!("Parse error: {}", e.trim_start_matches("parse: "));
+        return Ok(());
+    }
+    let mut out = stdout_writer(format, want_ast, false);
+    out.write(&row)?;
+    out.finish()
+}
+
 fn print_help() {
     eprintln!(
         "\
 Grieg CLI
 
 USAGE:
-  grieg-cli --expr '<EXPR>' [--mem] [--ast] [--pretty]
-  grieg-cli --jsonl <FILE> [--mem] [--ast] [--pretty]
+  grieg-cli --expr '<EXPR>' [--mem] [--logic <L>] [--ast] [--format <FMT>]
+  grieg-cli --jsonl <FILE> [--mem] [--logic <L>] [--ast] [--format <FMT>]
+  grieg-cli conformance [PATH...] [--mem] [--logic <L>] [--tag <T>]... [--exclude-tag <T>]...
+                        [--verbose] [--junit <FILE>] [--markdown <FILE>]
+  grieg-cli batch <FILE|-> [--mem] [--logic <L>] [--ast] [--format <FMT>] [--out <FILE>]
+                        [--threads <N>] [--chunk <N>]
+                        [--checkpoint <FILE> [--resume]] [--summary <FILE>] [--top <N>]
+  grieg-cli summarize <FILE|->... [--json] [--top <N>]
+  grieg-cli validate <FILE>... [--schema trace|telemetry|dive|<FILE>] [--json]
+  grieg-cli what-if <RULES> --scenarios <FILE> [--facts <FILE>] [--logic <L>] [--json]
   grieg-cli --help
 
 FLAGS:
   --expr <EXPR>     Evaluate a single Grieg expression
-  --jsonl <FILE>    Evaluate a JSONL file with objects: {{expr, mem?, expect_phase?, note?}}
+  --jsonl <FILE>    Evaluate a JSONL file with objects:
+                    {{expr, mem?, facts?, logic?, expect_phase?, expect_value?, expect_sheet?,
+                    id?, tags?, note?}}
   --mem             Enable MEM transport
-  --ast             Include AST in output
-  --pretty          Pretty-print JSON output
+  --logic <L>       Truth domain: k3 (Kleene, default), l3 (Lukasiewicz: x -> x is true),
+                    four (Belnap: @jam(x) is both true and false, printed as \"both\")
+                    or classical (unknowns are false); a case's own logic wins
+  --ast             Include the AST (S-expression) column
+  --format <FMT>    ndjson (default), pretty, table, csv or yaml
+  --pretty          Same as --format pretty
   --help            Show this help
+
+OUTPUT:
+  Every format carries the same columns for the same flags:
+    --expr:            input, [ast], value, phase, sheet, error
+    --jsonl / stdin:   line, id, input, [ast], value, phase, sheet, ok, expect_phase, note,
+                       rule_id, tags, error
+  Absent values are null (empty cells in csv/table); csv/table join tags with ';'.
+
+CONFORMANCE:
+  PATH              Case file or directory of *.jsonl/*.json files (default: conformance)
+  --tag <T>         Only run cases tagged T (repeatable)
+  --exclude-tag <T> Skip cases tagged T (repeatable)
+  --verbose         Also list passing cases
+  --junit <FILE>    Write a JUnit XML report
+  --markdown <FILE> Write a Markdown report
+  Cases may set skip/xfail; an xfail case that passes counts as a failure.
+  Exits 1 if any case fails or errors.
+
+BATCH:
+  <FILE|->          JSONL input (cases or raw expressions); '-' reads stdin; gzip is detected
+  --out <FILE>      Write results here instead of stdout
+  --format <FMT>    ndjson (default), pretty, csv or yaml (table is not streamable)
+  --threads <N>     Worker threads (default: all cores)
+  --chunk <N>       Lines per parallel chunk / checkpoint interval (default: 8192)
+  --checkpoint <F>  Record the last committed line after every chunk
+  --resume          Continue after the line recorded in --checkpoint
+  --summary <FILE>  Write a phase distribution summary (JSON) and print it as a table to stderr
+  --top <N>         Entries in the top VAC identifier / JAM sub-expression lists (default: 10)
+  Output order matches input order; throughput stats are printed to stderr.
+
+SUMMARIZE:
+  <FILE|->...       Result lines (batch/--jsonl output) or telemetry events to aggregate
+  --json            Print JSON instead of the table
+  --top <N>         Length of the top lists (default: 10)
+  Per phase, tag/channel and rule_id/note; result files carry no witness info, so every
+  identifier of a VAC result is counted as a VAC cause.
+
+VALIDATE:
+  <FILE>...         Trace steps, telemetry events or dive logs: one JSON document, an array
+                    of records, or JSON Lines
+  --schema <S>      Hold every record to docs/trace.schema.json (trace),
+                    docs/telemetry-event.schema.json (telemetry), docs/event-schema-dive.json
+                    (dive) or a schema file; by default each record's keys pick the schema
+  --json            Print the report as JSON
+  Prints file:line (or file[index]), schema, JSON pointer and message per problem; exits 1
+  if any record is invalid.
+
+WHAT-IF:
+  <RULES>           Rulepack: a JSON object of name -> expression, or JSONL cases named by
+                    rule_id (else id, else line number)
+  --scenarios <F>   JSON array of {{name, layers: [{{facts: {{ident: bool}}, hide: [ident]}}]}};
+                    upper layers override or hide facts of lower ones and of --facts
+  --facts <FILE>    Baseline MEM facts: an exported MEM document or an ident -> bool map
+  --json            Print the full report (baseline and every scenario's outcomes) as JSON
+  Lists, per scenario, the rules whose value or phase differs from the baseline.
 "
     );
 }
 
+fn batch_main(args: impl Iterator<Item = String>) -> i32 {
+    let mut opts = batch::Options {
+        input: String::new(),
+        out: None,
+        mem: false,
+        logic: Logic::default(),
+        ast: false,
+        format: Format::Ndjson,
+        threads: 0,
+        chunk: 8192,
+        checkpoint: None,
+        resume: false,
+    };
+    let mut summary_path: Option<PathBuf> = None;
+    let mut top = 10;
+    let mut args = args;
+    while let Some(a) = args.next() {
+        match a.as_str() {
+            "--mem" => opts.mem = true,
+            "--ast" => opts.ast = true,
+            "--resume" => opts.resume = true,
+            "--out" | "--format" | "--logic" | "--threads" | "--chunk" | "--checkpoint"
+            | "--summary" | "--top" => {
+                let Some(v) = args.next() else {
+                    eprintln!("{a} requires a value");
+                    return 2;
+                };
+                match a.as_str() {
+                    "--out" => opts.out = Some(PathBuf::from(v)),
+                    "--format" => match v.parse() {
+                        Ok(f) => opts.format = f,
+                        Err(e) => {
+                            eprintln!("{e}");
+                            return 2;
+                        }
+                    },
+                    "--logic" => match v.parse() {
+                        Ok(l) => opts.logic = l,
+                        Err(e) => {
+                            eprintln!("{e}");
+                            return 2;
+                        }
+                    },
+                    "--checkpoint" => opts.checkpoint = Some(PathBuf::from(v)),
+                    "--summary" => summary_path = Some(PathBuf::from(v)),
+                    _ => {
+                        let Ok(n) = v.parse() else {
+                            eprintln!("{a} expects a number, got '{v}'");
+                            return 2;
+                        };
+                        match a.as_str() {
+                            "--threads" => opts.threads = n,
+                            "--chunk" => opts.chunk = n,
+                            _ => top = n,
+                        }
+                    }
+                }
+            }
+            "--help" | "-h" => {
+                print_help();
+                return 0;
+            }
+            other if other.starts_with("--") => {
+                eprintln!("Unknown argument: {other}");
+                print_help();
+                return 2;
+            }
+            path => opts.input = path.to_string(),
+        }
+    }
+    if opts.input.is_empty() {
+        eprintln!("batch requires an input file (or '-')");
+        return 2;
+    }
+    if opts.resume && opts.checkpoint.is_none() {
+        eprintln!("--resume requires --checkpoint");
+        return 2;
+    }
+    match batch::run(&opts) {
+        Ok((stats, summary)) => {
+            eprintln!("{}", stats.to_json());
+            if let Some(p) = summary_path {
+                let js = serde_json::to_string_pretty(&summary.to_json(top)).unwrap();
+                if let Err(e) = std::fs::write(&p, js) {
+                    eprintln!("cannot write summary {}: {e}", p.display());
+                    return 1;
+                }
+                eprint!("{}", summary.table(top));
+            }
+            0
+        }
+        Err(e) => {
+            eprintln!("batch: {e}");
+            1
+        }
+    }
+}
+
+fn summarize_main(args: impl Iterator<Item = String>) -> i32 {
+    let mut files = Vec::new();
+    let mut as_json = false;
+    let mut top = 10;
+    let mut args = args;
+    while let Some(a) = args.next() {
+        match a.as_str() {
+            "--json" => as_json = true,
+            "--top" => match args.next().map(|v| v.parse()) {
+                Some(Ok(n)) => top = n,
+                _ => {
+                    eprintln!("--top expects a number");
+                    return 2;
+                }
+            },
+            "--help" | "-h" => {
+                print_help();
+                return 0;
+            }
+            other if other.starts_with("--") => {
+                eprintln!("Unknown argument: {other}");
+                print_help();
+                return 2;
+            }
+            path => files.push(path.to_string()),
+        }
+    }
+    if files.is_empty() {
+        eprintln!("summarize requires at least one file (or '-')");
+        return 2;
+    }
+    let mut s = summary::Summary::default();
+    if let Err(e) = summary::summarize_files(&files, &mut s) {
+        eprintln!("summarize: {e}");
+        return 1;
+    }
+    if as_json {
+        println!("{}", serde_json::to_string_pretty(&s.to_json(top)).unwrap());
+    } else {
+        print!("{}", s.table(top));
+    }
+    0
+}
+
+fn validate_main(args: impl Iterator<Item = String>) -> i32 {
+    let mut files = Vec::new();
+    let mut choice = validate::SchemaChoice::Detect;
+    let mut as_json = false;
+    let mut args = args;
+    while let Some(a) = args.next() {
+        match a.as_str() {
+            "--json" => as_json = true,
+            "--schema" => match args.next() {
+                Some(v) if v.ends_with(".json") => choice = validate::SchemaChoice::File(v.into()),
+                Some(v) => match v.parse() {
+                    Ok(k) => choice = validate::SchemaChoice::Builtin(k),
+                    Err(e) => {
+                        eprintln!("{e}");
+                        return 2;
+                    }
+                },
+                None => {
+                    eprintln!("--schema requires a value");
+                    return 2;
+                }
+            },
+            "--help" | "-h" => {
+                print_help();
+                return 0;
+            }
+            other if other.starts_with("--") => {
+                eprintln!("Unknown argument: {other}");
+                print_help();
+                return 2;
+            }
+            path => files.push(PathBuf::from(path)),
+        }
+    }
+    if files.is_empty() {
+        eprintln!("validate requires at least one file");
+        return 2;
+    }
+    let schemas = match validate::Schemas::new(&choice) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("validate: {e}");
+            return 2;
+        }
+    };
+    let mut report = validate::Report::default();
+    for f in &files {
+        if let Err(e) = report.check_file(&schemas, f) {
+            eprintln!("validate: {}: {e}", f.display());
+            return 2;
+        }
+    }
+    if as_json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    } else {
+        for f in &report.findings {
+            println!("{f}");
+        }
+        println!("{} records, {} invalid", report.records, report.invalid);
+    }
+    if report.invalid > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+fn what_if_main(args: impl Iterator<Item = String>) -> i32 {
+    let mut rules = None;
+    let mut scenarios = None;
+    let mut facts = None;
+    let mut logic = Logic::default();
+    let mut as_json = false;
+    let mut args = args;
+    while let Some(a) = args.next() {
+        match a.as_str() {
+            "--json" => as_json = true,
+            "--scenarios" | "--facts" | "--logic" => {
+                let Some(v) = args.next() else {
+                    eprintln!("{a} requires a value");
+                    return 2;
+                };
+                match a.as_str() {
+                    "--scenarios" => scenarios = Some(PathBuf::from(v)),
+                    "--facts" => facts = Some(PathBuf::from(v)),
+                    _ => match v.parse() {
+                        Ok(l) => logic = l,
+                        Err(e) => {
+                            eprintln!("{e}");
+                            return 2;
+                        }
+                    },
+                }
+            }
+            "--help" | "-h" => {
+                print_help();
+                return 0;
+            }
+            other if other.starts_with("--") => {
+                eprintln!("Unknown argument: {other}");
+                print_help();
+                return 2;
+            }
+            path => rules = Some(PathBuf::from(path)),
+        }
+    }
+    let (Some(rules), Some(scenarios)) = (rules, scenarios) else {
+        eprintln!("what-if requires a rules file and --scenarios");
+        return 2;
+    };
+    let load = || -> Result<_, String> {
+        let rules = whatif::load_rules(&rules).map_err(|e| format!("{}: {e}", rules.display()))?;
+        let scenarios = whatif::load_scenarios(&scenarios)
+            .map_err(|e| format!("{}: {e}", scenarios.display()))?;
+        let doc: Option<MemDoc> = match &facts {
+            Some(p) => {
+                let text =
+                    std::fs::read_to_string(p).map_err(|e| format!("{}: {e}", p.display()))?;
+                Some(serde_json::from_str(&text).map_err(|e| format!("{}: {e}", p.display()))?)
+            }
+            None => None,
+        };
+        Ok((rules, scenarios, doc))
+    };
+    let (rules, scenarios, doc) = match load() {
+        Ok(x) => x,
+        Err(e) => {
+            eprintln!("what-if: {e}");
+            return 2;
+        }
+    };
+    let mut ev = Evaluator::new(true).with_logic(logic);
+    if let Some(doc) = doc {
+        ev.import_mem_doc(doc);
+    }
+    let report = ev.what_if(&rules, &scenarios);
+    if as_json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    } else {
+        print!("{}", whatif::table(&report));
+    }
+    0
+}
+
+fn conformance_main(args: impl Iterator<Item = String>) -> i32 {
+    let mut opts = conformance::Options {
+        paths: Vec::new(),
+        mem: false,
+        logic: Logic::default(),
+        tags: Vec::new(),
+        exclude_tags: Vec::new(),
+        verbose: false,
+        junit: None,
+        markdown: None,
+    };
+    let mut args = args;
+    while let Some(a) = args.next() {
+        match a.as_str() {
+            "--mem" => opts.mem = true,
+            "--verbose" | "-v" => opts.verbose = true,
+            "--junit" | "--markdown" | "--tag" | "--exclude-tag" | "--logic" => {
+                let Some(v) = args.next() else {
+                    eprintln!("{a} requires a value");
+                    return 2;
+                };
+                match a.as_str() {
+                    "--junit" => opts.junit = Some(PathBuf::from(v)),
+                    "--markdown" => opts.markdown = Some(PathBuf::from(v)),
+                    "--tag" => opts.tags.push(v),
+                    "--logic" => match v.parse() {
+                        Ok(l) => opts.logic = l,
+                        Err(e) => {
+                            eprintln!("{e}");
+                            return 2;
+                        }
+                    },
+                    _ => opts.exclude_tags.push(v),
+                }
+            }
+            "--help" | "-h" => {
+                print_help();
+                return 0;
+            }
+            other if other.starts_with("--") => {
+                eprintln!("Unknown argument: {other}");
+                print_help();
+                return 2;
+            }
+            path => opts.paths.push(PathBuf::from(path)),
+        }
+    }
+    if opts.paths.is_empty() {
+        opts.paths.push(PathBuf::from("conformance"));
+    }
+    match conformance::run(&opts) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("conformance: {e}");
+            2
+        }
+    }
+}
+
 fn main() {
     // Minimal arg parsing (no extra deps).
-    let mut args = env::args().skip(1);
+    let mut args = env::args().skip(1).peekable();
+    match args.peek().map(String::as_str) {
+        Some("conformance") => {
+            args.next();
+            std::process::exit(conformance_main(args));
+        }
+        Some("batch") => {
+            args.next();
+            std::process::exit(batch_main(args));
+        }
+        Some("summarize") => {
+            args.next();
+            std::process::exit(summarize_main(args));
+        }
+        Some("validate") => {
+            args.next();
+            std::process::exit(validate_main(args));
+        }
+        Some("what-if") => {
+            args.next();
+            std::process::exit(what_if_main(args));
+        }
+        _ => {}
+    }
     let mut expr_opt: Option<String> = None;
     let mut jsonl_opt: Option<String> = None;
     let mut mem_flag = false;
+    let mut logic = Logic::default();
     let mut ast_flag = false;
-    let mut pretty_flag = false;
+    let mut format = Format::Ndjson;
 
     while let Some(a) = args.next() {
         match a.as_str() {
@@ -219,9 +668,31 @@ fn main() {
                     std::process::exit(2);
                 }
             }
+            "--format" => match args.next().map(|f| f.parse()) {
+                Some(Ok(f)) => format = f,
+                Some(Err(e)) => {
+                    eprintln!("{e}");
+                    std::process::exit(2);
+                }
+                None => {
+                    eprintln!("--format requires a value");
+                    std::process::exit(2);
+                }
+            },
+            "--logic" => match args.next().map(|l| l.parse()) {
+                Some(Ok(l)) => logic = l,
+                Some(Err(e)) => {
+                    eprintln!("{e}");
+                    std::process::exit(2);
+                }
+                None => {
+                    eprintln!("--logic requires a value");
+                    std::process::exit(2);
+                }
+            },
             "--mem" => mem_flag = true,
             "--ast" => ast_flag = true,
-            "--pretty" => pretty_flag = true,
+            "--pretty" => format = Format::Pretty,
             "--help" | "-h" => {
                 print_help();
                 return;
@@ -234,52 +705,40 @@ fn main() {
         }
     }
 
-    if let Some(jsonl_path) = jsonl_opt {
-        if let Err(e) = handle_jsonl(&jsonl_path, mem_flag, ast_flag, pretty_flag) {
-            eprintln!("cannot open file: {e}");
-            std::process::exit(1);
+    let res = if let Some(jsonl_path) = jsonl_opt {
+        handle_jsonl(&jsonl_path, mem_flag, logic, ast_flag, format)
+            .map_err(|e| eprintln!("{jsonl_path}: {e}"))
+    } else if let Some(expr) = expr_opt {
+        eval_single(&expr, mem_flag, logic, ast_flag, format).map_err(|e| eprintln!("output: {e}"))
+    } else {
+        // If no --expr/--jsonl, read from stdin (each line an expression).
+        let mut input = String::new();
+        if io::stdin().read_to_string(&mut input).is_err() || input.trim().is_empty() {
+            print_help();
+            return;
         }
-        return;
-    }
-
-    if let Some(expr) = expr_opt {
-        eval_single(&expr, mem_flag, ast_flag, pretty_flag);
-        return;
+        eval_lines(&input, mem_flag, logic, ast_flag, format).map_err(|e| eprintln!("output: {e}"))
+    };
+    if res.is_err() {
+        std::process::exit(1);
     }
+}
 
-    // If no --expr/--jsonl, read from stdin (each line an expression).
-    let mut input = String::new();
-    if io::stdin().read_to_string(&mut input).is_ok() && !input.trim().is_empty() {
-        for (lineno, line) in input.lines().enumerate() {
-            let line = line.trim();
-            if line.is_empty() {
-                continue;
-            }
-            match parse_expr(line) {
-                Ok(ast) => {
-                    let mut ev = Evaluator::new(mem_flag);
-                    let res = ev.eval(&ast, None);
-                    let out = Out {
-                        input: Some(line),
-                        ast: if ast_flag { Some(to_ast_string(&ast)) } else { None },
-                        value: value_to_json(&res.value),
-                        phase: phase_to_str(&res.phase),
-                        ok: None,
-                        expect_phase: None,
-                        note: None,
-                    };
-                    emit(&out, pretty_flag);
-                }
-                Err(e) => {
-                    println!("{}", json!({
-                        "line": lineno + 1,
-                        "error": format!("parse: {e}")
-                    }));
-                }
-            }
+fn eval_lines(
+    input: &str,
+    mem: bool,
+    logic: Logic,
+    want_ast: bool,
+    format: Format,
+) -> io::Result<()> {
+    let mut out = stdout_writer(format, want_ast, true);
+    for (lineno, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
         }
-        return;
+        let (row, _) = eval_raw(line, mem, logic, want_ast);
+        out.write(&Row { line: Some(lineno + 1), ..row })?;
     }
-
-    print_help();
+    out.finish()
 }
@@ -0,0 +1,362 @@
+//! Phase distribution summaries over batch runs.
+//!
+//! A `Summary` aggregates one `Record` per evaluated line: phase counts (overall, per tag and
+//! per rule), the identifiers that most often leave results in VAC, and the `@jam(..)`
+//! sub-expressions and conflicting identifiers that most often produce JAM.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use serde_json::{json, Map, Value};
+
+use grieg_engine::ast::{to_sexpr, Expr, PhaseOp};
+use grieg_parser::parse_expr;
+
+const PHASES: [&str; 4] = ["ALIVE", "JAM", "MEM", "VAC"];
+
+/// What the summary needs to know about one evaluated line.
+#[derive(Debug, Default)]
+pub struct Record {
+    pub phase: String,
+    pub tags: Vec<String>,
+    /// Grouping key: `rule_id`, else `note`.
+    pub rule: Option<String>,
+    /// Unwitnessed identifiers of a VAC result.
+    pub vac_idents: Vec<String>,
+    /// S-expressions of the `@jam(..)` nodes and conflicting identifiers of a JAM result.
+    pub jam_exprs: Vec<String>,
+}
+
+impl Record {
+    /// Build a record, deriving VAC/JAM causes from the AST.
+    /// `witnessed` tells whether an identifier had a MEM fact during evaluation, `conflicted`
+    /// whether its witnesses disagreed.
+    pub fn analyze(
+        phase: &str,
+        expr: &Expr,
+        tags: Vec<String>,
+        rule: Option<String>,
+        witnessed: impl Fn(&str) -> bool,
+        conflicted: impl Fn(&str) -> bool,
+    ) -> Self {
+        let mut r = Record { phase: phase.to_string(), tags, rule, ..Default::default() };
+        match phase {
+            "VAC" => collect_idents(expr, &mut |id| {
+                if !witnessed(id) && !r.vac_idents.iter().any(|v| v == id) {
+                    r.vac_idents.push(id.to_string());
+                }
+            }),
+            "JAM" => {
+                collect_jams(expr, &mut r.jam_exprs);
+                collect_idents(expr, &mut |id| {
+                    let ast = to_sexpr(&Expr::Ident(id.to_string()));
+                    if conflicted(id) && !r.jam_exprs.contains(&ast) {
+                        r.jam_exprs.push(ast);
+                    }
+                });
+            }
+            _ => {}
+        }
+        r
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counts {
+    total: u64,
+    phases: [u64; 4],
+}
+
+impl Counts {
+    fn add(&mut self, phase: &str) {
+        self.total += 1;
+        if let Some(i) = PHASES.iter().position(|p| *p == phase) {
+            self.phases[i] += 1;
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let mut phases = Map::new();
+        for (i, p) in PHASES.iter().enumerate() {
+            phases.insert(
+                p.to_string(),
+                json!({ "count": self.phases[i], "pct": pct(self.phases[i], self.total) }),
+            );
+        }
+        json!({ "total": self.total, "phases": phases })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Summary {
+    overall: Counts,
+    errors: u64,
+    by_tag: BTreeMap<String, Counts>,
+    by_rule: BTreeMap<String, Counts>,
+    vac_idents: HashMap<String, u64>,
+    jam_exprs: HashMap<String, u64>,
+}
+
+impl Summary {
+    pub fn add(&mut self, r: &Record) {
+        self.overall.add(&r.phase);
+        for t in &r.tags {
+            self.by_tag.entry(t.clone()).or_default().add(&r.phase);
+        }
+        if let Some(rule) = &r.rule {
+            self.by_rule.entry(rule.clone()).or_default().add(&r.phase);
+        }
+        for id in &r.vac_idents {
+            *self.vac_idents.entry(id.clone()).or_default() += 1;
+        }
+        for e in &r.jam_exprs {
+            *self.jam_exprs.entry(e.clone()).or_default() += 1;
+        }
+    }
+
+    /// A line that could not be evaluated (bad JSON, parse error).
+    pub fn add_error(&mut self) {
+        self.errors += 1;
+    }
+
+    pub fn to_json(&self, top: usize) -> Value {
+        let group = |m: &BTreeMap<String, Counts>| -> Map<String, Value> {
+            m.iter().map(|(k, c)| (k.clone(), c.to_json())).collect()
+        };
+        let mut j = self.overall.to_json();
+        let o = j.as_object_mut().unwrap();
+        o.insert("errors".into(), json!(self.errors));
+        o.insert("tags".into(), Value::Object(group(&self.by_tag)));
+        o.insert("rules".into(), Value::Object(group(&self.by_rule)));
+        o.insert(
+            "top_vac_identifiers".into(),
+            ranked(&self.vac_idents, top)
+                .into_iter()
+                .map(|(k, n)| json!({ "ident": k, "count": n }))
+                .collect(),
+        );
+        o.insert(
+            "top_jam_subexpressions".into(),
+            ranked(&self.jam_exprs, top)
+                .into_iter()
+                .map(|(k, n)| json!({ "ast": k, "count": n }))
+                .collect(),
+        );
+        j
+    }
+
+    /// Aligned plain-text rendering for terminals.
+    pub fn table(&self, top: usize) -> String {
+        let mut s = String::new();
+        let _ = writeln!(s, "evaluated: {}   errors: {}", self.overall.total, self.errors);
+        let _ = writeln!(s);
+        table_block(&mut s, "phase mix", [("(all)".to_string(), &self.overall)]);
+        if !self.by_tag.is_empty() {
+            table_block(&mut s, "by tag", self.by_tag.iter().map(|(k, c)| (k.clone(), c)));
+        }
+        if !self.by_rule.is_empty() {
+            table_block(&mut s, "by rule", self.by_rule.iter().map(|(k, c)| (k.clone(), c)));
+        }
+        ranked_block(&mut s, "top VAC identifiers", &ranked(&self.vac_idents, top));
+        ranked_block(&mut s, "top JAM sub-expressions", &ranked(&self.jam_exprs, top));
+        s
+    }
+}
+
+/// Summarize existing result lines (batch/`--jsonl` output or telemetry events).
+///
+/// Records need `phase` and `input` (or `expr`); `tags`/`channel` and `rule_id`/`note` are
+/// optional. `witnesses` and `conflicts` (as in `grieg-http` responses) name the identifiers
+/// that had a fact or a conflict; without them every identifier of a VAC result counts as a
+/// VAC cause and only `@jam(..)` as a JAM cause.
+pub fn summarize_files(paths: &[String], summary: &mut Summary) -> io::Result<()> {
+    for p in paths {
+        let r: Box<dyn BufRead> = if p == "-" {
+            Box::new(io::stdin().lock())
+        } else {
+            Box::new(BufReader::new(File::open(Path::new(p))?))
+        };
+        for line in r.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match record_from_result(&line) {
+                Some(rec) => summary.add(&rec),
+                None => summary.add_error(),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn record_from_result(line: &str) -> Option<Record> {
+    let v: Value = serde_json::from_str(line).ok()?;
+    let phase = v.get("phase")?.as_str()?;
+    let mut tags: Vec<String> = v
+        .get("tags")
+        .and_then(Value::as_array)
+        .map(|a| a.iter().filter_map(|t| t.as_str().map(String::from)).collect())
+        .unwrap_or_default();
+    if let Some(ch) = v.get("channel").and_then(Value::as_str) {
+        tags.push(ch.to_string());
+    }
+    let rule = ["rule_id", "note"]
+        .iter()
+        .find_map(|k| v.get(*k).and_then(Value::as_str))
+        .map(String::from);
+    let idents = |key: &str| -> Vec<String> {
+        v.get(key)
+            .and_then(Value::as_array)
+            .map(|a| a.iter().filter_map(|x| x["ident"].as_str().map(String::from)).collect())
+            .unwrap_or_default()
+    };
+    let (witnessed, conflicted) = (idents("witnesses"), idents("conflicts"));
+    let text = v.get("input").or_else(|| v.get("expr")).and_then(Value::as_str);
+    match text.map(parse_expr) {
+        Some(Ok(e)) => Some(Record::analyze(
+            phase,
+            &e,
+            tags,
+            rule,
+            |id| witnessed.iter().chain(&conflicted).any(|w| w == id),
+            |id| conflicted.iter().any(|c| c == id),
+        )),
+        _ => Some(Record { phase: phase.to_string(), tags, rule, ..Default::default() }),
+    }
+}
+
+fn collect_idents(e: &Expr, f: &mut impl FnMut(&str)) {
+    match e {
+        Expr::Bool(_) => {}
+        Expr::Ident(s) => f(s),
+        Expr::Not(x) => collect_idents(x, f),
+        Expr::And(a, b) | Expr::Or(a, b) | Expr::Imp(a, b) => {
+            collect_idents(a, f);
+            collect_idents(b, f);
+        }
+        // `@vac(x)` yields VAC by construction; its body is not a missing witness.
+        Expr::PhaseOp(PhaseOp::Vac, _) => {}
+        Expr::PhaseOp(_, x) => collect_idents(x, f),
+    }
+}
+
+fn collect_jams(e: &Expr, out: &mut Vec<String>) {
+    match e {
+        Expr::Bool(_) | Expr::Ident(_) => {}
+        Expr::Not(x) => collect_jams(x, out),
+        Expr::And(a, b) | Expr::Or(a, b) | Expr::Imp(a, b) => {
+            collect_jams(a, out);
+            collect_jams(b, out);
+        }
+        Expr::PhaseOp(PhaseOp::Jam, _) => out.push(to_sexpr(e)),
+        Expr::PhaseOp(_, x) => collect_jams(x, out),
+    }
+}
+
+fn pct(n: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        (n as f64 * 10_000.0 / total as f64).round() / 100.0
+    }
+}
+
+/// Most frequent first; ties broken by key for stable output.
+fn ranked(m: &HashMap<String, u64>, top: usize) -> Vec<(&str, u64)> {
+    let mut v: Vec<(&str, u64)> = m.iter().map(|(k, n)| (k.as_str(), *n)).collect();
+    v.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    v.truncate(top);
+    v
+}
+
+fn table_block<'a>(
+    s: &mut String,
+    title: &str,
+    rows: impl IntoIterator<Item = (String, &'a Counts)>,
+) {
+    let rows: Vec<_> = rows.into_iter().collect();
+    let w = rows.iter().map(|(k, _)| k.chars().count()).max().unwrap_or(0).max(title.len());
+    let _ = write!(s, "{title:<w$}  {:>9}", "total");
+    for p in PHASES {
+        let _ = write!(s, "  {p:>15}");
+    }
+    s.push('\n');
+    for (k, c) in rows {
+        let _ = write!(s, "{k:<w$}  {:>9}", c.total);
+        for (i, _) in PHASES.iter().enumerate() {
+            let cell = format!("{} ({:.1}%)", c.phases[i], pct(c.phases[i], c.total));
+            let _ = write!(s, "  {cell:>15}");
+        }
+        s.push('\n');
+    }
+    s.push('\n');
+}
+
+fn ranked_block(s: &mut String, title: &str, rows: &[(&str, u64)]) {
+    if rows.is_empty() {
+        return;
+    }
+    let _ = writeln!(s, "{title}");
+    for (k, n) in rows {
+        let _ = writeln!(s, "  {n:>9}  {k}");
+    }
+    s.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_phases_tags_and_causes() {
+        let mut s = Summary::default();
+        let e = parse_expr("a -> b").unwrap();
+        s.add(&Record::analyze("VAC", &e, vec!["sap".into()], None, |id| id == "a", |_| false));
+        let e = parse_expr("@jam(x) | y").unwrap();
+        let rule = Some("R1".into());
+        s.add(&Record::analyze("JAM", &e, vec!["sap".into()], rule, |_| false, |_| false));
+        let e = parse_expr("true").unwrap();
+        s.add(&Record::analyze("ALIVE", &e, vec![], None, |_| false, |_| false));
+        s.add_error();
+
+        let j = s.to_json(5);
+        assert_eq!(j["total"], 3);
+        assert_eq!(j["errors"], 1);
+        assert_eq!(j["phases"]["VAC"]["pct"], 33.33);
+        assert_eq!(j["tags"]["sap"]["total"], 2);
+        assert_eq!(j["rules"]["R1"]["phases"]["JAM"]["count"], 1);
+        assert_eq!(j["top_vac_identifiers"], json!([{ "ident": "b", "count": 1 }]));
+        assert_eq!(j["top_jam_subexpressions"][0]["ast"], "(@jam id:x)");
+    }
+
+    #[test]
+    fn reads_result_and_telemetry_lines() {
+        let r = record_from_result(r#"{"input":"a & b","value":null,"phase":"VAC","note":"n"}"#)
+            .unwrap();
+        assert_eq!((r.rule.as_deref(), r.vac_idents.len()), (Some("n"), 2));
+        let r = record_from_result(r#"{"expr":"@jam(true)","phase":"JAM","channel":"ipc.v0"}"#)
+            .unwrap();
+        assert_eq!(r.tags, ["ipc.v0"]);
+        assert_eq!(r.jam_exprs, ["(@jam true)"]);
+    }
+
+    #[test]
+    fn conflicts_are_jam_causes_and_witnesses_are_not_vac_causes() {
+        let r = record_from_result(
+            r#"{"input":"~paid & due","phase":"JAM","value":null,
+                "conflicts":[{"ident":"paid","claims":[]}]}"#,
+        )
+        .unwrap();
+        assert_eq!(r.jam_exprs, ["id:paid"]);
+        let r = record_from_result(
+            r#"{"input":"paid & due","phase":"VAC","value":null,
+                "witnesses":[{"ident":"paid","value":true,"source":"erp"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(r.vac_idents, ["due"]);
+    }
+}
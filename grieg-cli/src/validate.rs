@@ -0,0 +1,376 @@
+//! Checks trace steps, telemetry events and dive event logs against the JSON Schemas published
+//! in `docs/`, which other-language consumers code against.
+//!
+//! A file is either one JSON document (an object, or an array of records) or JSON Lines. Each
+//! record is validated against the schema named with `--schema`, or the one its shape suggests.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use jsonschema::Validator;
+use serde::Serialize;
+use serde_json::Value;
+
+pub const TRACE_SCHEMA: &str = include_str!("../../docs/trace.schema.json");
+pub const TELEMETRY_SCHEMA: &str = include_str!("../../docs/telemetry-event.schema.json");
+pub const DIVE_SCHEMA: &str = include_str!("../../docs/event-schema-dive.json");
+
+/// Which contract a record is held to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    Trace,
+    Telemetry,
+    Dive,
+}
+
+impl Kind {
+    /// Guess from the record's keys: `op`/`pre`/`post`, `ts`, or `meta`/`events`.
+    pub fn detect(v: &Value) -> Option<Kind> {
+        let o = v.as_object()?;
+        if o.contains_key("op") && o.contains_key("pre") {
+            Some(Kind::Trace)
+        } else if o.contains_key("meta") || o.contains_key("events") {
+            Some(Kind::Dive)
+        } else if o.contains_key("ts") {
+            Some(Kind::Telemetry)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Kind::Trace => "trace",
+            Kind::Telemetry => "telemetry",
+            Kind::Dive => "dive",
+        })
+    }
+}
+
+impl FromStr for Kind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "trace" => Ok(Kind::Trace),
+            "telemetry" => Ok(Kind::Telemetry),
+            "dive" => Ok(Kind::Dive),
+            _ => Err(format!("unknown schema {s:?} (trace, telemetry, dive or a .json file)")),
+        }
+    }
+}
+
+/// `--schema`: a built-in contract or a schema file.
+pub enum SchemaChoice {
+    Detect,
+    Builtin(Kind),
+    File(PathBuf),
+}
+
+fn compile(text: &str, name: &str) -> Result<Validator, String> {
+    let schema: Value = serde_json::from_str(text).map_err(|e| format!("{name}: {e}"))?;
+    jsonschema::options()
+        .should_validate_formats(true)
+        .build(&schema)
+        .map_err(|e| format!("{name}: {e}"))
+}
+
+pub struct Schemas {
+    trace: Validator,
+    telemetry: Validator,
+    dive: Validator,
+    custom: Option<Validator>,
+    forced: Option<Kind>,
+}
+
+impl Schemas {
+    pub fn new(choice: &SchemaChoice) -> Result<Self, String> {
+        let custom = match choice {
+            SchemaChoice::File(p) => {
+                let text = fs::read_to_string(p).map_err(|e| format!("{}: {e}", p.display()))?;
+                Some(compile(&text, &p.display().to_string())?)
+            }
+            _ => None,
+        };
+        Ok(Self {
+            trace: compile(TRACE_SCHEMA, "trace.schema.json")?,
+            telemetry: compile(TELEMETRY_SCHEMA, "telemetry-event.schema.json")?,
+            dive: compile(DIVE_SCHEMA, "event-schema-dive.json")?,
+            custom,
+            forced: match choice {
+                SchemaChoice::Builtin(k) => Some(*k),
+                _ => None,
+            },
+        })
+    }
+
+    fn builtin(&self, kind: Kind) -> &Validator {
+        match kind {
+            Kind::Trace => &self.trace,
+            Kind::Telemetry => &self.telemetry,
+            Kind::Dive => &self.dive,
+        }
+    }
+
+    /// Validation errors of one record, as `(schema, JSON pointer, message)`.
+    pub fn check(&self, v: &Value) -> Result<(String, Vec<(String, String)>), String> {
+        let (name, validator) = match (&self.custom, self.forced.or_else(|| Kind::detect(v))) {
+            (Some(c), _) => ("custom".to_string(), c),
+            (None, Some(k)) => (k.to_string(), self.builtin(k)),
+            (None, None) => {
+                return Err("unrecognised record: expected a trace step (op, pre, post), a \
+                            telemetry event (ts) or a dive log (meta, events)"
+                    .into())
+            }
+        };
+        let errors = validator
+            .iter_errors(v)
+            .map(|e| (e.instance_path.to_string(), e.to_string()))
+            .collect();
+        Ok((name, errors))
+    }
+}
+
+/// One problem with one record.
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub file: String,
+    /// 1-based line of a JSON Lines file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// Index into a top-level array.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+    /// JSON pointer to the offending field ("" for the record itself).
+    pub pointer: String,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.file)?;
+        if let Some(l) = self.line {
+            write!(f, ":{l}")?;
+        }
+        if let Some(i) = self.index {
+            write!(f, "[{i}]")?;
+        }
+        if let Some(s) = &self.schema {
+            write!(f, ": {s}")?;
+        }
+        let at = if self.pointer.is_empty() { "(record)" } else { &self.pointer };
+        write!(f, " {at}: {}", self.message)
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Report {
+    pub records: usize,
+    /// Records with at least one finding.
+    pub invalid: usize,
+    pub findings: Vec<Finding>,
+}
+
+impl Report {
+    fn record(
+        &mut self,
+        schemas: &Schemas,
+        file: &str,
+        at: (Option<usize>, Option<usize>),
+        v: &Value,
+    ) {
+        self.records += 1;
+        let (line, index) = at;
+        let finding = |schema: Option<String>, pointer: String, message: String| Finding {
+            file: file.to_string(),
+            line,
+            index,
+            schema,
+            pointer,
+            message,
+        };
+        match schemas.check(v) {
+            Ok((_, errors)) if errors.is_empty() => {}
+            Ok((name, errors)) => {
+                self.invalid += 1;
+                for (pointer, message) in errors {
+                    self.findings.push(finding(Some(name.clone()), pointer, message));
+                }
+            }
+            Err(message) => {
+                self.invalid += 1;
+                self.findings.push(finding(None, String::new(), message));
+            }
+        }
+    }
+
+    /// Validate every record of `path`: JSON Lines for `.jsonl`/`.ndjson` or anything that is
+    /// not one JSON document, else that document (one record, or an array of them).
+    pub fn check_file(&mut self, schemas: &Schemas, path: &Path) -> std::io::Result<()> {
+        let text = fs::read_to_string(path)?;
+        let file = path.display().to_string();
+        let lines = matches!(path.extension().and_then(|e| e.to_str()), Some("jsonl" | "ndjson"));
+        let doc = if lines { None } else { serde_json::from_str::<Value>(&text).ok() };
+        match doc {
+            Some(Value::Array(items)) => {
+                for (i, v) in items.iter().enumerate() {
+                    self.record(schemas, &file, (None, Some(i)), v);
+                }
+            }
+            Some(v) => self.record(schemas, &file, (None, None), &v),
+            None => {
+                for (i, line) in text.lines().enumerate() {
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    match serde_json::from_str::<Value>(line) {
+                        Ok(v) => self.record(schemas, &file, (Some(i + 1), None), &v),
+                        Err(e) => {
+                            self.records += 1;
+                            self.invalid += 1;
+                            self.findings.push(Finding {
+                                file: file.clone(),
+                                line: Some(i + 1),
+                                index: None,
+                                schema: None,
+                                pointer: String::new(),
+                                message: format!("not JSON: {e}"),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use grieg_engine::phase::Phase;
+    use grieg_engine::trace::TraceStep;
+    use grieg_engine::Evaluator;
+    use grieg_parser::parse_expr;
+    use grieg_telemetry::{Checkpoint, PulseRecord, TelemetryEvent, WitnessRecord};
+
+    fn schemas() -> Schemas {
+        Schemas::new(&SchemaChoice::Detect).unwrap()
+    }
+
+    fn errors(kind: Kind, v: &Value) -> Vec<(String, String)> {
+        let s = Schemas::new(&SchemaChoice::Builtin(kind)).unwrap();
+        s.check(v).unwrap().1
+    }
+
+    #[test]
+    fn rust_trace_steps_match_the_trace_schema() {
+        let e = parse_expr("@mem(a -> ~b) & @vac(c)").unwrap();
+        let (_, steps) = Evaluator::new(true).eval_traced(&e);
+        for s in &steps {
+            let v = serde_json::to_value(s).unwrap();
+            assert_eq!(errors(Kind::Trace, &v), [], "{v}");
+        }
+        // Every optional field the type can carry.
+        let full = TraceStep {
+            op: "implies",
+            pre: Phase::ALIVE,
+            post: Phase::JAM,
+            sink: true,
+            sheet: Some("C"),
+            theta: Some(0.5),
+            rho: Some(1.0),
+            note: Some("n".into()),
+        };
+        assert_eq!(errors(Kind::Trace, &serde_json::to_value(full).unwrap()), []);
+    }
+
+    #[test]
+    fn rust_telemetry_events_match_the_telemetry_schema() {
+        let pulse = |kind: &str| PulseRecord {
+            kind: kind.into(),
+            step: 3,
+            op: "implies".into(),
+            phase: "ALIVE".into(),
+            chain: Some(0),
+            winding: Some(1),
+            ident: Some("a".into()),
+        };
+        let full = TelemetryEvent {
+            expr: Some("a -> b".into()),
+            ast: Some("(-> id:a id:b)".into()),
+            phase: Some("VAC".into()),
+            logic: Some("four".into()),
+            value_text: Some("Unknown".into()),
+            value_bool: Some(true),
+            amplitude: Some(0.25),
+            pulses: vec![pulse("SINK"), pulse("WITNESS")],
+            witnesses: vec![WitnessRecord {
+                ident: "a".into(),
+                value: true,
+                source: "sensor".into(),
+                asserted_at: TelemetryEvent::new().ts,
+                expires_at: Some(TelemetryEvent::new().ts),
+                confidence: 0.5,
+                expired: true,
+            }],
+            seq: Some(7),
+            prev: Some(grieg_telemetry::GENESIS.into()),
+            checkpoint: Some(Checkpoint { key: "ab".repeat(32), signature: "cd".repeat(64) }),
+            ..TelemetryEvent::new()
+        };
+        for ev in [TelemetryEvent::new(), full] {
+            let v = serde_json::to_value(&ev).unwrap();
+            assert_eq!(Kind::detect(&v), Some(Kind::Telemetry));
+            assert_eq!(errors(Kind::Telemetry, &v), [], "{v}");
+        }
+    }
+
+    #[test]
+    fn reports_where_records_break_the_contract() {
+        let dir = std::env::temp_dir().join(format!("grieg-validate-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("mixed.jsonl");
+        let good = serde_json::to_string(&TelemetryEvent::new()).unwrap();
+        let lines = [
+            good.as_str(),
+            r#"{"op":"not","pre":"ALIVE","post":"DEAD"}"#,
+            r#"{"ts":"2025-01-01T00:00:00Z","colour":"red"}"#,
+            r#"{"hello":1}"#,
+            "{oops",
+        ];
+        fs::write(&path, lines.join("\n")).unwrap();
+
+        let mut r = Report::default();
+        r.check_file(&schemas(), &path).unwrap();
+        assert_eq!((r.records, r.invalid), (5, 4));
+        let at: Vec<(Option<usize>, Option<&str>, &str)> =
+            r.findings.iter().map(|f| (f.line, f.schema.as_deref(), f.pointer.as_str())).collect();
+        assert_eq!(
+            at,
+            [
+                (Some(2), Some("trace"), "/post"),
+                (Some(3), Some("telemetry"), ""),
+                (Some(4), None, ""),
+                (Some(5), None, ""),
+            ]
+        );
+        assert!(r.findings[1].message.contains("colour"));
+
+        let dive = dir.join("dive.json");
+        fs::write(&dive, r#"{"meta":{"dive_id":"d1","rulepack_id":"r"},"events":[]}"#).unwrap();
+        let mut r = Report::default();
+        r.check_file(&schemas(), &dive).unwrap();
+        let shown = r.findings[0].to_string();
+        assert_eq!(shown, format!("{}: dive /meta: {}", dive.display(), r.findings[0].message));
+        assert!(shown.ends_with("\"generated_at\" is a required property"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
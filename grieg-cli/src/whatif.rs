@@ -0,0 +1,114 @@
+//! `grieg-cli what-if`: a rulepack under the baseline facts and under named scenarios.
+//!
+//! Rules are a JSON object of rule name -> expression, or JSONL cases (named by `rule_id`,
+//! else `id`, else line number). Scenarios are a JSON array of
+//! `{"name": .., "layers": [{"facts": {..}, "hide": [..]}, ..]}`, bottom layer first.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use grieg_engine::ast::Expr;
+use grieg_engine::scenario::{Outcome, Scenario, WhatIfReport};
+use grieg_engine::value::V;
+use grieg_parser::parse_expr;
+use serde_json::{Map, Value};
+
+use crate::case::JsonlCase;
+use crate::phase_to_str;
+
+pub fn load_rules(path: &Path) -> Result<Vec<(String, Expr)>, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let named: Vec<(String, String)> = match serde_json::from_str::<Map<String, Value>>(&text) {
+        Ok(map) if !map.contains_key("expr") => map
+            .into_iter()
+            .map(|(name, e)| match e {
+                Value::String(e) => Ok((name, e)),
+                _ => Err(format!("rule {name:?}: expected an expression string")),
+            })
+            .collect::<Result<_, _>>()?,
+        _ => text
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty())
+            .map(|(i, l)| {
+                let c: JsonlCase =
+                    serde_json::from_str(l).map_err(|e| format!("line {}: {e}", i + 1))?;
+                let name = c.meta.rule_id.or(c.meta.id).unwrap_or_else(|| (i + 1).to_string());
+                Ok((name, c.expr))
+            })
+            .collect::<Result<_, String>>()?,
+    };
+    named
+        .into_iter()
+        .map(|(name, e)| match parse_expr(&e) {
+            Ok(expr) => Ok((name, expr)),
+            Err(err) => Err(format!("rule {name:?}: parse: {err}")),
+        })
+        .collect()
+}
+
+pub fn load_scenarios(path: &Path) -> Result<Vec<Scenario>, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&text).map_err(|e| e.to_string())
+}
+
+fn outcome(o: &Outcome) -> String {
+    let v = match o.value {
+        V::Bool(b) => b.to_string(),
+        V::Unknown => "null".to_string(),
+        V::Both => "both".to_string(),
+    };
+    format!("{v} {} {}", phase_to_str(&o.phase), o.sheet)
+}
+
+/// One block per scenario listing the rules that changed, baseline -> scenario, each as
+/// value, phase and sheet.
+pub fn table(r: &WhatIfReport) -> String {
+    let mut s = String::new();
+    let w = r.rules.iter().map(|n| n.chars().count()).max().unwrap_or(0);
+    for sc in &r.scenarios {
+        let _ = writeln!(s, "{}: {} of {} rules changed", sc.name, sc.changes.len(), r.rules.len());
+        for d in &sc.changes {
+            let (b, o) = (outcome(&d.baseline), outcome(&d.scenario));
+            let _ = writeln!(s, "  {:<w$}  {b:<13} -> {o}", d.rule);
+        }
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use grieg_engine::mem::Fact;
+    use grieg_engine::Evaluator;
+
+    #[test]
+    fn reports_rules_a_scenario_changes() {
+        let dir = std::env::temp_dir().join(format!("grieg-whatif-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (rules, cases, scenarios) =
+            (dir.join("rules.json"), dir.join("rules.jsonl"), dir.join("scenarios.json"));
+        fs::write(&rules, r#"{"release": "paid & ~hold", "audit": "hold -> paid"}"#).unwrap();
+        fs::write(&cases, "{\"expr\": \"paid\", \"rule_id\": \"p\"}\n\n{\"expr\": \"hold\"}\n")
+            .unwrap();
+        let doc = r#"[{"name": "credit-hold", "layers": [{"facts": {"hold": true}}]},
+                      {"name": "unpaid", "layers": [{"hide": ["paid"]}]}]"#;
+        fs::write(&scenarios, doc).unwrap();
+
+        let names: Vec<String> = load_rules(&cases).unwrap().into_iter().map(|r| r.0).collect();
+        assert_eq!(names, ["p", "3"]);
+        let mut ev = Evaluator::new(true);
+        ev.assert_mem(Fact::new("erp", "paid", true));
+        ev.assert_mem(Fact::new("erp", "hold", false));
+        let r = ev.what_if(&load_rules(&rules).unwrap(), &load_scenarios(&scenarios).unwrap());
+        assert_eq!(
+            table(&r),
+            "credit-hold: 1 of 2 rules changed\n  release  true MEM F    -> false MEM C\n\
+             unpaid: 2 of 2 rules changed\n  release  true MEM F    -> null VAC C\n  \
+             audit    true MEM F    -> true ALIVE F\n"
+        );
+        assert!(load_rules(&scenarios).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -17,6 +17,22 @@ pub enum Expr {
     PhaseOp(PhaseOp, Box<Expr>),
 }
 
+/// Trace/telemetry name of the node's operator.
+pub fn op_name(e: &Expr) -> &'static str {
+    match e {
+        Expr::Bool(_) => "bool",
+        Expr::Ident(_) => "ident",
+        Expr::Not(_) => "not",
+        Expr::And(..) => "and",
+        Expr::Or(..) => "or",
+        Expr::Imp(..) => "implies",
+        Expr::PhaseOp(PhaseOp::Mem, _) => "@mem",
+        Expr::PhaseOp(PhaseOp::Jam, _) => "@jam",
+        Expr::PhaseOp(PhaseOp::Vac, _) => "@vac",
+        Expr::PhaseOp(PhaseOp::Alive, _) => "@alive",
+    }
+}
+
 pub fn to_sexpr(e: &Expr) -> String {
     use Expr::*;
     match e {
@@ -1,9 +1,20 @@
 //! Grieg evaluation (SpecRef: S3.*, S4.*)
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Utc};
 
 use crate::ast::{Expr, PhaseOp};
-use crate::phase::Phase;
+use crate::logic::{Classical, Four, Logic, TruthDomain, K3, L3};
+use crate::mem::{
+    AsOf, Conflict, ConsistencyReport, Fact, MemDoc, MemStore, Overlay, Record, Transaction,
+    Witness, DEFAULT_SOURCE,
+};
+use crate::phase::{Phase, Sheet};
+#[cfg(feature = "pulse")]
+use crate::pulse::{Pulse, Recorder};
+#[cfg(feature = "emit_geometry")]
+use crate::trace::TraceStep;
 use crate::value::V;
 
 /// Result of evaluating an expression.
@@ -11,15 +22,83 @@ use crate::value::V;
 pub struct EvalResult {
     pub value: V,
     pub phase: Phase,
+    /// Identifiers in the expression whose MEM sources disagree, in evaluation order.
+    pub conflicts: Vec<Conflict>,
+    /// MEM facts that resolved identifiers in the expression, in evaluation order.
+    pub witnesses: Vec<Fact>,
+    /// MEM facts that would have, had they been valid (expired, or not yet in force); their
+    /// identifiers were VAC.
+    pub expired: Vec<Fact>,
+    /// The sheet the result lies on: F when it follows from witnessed facts, C when it rests
+    /// on a missing witness or a what-if layer.
+    pub sheet: Sheet,
+    /// This `->` step is at or after the sink of its implication chain (SpecRef: I2). The sink
+    /// is the first link in evaluation order (innermost first) where modus ponens fires on F;
+    /// once a chain has sunk, every later link stays sunk.
+    pub sink: bool,
+    /// This `->` step is the sink itself: the link where its chain first became sunk.
+    pub first_sink: bool,
+    /// Sinks on each implication chain of the expression, chains in source order. A chain is
+    /// a maximal run of `->` linked through consequents: `a -> b -> c` is one chain.
+    pub sinks: Vec<usize>,
 }
 
-/// Evaluator with optional MEM semantics and a tiny persistent store.
-/// NOTE: `mem_store` is ONLY for persistence (--mem-db). We don't write to it during eval yet.
+impl EvalResult {
+    fn new(value: V, phase: Phase) -> Self {
+        Self {
+            value,
+            phase,
+            conflicts: Vec::new(),
+            witnesses: Vec::new(),
+            expired: Vec::new(),
+            sheet: Sheet::F,
+            sink: false,
+            first_sink: false,
+            sinks: Vec::new(),
+        }
+    }
+
+    /// The same result on `sheet`.
+    fn on(self, sheet: Sheet) -> Self {
+        Self { sheet, ..self }
+    }
+
+    /// Carry the operands' conflicts and provenance up to their parent.
+    fn inherit(mut self, operands: impl IntoIterator<Item = EvalResult>) -> Self {
+        for r in operands {
+            self.conflicts.extend(r.conflicts);
+            self.witnesses.extend(r.witnesses);
+            self.expired.extend(r.expired);
+            self.sinks.extend(r.sinks);
+        }
+        self
+    }
+}
+
+/// Evaluator with optional MEM semantics over a bitemporal fact store.
+///
+/// Evaluation only reads the store: identifiers resolve to the facts valid at `at` and known
+/// at `known_at`, through any what-if layers. Writes go through `assert_mem`/`retract_*` or a
+/// `Transaction` (`begin`/`commit`), which stamps its changes with one transaction time. A
+/// `snapshot` is a cheap copy that later writes on either side do not reach; the store's
+/// history also round-trips through `export_mem_doc`/`import_mem_doc` (`--mem-db`).
+#[derive(Clone)]
 pub struct Evaluator {
     /// Enable MEM semantics (SpecRef: S3.10).
     pub mem_enabled: bool,
-    /// Persistent MEM facts (identifier -> bool). Used by CLI load/save.
-    mem_store: HashMap<String, bool>,
+    /// How values compose (SpecRef: S3.3–S3.6); Kleene K3 unless set.
+    pub logic: Logic,
+    /// Valid time MEM facts are judged at (expiry); the wall clock when `None`.
+    pub at: Option<DateTime<Utc>>,
+    /// Transaction time: evaluate against the MEM store as it stood then; as it stands now
+    /// when `None`.
+    pub known_at: Option<DateTime<Utc>>,
+    /// MEM facts, per source, with their history.
+    mem_store: MemStore,
+    /// What-if layers over `mem_store`, bottom first (`Evaluator::under`).
+    pub(crate) overlays: Vec<Overlay>,
+    /// `at`/`known_at`, fixed when an evaluation starts.
+    view: AsOf,
 }
 
 impl Evaluator {
@@ -27,29 +106,176 @@ impl Evaluator {
     pub fn new(mem_enabled: bool) -> Self {
         Self {
             mem_enabled,
-            mem_store: HashMap::new(),
+            logic: Logic::default(),
+            at: None,
+            known_at: None,
+            mem_store: MemStore::default(),
+            overlays: Vec::new(),
+            view: AsOf::valid(Utc::now()),
         }
     }
 
+    /// Evaluate under `logic` instead of K3.
+    pub fn with_logic(mut self, logic: Logic) -> Self {
+        self.logic = logic;
+        self
+    }
+
+    /// Judge MEM facts as of `at` instead of the wall clock.
+    pub fn at(mut self, at: DateTime<Utc>) -> Self {
+        self.at = Some(at);
+        self
+    }
+
+    /// Evaluate against the MEM store as it stood at `known_at` instead of now.
+    pub fn known_at(mut self, known_at: DateTime<Utc>) -> Self {
+        self.known_at = Some(known_at);
+        self
+    }
+
+    fn as_of(&self) -> AsOf {
+        AsOf { valid: self.at.unwrap_or_else(Utc::now), known: self.known_at }
+    }
+
     // ----------------------------------------------------------------
     // Persistence hooks (used by grieg-cli --mem-db).
     // These do not change eval rules; they only make free idents resolvable.
     // ----------------------------------------------------------------
 
-    /// SpecRef: S3.10 — import external MEM facts for persistence.
+    /// SpecRef: S3.10 — import external MEM facts for persistence (as `DEFAULT_SOURCE`).
     pub fn import_mem(&mut self, map: HashMap<String, bool>) {
+        self.import_mem_from(DEFAULT_SOURCE, map);
+    }
+
+    /// SpecRef: S3.10 — import MEM facts asserted by `source`. They replace that source's
+    /// earlier claims; a different value from another source makes the identifier JAM.
+    pub fn import_mem_from(&mut self, source: &str, map: HashMap<String, bool>) {
+        if !self.mem_enabled {
+            return;
+        }
+        self.mem_store.extend(source, map, Utc::now());
+    }
+
+    /// SpecRef: S3.10 — record one MEM fact with its provenance and expiry.
+    pub fn assert_mem(&mut self, fact: Fact) {
         if !self.mem_enabled {
             return;
         }
-        self.mem_store.extend(map);
+        self.mem_store.insert(fact, Utc::now());
+    }
+
+    /// SpecRef: S3.10 — load a document written by `export_mem_doc` (or a plain map),
+    /// history included.
+    pub fn import_mem_doc(&mut self, doc: MemDoc) {
+        if !self.mem_enabled {
+            return;
+        }
+        for record in doc.facts {
+            self.mem_store.restore(record);
+        }
+    }
+
+    /// SpecRef: S3.10 — retract MEM facts from every source; retracted identifiers resolve
+    /// to VAC again (their history is kept).
+    pub fn retract_mem<'a>(&mut self, names: impl IntoIterator<Item = &'a str>) {
+        let tx = Utc::now();
+        for n in names {
+            self.mem_store.retract(n, tx);
+        }
+    }
+
+    /// SpecRef: S3.10 — retract `source`'s claims only.
+    pub fn retract_mem_from<'a>(&mut self, source: &str, names: impl IntoIterator<Item = &'a str>) {
+        let tx = Utc::now();
+        for n in names {
+            self.mem_store.retract_from(source, n, tx);
+        }
+    }
+
+    /// SpecRef: S3.10 — retract everything `source` asserted.
+    pub fn retract_source(&mut self, source: &str) {
+        self.mem_store.retract_source(source, Utc::now());
+    }
+
+    /// Retract every MEM fact.
+    pub fn clear_mem(&mut self) {
+        self.mem_store.clear(Utc::now());
+    }
+
+    /// Retract MEM facts that have expired; returns how many.
+    pub fn retract_expired(&mut self) -> usize {
+        self.mem_store.retract_expired(self.as_of().valid, Utc::now())
+    }
+
+    /// Start a batch of MEM changes. Nothing in it is visible until `commit`; drop it, or call
+    /// `rollback`, to abandon it.
+    pub fn begin(&self) -> Transaction {
+        self.mem_store.begin()
+    }
+
+    /// SpecRef: S3.10 — apply a batch of MEM changes at once, all stamped with the same
+    /// transaction time. Returns how many changes were applied.
+    pub fn commit(&mut self, tx: Transaction) -> usize {
+        if !self.mem_enabled {
+            return 0;
+        }
+        tx.commit(&mut self.mem_store, Utc::now())
     }
 
-    /// SpecRef: S3.10 — export current MEM facts for persistence.
+    /// An evaluator over the MEM store as it is now. It shares the store's structure, so this
+    /// is cheap; later changes to either side are not seen by the other. Hand it to readers
+    /// that should not wait for, or see half of, the next write.
+    pub fn snapshot(&self) -> Evaluator {
+        self.clone()
+    }
+
+    /// Forget MEM history retracted at or before `horizon`; evaluations known at or after it
+    /// are unaffected. Returns how many records were dropped.
+    pub fn compact_mem(&mut self, horizon: DateTime<Utc>) -> usize {
+        self.mem_store.compact(horizon)
+    }
+
+    /// Every assertion and retraction of `name`, oldest first.
+    pub fn mem_history(&self, name: &str) -> Vec<Record> {
+        self.mem_store.history(name).to_vec()
+    }
+
+    /// Current MEM facts with their provenance, expired ones included, sorted by identifier
+    /// and source.
+    pub fn mem_facts(&self) -> Vec<Fact> {
+        let mut facts: Vec<Fact> = self.mem_store.current().cloned().collect();
+        facts.sort_by(|a, b| (&a.ident, &a.source).cmp(&(&b.ident, &b.source)));
+        facts
+    }
+
+    /// SpecRef: S3.10 — export current MEM facts for persistence. Identifiers whose sources
+    /// disagree, and expired facts, are left out; see `export_mem_doc` and `consistency`.
     pub fn export_mem(&self) -> HashMap<String, bool> {
         if !self.mem_enabled {
             return HashMap::new();
         }
-        self.mem_store.clone()
+        self.mem_store.agreed(self.as_of())
+    }
+
+    /// The MEM store with its history, in the versioned format.
+    pub fn export_mem_doc(&self) -> MemDoc {
+        if !self.mem_enabled {
+            return MemDoc::new(Vec::new());
+        }
+        self.mem_store.to_doc()
+    }
+
+    /// Valid MEM facts as source -> identifier -> value.
+    pub fn export_mem_by_source(&self) -> BTreeMap<String, BTreeMap<String, bool>> {
+        if !self.mem_enabled {
+            return BTreeMap::new();
+        }
+        self.mem_store.by_source(self.as_of())
+    }
+
+    /// Every contradiction between valid MEM claims.
+    pub fn consistency(&self) -> ConsistencyReport {
+        self.mem_store.report(self.as_of())
     }
 
     // ----------------------------------------------------------------
@@ -58,127 +284,247 @@ impl Evaluator {
 
     /// Evaluate an expression. Second arg kept to match existing call sites.
     pub fn eval(&mut self, e: &Expr, _unused: Option<&mut ()>) -> EvalResult {
+        self.eval_logic(e, &mut |_, _, _| {})
+    }
+
+    /// Evaluate as of `t` in both valid and transaction time: what the expression came to
+    /// at `t`, with the MEM facts the store held then.
+    pub fn eval_as_of(&mut self, e: &Expr, t: DateTime<Utc>) -> EvalResult {
+        let saved = (self.at.replace(t), self.known_at.replace(t));
+        let r = self.eval(e, None);
+        (self.at, self.known_at) = saved;
+        r
+    }
+
+    /// Evaluate in a truth domain fixed at compile time, whatever `self.logic` says.
+    pub fn eval_in<D: TruthDomain>(&mut self, e: &Expr) -> EvalResult {
+        self.view = self.as_of();
+        self.eval_node::<D>(e, Sheet::F, &mut |_, _, _| {})
+    }
+
+    /// Evaluate and record one `TraceStep` per AST node, children before parents.
+    /// Outcomes are identical to `eval`.
+    #[cfg(feature = "emit_geometry")]
+    pub fn eval_traced(&mut self, e: &Expr) -> (EvalResult, Vec<TraceStep>) {
+        let mut steps = Vec::new();
+        let r = self.eval_logic(e, &mut |node, pre, r| steps.push(TraceStep::new(node, pre, r)));
+        (r, steps)
+    }
+
+    /// Evaluate and derive the invariant pulse events (see `pulse`). Outcomes are identical
+    /// to `eval`.
+    #[cfg(feature = "pulse")]
+    pub fn eval_pulsed(&mut self, e: &Expr) -> (EvalResult, Vec<Pulse>) {
+        let mut rec = Recorder::new(e);
+        let r = self.eval_logic(e, &mut |node, pre, r| rec.step(node, pre, r));
+        (r, rec.pulses)
+    }
+
+    /// `eval_traced` and `eval_pulsed` in one pass.
+    #[cfg(all(feature = "pulse", feature = "emit_geometry"))]
+    pub fn eval_traced_pulsed(&mut self, e: &Expr) -> (EvalResult, Vec<TraceStep>, Vec<Pulse>) {
+        let (mut steps, mut rec) = (Vec::new(), Recorder::new(e));
+        let r = self.eval_logic(e, &mut |node, pre, r| {
+            steps.push(TraceStep::new(node, pre, r));
+            rec.step(node, pre, r);
+        });
+        (r, steps, rec.pulses)
+    }
+
+    /// `eval_node` in the domain `self.logic` names.
+    fn eval_logic(
+        &mut self,
+        e: &Expr,
+        step: &mut dyn FnMut(&Expr, Phase, &EvalResult),
+    ) -> EvalResult {
+        self.view = self.as_of();
+        match self.logic {
+            Logic::K3 => self.eval_node::<K3>(e, Sheet::F, step),
+            Logic::L3 => self.eval_node::<L3>(e, Sheet::F, step),
+            Logic::Four => self.eval_node::<Four>(e, Sheet::F, step),
+            Logic::Classical => self.eval_node::<Classical>(e, Sheet::F, step),
+        }
+    }
+
+    /// Big-step evaluation; `step(node, pre, result)` runs after each node, where `pre` is
+    /// the phase the node's rule started from (its operands' join, or its own for leaves).
+    /// `on` is the sheet the node is evaluated on.
+    fn eval_node<D: TruthDomain>(
+        &mut self,
+        e: &Expr,
+        on: Sheet,
+        step: &mut dyn FnMut(&Expr, Phase, &EvalResult),
+    ) -> EvalResult {
+        let (pre, r) = self.eval_rule::<D>(e, on, step);
+        step(e, pre, &r);
+        r
+    }
+
+    fn eval_rule<D: TruthDomain>(
+        &mut self,
+        e: &Expr,
+        on: Sheet,
+        step: &mut dyn FnMut(&Expr, Phase, &EvalResult),
+    ) -> (Phase, EvalResult) {
         match e {
-            Expr::Bool(b) => EvalResult {
-                value: V::Bool(*b),
-                phase: Phase::ALIVE, // S3.1
-            },
+            Expr::Bool(b) => {
+                let r = EvalResult::new(V::Bool(*b), Phase::ALIVE); // S3.1
+                (Phase::ALIVE, r.on(on))
+            }
 
             Expr::Ident(name) => {
-                // S3.2: free identifiers → Unknown,VAC unless a MEM fact exists
-                if let Some(b) = self.mem_store.get(name) {
-                    EvalResult {
-                        value: V::Bool(*b),
-                        phase: Phase::MEM,
+                // S3.2: free identifiers → Unknown,VAC unless a valid MEM fact exists. Only
+                // the store witnesses on F; what-if layers and missing witnesses lie on C.
+                let (witness, from) =
+                    self.mem_store.lookup_through(&self.overlays, name, self.view);
+                let sheet = if from == Sheet::C { Sheet::C } else { on };
+                match witness {
+                    Witness::Fact(witnesses) => {
+                        let r = EvalResult::new(V::Bool(witnesses[0].value), Phase::MEM);
+                        (Phase::MEM, EvalResult { witnesses, ..r }.on(sheet))
+                    }
+                    Witness::Absent => {
+                        (Phase::VAC, EvalResult::new(D::gap(), Phase::VAC).on(Sheet::C))
                     }
-                } else {
-                    EvalResult {
-                        value: V::Unknown,
-                        phase: Phase::VAC,
+                    // A stale witness witnesses nothing.
+                    Witness::Expired(expired) => (
+                        Phase::VAC,
+                        EvalResult { expired, ..EvalResult::new(D::gap(), Phase::VAC) }
+                            .on(Sheet::C),
+                    ),
+                    // Witnessed, but contradictorily: a boundary, as if `@jam(..)` of a gap.
+                    Witness::Conflict(c) => {
+                        let r = EvalResult::new(D::jam(D::gap()), Phase::JAM);
+                        (Phase::MEM, EvalResult { conflicts: vec![c], ..r }.on(sheet))
                     }
                 }
             }
 
             Expr::Not(x) => {
-                let r = self.eval(x, None);
-                let v = match r.value.to_bool() {
-                    Some(true) => V::Bool(false),
-                    Some(false) => V::Bool(true),
-                    None => V::Unknown,
-                };
-                let phase = if v.to_bool().is_none() {
-                    Phase::VAC
-                } else {
-                    r.phase
-                }; // S4.6
-                EvalResult { value: v, phase }
+                let r = self.eval_node::<D>(x, on, step);
+                let v = D::not(r.value.clone());
+                let phase = settle(&v, r.phase, &[&r]); // S4.6
+                let sheet = sheet_of(&v, phase, on, &[&r], |v| D::not(v[0].clone()));
+                (r.phase, EvalResult::new(v, phase).on(sheet).inherit([r]))
             }
 
             Expr::And(a, b) => {
-                let ra = self.eval(a, None);
-                let rb = self.eval(b, None);
-                let v = and3(ra.value, rb.value); // S3.4
-                let mut phase = join(ra.phase, rb.phase); // S4.3
-                if v.to_bool().is_none() {
-                    phase = Phase::VAC;
-                } // S4.6
-                EvalResult { value: v, phase }
+                let ra = self.eval_node::<D>(a, on, step);
+                let rb = self.eval_node::<D>(b, on, step);
+                let v = D::and(ra.value.clone(), rb.value.clone()); // S3.4
+                let pre = join(ra.phase, rb.phase); // S4.3
+                let phase = settle(&v, pre, &[&ra, &rb]); // S4.6
+                let sheet =
+                    sheet_of(&v, phase, on, &[&ra, &rb], |v| D::and(v[0].clone(), v[1].clone()));
+                (pre, EvalResult::new(v, phase).on(sheet).inherit([ra, rb]))
             }
 
             Expr::Or(a, b) => {
-                let ra = self.eval(a, None);
-                let rb = self.eval(b, None);
-                let v = or3(ra.value, rb.value); // S3.5
-                let mut phase = join(ra.phase, rb.phase);
-                if v.to_bool().is_none() {
-                    phase = Phase::VAC;
-                }
-                EvalResult { value: v, phase }
+                let ra = self.eval_node::<D>(a, on, step);
+                let rb = self.eval_node::<D>(b, on, step);
+                let v = D::or(ra.value.clone(), rb.value.clone()); // S3.5
+                let pre = join(ra.phase, rb.phase);
+                let phase = settle(&v, pre, &[&ra, &rb]);
+                let sheet =
+                    sheet_of(&v, phase, on, &[&ra, &rb], |v| D::or(v[0].clone(), v[1].clone()));
+                (pre, EvalResult::new(v, phase).on(sheet).inherit([ra, rb]))
             }
 
             Expr::Imp(a, b) => {
-                let ra = self.eval(a, None);
-                let rb = self.eval(b, None);
-                let v = imp3(ra.value, rb.value); // S3.6
-                let mut phase = join(ra.phase, rb.phase);
-                if v.to_bool().is_none() {
-                    phase = Phase::VAC;
-                }
-                EvalResult { value: v, phase }
+                let ra = self.eval_node::<D>(a, on, step);
+                let mut rb = self.eval_node::<D>(b, on, step);
+                let v = D::imp(ra.value.clone(), rb.value.clone()); // S3.6
+                let pre = join(ra.phase, rb.phase);
+                let phase = settle(&v, pre, &[&ra, &rb]);
+                let sheet =
+                    sheet_of(&v, phase, on, &[&ra, &rb], |v| D::imp(v[0].clone(), v[1].clone()));
+                // I2: a chain continues through its consequent and sinks once, at the first
+                // modus-ponens link on F in evaluation order; every later link stays sunk.
+                let (below, below_sunk) = match **b {
+                    Expr::Imp(..) => (rb.sinks.remove(0), rb.sink),
+                    _ => (0, false),
+                };
+                let modus_ponens = ra.value == V::Bool(true) && rb.value.to_bool().is_some();
+                let first_sink = modus_ponens && sheet == Sheet::F && !below_sunk;
+                let r = EvalResult::new(v, phase).on(sheet);
+                let sinks = vec![below + usize::from(first_sink)];
+                let r = EvalResult { sink: first_sink || below_sunk, first_sink, sinks, ..r };
+                (pre, r.inherit([ra, rb]))
             }
 
             Expr::PhaseOp(op, x) => {
-                let r = self.eval(x, None);
-                match op {
-                    PhaseOp::Alive => EvalResult {
-                        value: r.value,
-                        phase: Phase::ALIVE,
-                    }, // S3.7, S4.4
-                    PhaseOp::Jam => EvalResult {
-                        value: r.value,
-                        phase: Phase::JAM,
-                    }, // S3.8, S4.4
-                    PhaseOp::Vac => EvalResult {
-                        value: V::Unknown,
-                        phase: Phase::VAC,
-                    }, // S3.9
-                    PhaseOp::Mem => EvalResult {
-                        value: r.value,
-                        phase: Phase::MEM,
-                    }, // S3.10
-                }
+                // `@mem` evaluates its operand on the other sheet and transports the result
+                // back; the other markers leave the sheet alone, and `@vac` projects to C.
+                let inner = if *op == PhaseOp::Mem { on.flip() } else { on };
+                let r = self.eval_node::<D>(x, inner, step);
+                let pre = r.phase;
+                let value = r.value.clone();
+                let out = match op {
+                    PhaseOp::Alive => EvalResult::new(value, Phase::ALIVE), // S3.7, S4.4
+                    PhaseOp::Jam => EvalResult::new(D::jam(value), Phase::JAM), // S3.8, S4.4
+                    PhaseOp::Vac => EvalResult::new(D::gap(), Phase::VAC), // S3.9
+                    PhaseOp::Mem => EvalResult::new(value, Phase::MEM), // S3.10
+                };
+                let sheet = match op {
+                    PhaseOp::Vac => Sheet::C,
+                    // No witness is transported: an unknown stays on C.
+                    _ if out.value == V::Unknown => Sheet::C,
+                    PhaseOp::Mem => r.sheet.flip(),
+                    PhaseOp::Alive | PhaseOp::Jam => r.sheet,
+                };
+                (pre, out.on(sheet).inherit([r]))
             }
         }
     }
 }
 
 // ----------------------------------------------------------------
-// Truth-functions with Unknown lifting (S3.3–S3.6)
+// Sheet of a connective's result (SpecRef: sheets F:C)
 // ----------------------------------------------------------------
 
-fn and3(a: V, b: V) -> V {
-    match (a.to_bool(), b.to_bool()) {
-        (Some(false), _) | (_, Some(false)) => V::Bool(false),
-        (Some(true), Some(true)) => V::Bool(true),
-        _ => V::Unknown,
+/// A connective's result lies on C when it is VAC or unknown, or when some operand on C
+/// could change it: `f` gives another value for some boolean in place of those operands.
+/// Otherwise it stays on the sheet `on` it was evaluated on.
+fn sheet_of(
+    v: &V,
+    phase: Phase,
+    on: Sheet,
+    operands: &[&EvalResult],
+    f: impl Fn(&[V]) -> V,
+) -> Sheet {
+    if phase == Phase::VAC || *v == V::Unknown {
+        return Sheet::C;
     }
-}
-
-fn or3(a: V, b: V) -> V {
-    match (a.to_bool(), b.to_bool()) {
-        (Some(true), _) | (_, Some(true)) => V::Bool(true),
-        (Some(false), Some(false)) => V::Bool(false),
-        _ => V::Unknown,
+    let holds = (0..1u32 << operands.len()).all(|bits| {
+        let args: Vec<V> = operands
+            .iter()
+            .enumerate()
+            .map(|(i, r)| match r.sheet {
+                Sheet::F => r.value.clone(),
+                Sheet::C => V::Bool(bits >> i & 1 == 1),
+            })
+            .collect();
+        f(&args) == *v
+    });
+    if holds {
+        on
+    } else {
+        Sheet::C
     }
 }
 
-fn imp3(a: V, b: V) -> V {
-    match (a.to_bool(), b.to_bool()) {
-        (Some(false), _) => V::Bool(true),
-        (Some(true), Some(v)) => V::Bool(v),
-        (Some(true), None) => V::Unknown,
-        (None, Some(true)) => V::Bool(true),
-        _ => V::Unknown,
+// ----------------------------------------------------------------
+// Phase of a connective's result (S4.6)
+// ----------------------------------------------------------------
+
+/// Unknown settles to VAC and Both to JAM; a boolean keeps the operands' phase. A result
+/// that rests on conflicting witnesses stays JAM: a contradiction is never a mere gap.
+fn settle(v: &V, pre: Phase, operands: &[&EvalResult]) -> Phase {
+    match v {
+        V::Bool(_) => pre,
+        V::Unknown if operands.iter().any(|r| !r.conflicts.is_empty()) => Phase::JAM,
+        V::Unknown => Phase::VAC,
+        V::Both => Phase::JAM,
     }
 }
 
@@ -1,4 +1,8 @@
+use std::collections::HashSet;
+use std::rc::Rc;
+
 use super::ast::L;
+use crate::phase::Phase;
 
 pub struct LamResult {
     pub term: L,
@@ -6,7 +10,195 @@ pub struct LamResult {
     pub diverged: bool,
 }
 
-/// Leftmost-outermost β-reduction skeleton with fuel (TODO: implement)
-pub fn eval_normal_order(t: L, _fuel: usize) -> LamResult {
-    LamResult { term: t, steps: 0, diverged: false }
+impl LamResult {
+    /// Normal form reached → ALIVE; fuel exhausted → JAM (divergence is a boundary).
+    pub fn phase(&self) -> Phase {
+        if self.diverged {
+            Phase::JAM
+        } else {
+            Phase::ALIVE
+        }
+    }
+}
+
+/// Leftmost-outermost β-reduction with fuel.
+/// `fuel` bounds the number of β-steps; running out marks the result as diverged.
+pub fn eval_normal_order(t: L, fuel: usize) -> LamResult {
+    let mut term = from_l(&t, &mut Vec::new());
+    let mut steps = 0;
+    loop {
+        match step(&term) {
+            None => break,
+            Some(_) if steps == fuel => {
+                return LamResult { term: to_l(&term, &mut Vec::new()), steps, diverged: true }
+            }
+            Some(next) => {
+                term = next;
+                steps += 1;
+            }
+        }
+    }
+    LamResult { term: to_l(&term, &mut Vec::new()), steps, diverged: false }
+}
+
+/// Decode a Church numeral `λf.λx. f (f (… x))`, if the term is one.
+pub fn church_numeral(t: &L) -> Option<u64> {
+    let L::Lam(f, body) = t else { return None };
+    let L::Lam(x, inner) = &**body else { return None };
+    if f == x {
+        return None;
+    }
+    let mut cur: &L = inner;
+    let mut n = 0;
+    loop {
+        match cur {
+            L::Var(v) if v == x => return Some(n),
+            L::App(g, rest) if matches!(&**g, L::Var(v) if v == f) => {
+                n += 1;
+                cur = rest;
+            }
+            _ => return None,
+        }
+    }
+}
+
+// Reduction runs on a nameless, shared copy of the term: bound variables are de Bruijn
+// indices, children are `Rc`s so a β-step rebuilds only the path to its redex and shares the
+// argument instead of copying it, and each node caches whether it is closed above some depth
+// and whether it is already normal, so substitution and the redex search skip those subterms.
+
+type Term = Rc<Node>;
+
+struct Node {
+    t: T,
+    /// One more than the largest index that escapes the node (0 when closed).
+    loose: usize,
+    /// No redex anywhere below.
+    normal: bool,
+}
+
+enum T {
+    Bound(usize),
+    Free(String),
+    /// The binder keeps its source name for printing.
+    Lam(String, Term),
+    App(Term, Term),
+}
+
+fn bound(i: usize) -> Term {
+    Rc::new(Node { t: T::Bound(i), loose: i + 1, normal: true })
+}
+
+fn lam(x: String, b: Term) -> Term {
+    let (loose, normal) = (b.loose.saturating_sub(1), b.normal);
+    Rc::new(Node { t: T::Lam(x, b), loose, normal })
+}
+
+fn app(f: Term, a: Term) -> Term {
+    let loose = f.loose.max(a.loose);
+    let normal = !matches!(f.t, T::Lam(..)) && f.normal && a.normal;
+    Rc::new(Node { t: T::App(f, a), loose, normal })
+}
+
+fn from_l(t: &L, scope: &mut Vec<String>) -> Term {
+    match t {
+        L::Var(v) => match scope.iter().rev().position(|x| x == v) {
+            Some(i) => bound(i),
+            None => Rc::new(Node { t: T::Free(v.clone()), loose: 0, normal: true }),
+        },
+        L::Lam(x, b) => {
+            scope.push(x.clone());
+            let b = from_l(b, scope);
+            scope.pop();
+            lam(x.clone(), b)
+        }
+        L::App(f, a) => app(from_l(f, scope), from_l(a, scope)),
+    }
+}
+
+/// Back to named form; a binder whose name would capture a variable from outside is renamed.
+fn to_l(t: &Term, scope: &mut Vec<String>) -> L {
+    match &t.t {
+        T::Bound(i) => L::Var(scope[scope.len() - 1 - i].clone()),
+        T::Free(v) => L::Var(v.clone()),
+        T::Lam(x, b) => {
+            let mut outer = HashSet::new();
+            outer_names(b, 1, scope, &mut outer);
+            let x = if outer.contains(x) { fresh(x, &outer) } else { x.clone() };
+            scope.push(x.clone());
+            let b = to_l(b, scope);
+            scope.pop();
+            L::Lam(x, Box::new(b))
+        }
+        T::App(f, a) => L::App(Box::new(to_l(f, scope)), Box::new(to_l(a, scope))),
+    }
+}
+
+/// Names of the variables `t` refers to outside its `depth` innermost binders.
+fn outer_names(t: &Term, depth: usize, scope: &[String], out: &mut HashSet<String>) {
+    match &t.t {
+        T::Bound(i) if *i >= depth => {
+            out.insert(scope[scope.len() - 1 - (i - depth)].clone());
+        }
+        T::Bound(_) => {}
+        T::Free(v) => {
+            out.insert(v.clone());
+        }
+        T::Lam(_, b) => outer_names(b, depth + 1, scope, out),
+        T::App(f, a) => {
+            outer_names(f, depth, scope, out);
+            outer_names(a, depth, scope, out);
+        }
+    }
+}
+
+/// One leftmost-outermost β-step, or `None` if `t` is in normal form.
+fn step(t: &Term) -> Option<Term> {
+    if t.normal {
+        return None;
+    }
+    match &t.t {
+        T::Bound(_) | T::Free(_) => None,
+        T::Lam(x, b) => step(b).map(|b2| lam(x.clone(), b2)),
+        T::App(f, a) => {
+            if let T::Lam(_, body) = &f.t {
+                return Some(subst(body, 0, a));
+            }
+            if !f.normal {
+                return step(f).map(|f2| app(f2, a.clone()));
+            }
+            step(a).map(|a2| app(f.clone(), a2))
+        }
+    }
+}
+
+/// `t[depth := s]`, for the body of a binder `depth` levels up; indices above it drop by one.
+fn subst(t: &Term, depth: usize, s: &Term) -> Term {
+    if t.loose <= depth {
+        return t.clone();
+    }
+    match &t.t {
+        T::Bound(i) if *i == depth => shift(s, depth, 0),
+        T::Bound(i) => bound(i - 1),
+        T::Free(_) => t.clone(),
+        T::Lam(x, b) => lam(x.clone(), subst(b, depth + 1, s)),
+        T::App(f, a) => app(subst(f, depth, s), subst(a, depth, s)),
+    }
+}
+
+/// Raise the indices of `t` that escape `cutoff` binders by `d`.
+fn shift(t: &Term, d: usize, cutoff: usize) -> Term {
+    if d == 0 || t.loose <= cutoff {
+        return t.clone();
+    }
+    match &t.t {
+        T::Bound(i) => bound(i + d),
+        T::Free(_) => t.clone(),
+        T::Lam(x, b) => lam(x.clone(), shift(b, d, cutoff + 1)),
+        T::App(f, a) => app(shift(f, d, cutoff), shift(a, d, cutoff)),
+    }
+}
+
+fn fresh(base: &str, avoid: &HashSet<String>) -> String {
+    (1..).map(|i| format!("{base}{i}")).find(|c| !avoid.contains(c)).unwrap()
 }
@@ -1,17 +1,24 @@
 pub mod eval;
 pub mod phase;
 pub mod ast;
+pub mod logic;
+pub mod mem;
+pub mod scenario;
 pub mod value;
 
 #[cfg(feature = "emit_geometry")]
 pub mod trace;
 
+#[cfg(feature = "pulse")]
+pub mod pulse;
+
 #[cfg(feature = "lam")]
 pub mod lam;
 
 // Public facade (stable API)
 pub use crate::eval::Evaluator;
-pub use crate::phase::Phase;          // if you have a Phase enum module
+pub use crate::logic::{Logic, TruthDomain};
+pub use crate::phase::{Phase, Sheet};
 pub use crate::ast::Expr;             // if Expr lives in engine::ast and is useful to expose
 
 
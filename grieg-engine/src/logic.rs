@@ -0,0 +1,276 @@
+//! Truth domains: how values compose under the connectives (SpecRef: S3.3–S3.6).
+//!
+//! The evaluator is generic over [`TruthDomain`]; [`Logic`] picks one at run time. Phases
+//! are unaffected except that a result of [`V::Both`] settles to JAM, as Unknown settles
+//! to VAC (S4.6).
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::value::V;
+
+/// Truth-functions of one logic. Operands are whatever the domain itself produces, so
+/// `V::Both` only ever reaches [`Four`].
+pub trait TruthDomain {
+    /// Name used by `Logic`'s `Display`/`FromStr`.
+    const NAME: &'static str;
+
+    /// Value of a truth-value gap: a free identifier, or `@vac(..)`.
+    fn gap() -> V {
+        V::Unknown
+    }
+
+    /// Value of `@jam(x)` given `x`'s value.
+    fn jam(v: V) -> V {
+        v
+    }
+
+    fn not(a: V) -> V;
+    fn and(a: V, b: V) -> V;
+    fn or(a: V, b: V) -> V;
+    fn imp(a: V, b: V) -> V;
+}
+
+/// Strong Kleene K3 (the default): Unknown absorbs unless the other side decides, so
+/// `x -> x` is Unknown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct K3;
+
+impl TruthDomain for K3 {
+    const NAME: &'static str = "k3";
+
+    fn not(a: V) -> V {
+        match a {
+            V::Bool(b) => V::Bool(!b),
+            other => other,
+        }
+    }
+
+    fn and(a: V, b: V) -> V {
+        match (a.to_bool(), b.to_bool()) {
+            (Some(false), _) | (_, Some(false)) => V::Bool(false),
+            (Some(true), Some(true)) => V::Bool(true),
+            _ => V::Unknown,
+        }
+    }
+
+    fn or(a: V, b: V) -> V {
+        match (a.to_bool(), b.to_bool()) {
+            (Some(true), _) | (_, Some(true)) => V::Bool(true),
+            (Some(false), Some(false)) => V::Bool(false),
+            _ => V::Unknown,
+        }
+    }
+
+    fn imp(a: V, b: V) -> V {
+        K3::or(K3::not(a), b)
+    }
+}
+
+/// Łukasiewicz Ł3: K3's `~`, `&`, `|`, but `Unknown -> Unknown` is true, so `x -> x` is
+/// valid.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct L3;
+
+impl TruthDomain for L3 {
+    const NAME: &'static str = "l3";
+
+    fn not(a: V) -> V {
+        K3::not(a)
+    }
+
+    fn and(a: V, b: V) -> V {
+        K3::and(a, b)
+    }
+
+    fn or(a: V, b: V) -> V {
+        K3::or(a, b)
+    }
+
+    fn imp(a: V, b: V) -> V {
+        match (&a, &b) {
+            (V::Unknown, V::Unknown) => V::Bool(true),
+            _ => K3::imp(a, b),
+        }
+    }
+}
+
+/// Belnap–Dunn FOUR: true, false, neither (`V::Unknown`) and both (`V::Both`). A jammed
+/// value has been told true and false at once, so `@jam(x)` is Both.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Four;
+
+impl Four {
+    /// Position in the truth order: false < {neither, both} < true.
+    fn rank(v: &V) -> u8 {
+        match v {
+            V::Bool(false) => 0,
+            V::Unknown | V::Both => 1,
+            V::Bool(true) => 2,
+        }
+    }
+}
+
+impl TruthDomain for Four {
+    const NAME: &'static str = "four";
+
+    fn jam(_: V) -> V {
+        V::Both
+    }
+
+    fn not(a: V) -> V {
+        K3::not(a)
+    }
+
+    /// Meet in the truth order; neither and both meet at false.
+    fn and(a: V, b: V) -> V {
+        match (Four::rank(&a), Four::rank(&b)) {
+            (1, 1) if a != b => V::Bool(false),
+            (x, y) if x <= y => a,
+            _ => b,
+        }
+    }
+
+    /// Join in the truth order; neither and both join at true.
+    fn or(a: V, b: V) -> V {
+        match (Four::rank(&a), Four::rank(&b)) {
+            (1, 1) if a != b => V::Bool(true),
+            (x, y) if x >= y => a,
+            _ => b,
+        }
+    }
+
+    fn imp(a: V, b: V) -> V {
+        Four::or(Four::not(a), b)
+    }
+}
+
+/// Two-valued logic under a closed world: a gap is false.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Classical;
+
+impl TruthDomain for Classical {
+    const NAME: &'static str = "classical";
+
+    fn gap() -> V {
+        V::Bool(false)
+    }
+
+    fn not(a: V) -> V {
+        V::Bool(!Classical::truth(&a))
+    }
+
+    fn and(a: V, b: V) -> V {
+        V::Bool(Classical::truth(&a) && Classical::truth(&b))
+    }
+
+    fn or(a: V, b: V) -> V {
+        V::Bool(Classical::truth(&a) || Classical::truth(&b))
+    }
+
+    fn imp(a: V, b: V) -> V {
+        V::Bool(!Classical::truth(&a) || Classical::truth(&b))
+    }
+}
+
+impl Classical {
+    fn truth(v: &V) -> bool {
+        v.to_bool().unwrap_or(false)
+    }
+}
+
+/// Run-time choice of truth domain.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Logic {
+    #[default]
+    K3,
+    L3,
+    Four,
+    Classical,
+}
+
+impl Logic {
+    pub const ALL: [Logic; 4] = [Logic::K3, Logic::L3, Logic::Four, Logic::Classical];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Logic::K3 => K3::NAME,
+            Logic::L3 => L3::NAME,
+            Logic::Four => Four::NAME,
+            Logic::Classical => Classical::NAME,
+        }
+    }
+}
+
+impl fmt::Display for Logic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Logic {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "k3" | "kleene" => Ok(Logic::K3),
+            "l3" | "ł3" | "lukasiewicz" => Ok(Logic::L3),
+            "four" | "belnap" => Ok(Logic::Four),
+            "classical" | "bool" => Ok(Logic::Classical),
+            _ => Err(format!("unknown logic {s:?} (k3, l3, four, classical)")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const T: V = V::Bool(true);
+    const F: V = V::Bool(false);
+    const N: V = V::Unknown;
+    const B: V = V::Both;
+
+    #[test]
+    fn implication_tables() {
+        assert_eq!(K3::imp(N, N), N);
+        assert_eq!(L3::imp(N, N), T);
+        assert_eq!((L3::imp(T, N), L3::imp(N, F), L3::imp(N, T)), (N, N, T));
+        assert_eq!(Four::imp(N, N), N);
+        assert_eq!(Four::imp(B, B), B);
+        assert_eq!(Four::imp(B, N), T);
+        assert_eq!(Classical::imp(N, N), T);
+    }
+
+    #[test]
+    fn four_is_a_lattice() {
+        let all = [T, F, N, B];
+        for a in &all {
+            assert_eq!(Four::and(a.clone(), T), *a);
+            assert_eq!(Four::or(a.clone(), F), *a);
+            assert_eq!(Four::not(Four::not(a.clone())), *a);
+            for b in &all {
+                assert_eq!(Four::and(a.clone(), b.clone()), Four::and(b.clone(), a.clone()));
+                // De Morgan
+                assert_eq!(
+                    Four::not(Four::and(a.clone(), b.clone())),
+                    Four::or(Four::not(a.clone()), Four::not(b.clone()))
+                );
+            }
+        }
+        assert_eq!(Four::and(N, B), F);
+        assert_eq!(Four::or(N, B), T);
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for l in Logic::ALL {
+            assert_eq!(l.to_string().parse::<Logic>(), Ok(l));
+        }
+        assert_eq!("Belnap".parse::<Logic>(), Ok(Logic::Four));
+        assert!("fuzzy".parse::<Logic>().is_err());
+    }
+}
@@ -0,0 +1,870 @@
+//! MEM store: facts as asserted by each source (SpecRef: S3.10).
+//!
+//! A source re-asserting a fact overwrites its own claim; two sources claiming different
+//! values for one identifier are a conflict, and the identifier evaluates to JAM. Every claim
+//! keeps its provenance (source, assertion time, confidence) and may carry an expiry, after
+//! which it no longer witnesses anything and the identifier is VAC again.
+//!
+//! The store is bitemporal. A fact holds over its *valid time* (`valid_from` until
+//! `expires_at`), and the store held it over its *transaction time* (`recorded_at` until it
+//! was retracted or superseded). Retractions close records instead of deleting them, so a
+//! lookup `AsOf` any past instant sees what was true then, as the store knew it then.
+//! `compact` forgets records that were closed before a horizon.
+//!
+//! Cloning a store is cheap: identifiers share their histories until one side writes to them,
+//! so a clone is a consistent snapshot readers can evaluate against while a writer moves on.
+//! A `Transaction` stages assertions and retractions and applies them all at once, under one
+//! transaction time, or not at all.
+//!
+//! An `Overlay` is a hypothetical layer over a store — the counterfactual sheet C of the spec:
+//! it adds, overrides or hides identifiers without touching (or copying) what lies below.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::phase::Sheet;
+
+/// Source of facts imported without one (`Evaluator::import_mem`).
+pub const DEFAULT_SOURCE: &str = "default";
+
+/// Source of overlay facts given as a plain identifier -> bool map.
+pub const SCENARIO_SOURCE: &str = "scenario";
+
+/// Version written by `MemDoc`: 1 is the plain identifier -> bool map, 2 adds provenance,
+/// 3 the transaction-time history.
+pub const MEM_FORMAT_VERSION: u32 = 3;
+
+/// One source's assertion of an identifier, with its provenance.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fact {
+    pub ident: String,
+    pub value: bool,
+    pub source: String,
+    pub asserted_at: DateTime<Utc>,
+    /// From this instant on the fact holds; since always when `None`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_from: Option<DateTime<Utc>>,
+    /// From this instant on the fact is stale and no longer witnesses `ident`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// How much the source vouches for the value, in `[0, 1]`. Recorded, not weighed.
+    #[serde(default = "certain")]
+    pub confidence: f64,
+}
+
+fn certain() -> f64 {
+    1.0
+}
+
+impl Fact {
+    /// `source` asserts `ident = value` now, for good, with full confidence.
+    pub fn new(source: &str, ident: impl Into<String>, value: bool) -> Self {
+        Self {
+            ident: ident.into(),
+            value,
+            source: source.to_string(),
+            asserted_at: Utc::now(),
+            valid_from: None,
+            expires_at: None,
+            confidence: 1.0,
+        }
+    }
+
+    pub fn asserted_at(mut self, at: DateTime<Utc>) -> Self {
+        self.asserted_at = at;
+        self
+    }
+
+    /// Hold only from `from` on.
+    pub fn valid_from(mut self, from: DateTime<Utc>) -> Self {
+        self.valid_from = Some(from);
+        self
+    }
+
+    /// Expire `ttl` after the assertion time (never, if that is past the calendar's end).
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.expires_at = self.asserted_at.checked_add_signed(ttl);
+        self
+    }
+
+    /// `with_ttl` in whole seconds.
+    pub fn with_ttl_secs(self, secs: u64) -> Self {
+        match i64::try_from(secs).ok().and_then(Duration::try_seconds) {
+            Some(ttl) => self.with_ttl(ttl),
+            None => Self { expires_at: None, ..self },
+        }
+    }
+
+    /// Clamped to `[0, 1]`.
+    pub fn with_confidence(mut self, confidence: f64) -> Self {
+        self.confidence = confidence.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn expired_at(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|e| e <= now)
+    }
+
+    /// Within the valid time: in force and not expired.
+    pub fn valid_at(&self, t: DateTime<Utc>) -> bool {
+        self.valid_from.is_none_or(|v| v <= t) && !self.expired_at(t)
+    }
+}
+
+impl fmt::Display for Fact {
+    /// `paid = true (erp, asserted 2026-10-19T08:00:00Z, expires 2026-10-19T09:00:00Z, confidence 0.9)`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ts = |t: &DateTime<Utc>| t.to_rfc3339_opts(SecondsFormat::AutoSi, true);
+        let Fact { ident, value, source, asserted_at, .. } = self;
+        write!(f, "{ident} = {value} ({source}, asserted {}", ts(asserted_at))?;
+        if let Some(v) = &self.valid_from {
+            write!(f, ", valid from {}", ts(v))?;
+        }
+        if let Some(e) = &self.expires_at {
+            write!(f, ", expires {}", ts(e))?;
+        }
+        if self.confidence < 1.0 {
+            write!(f, ", confidence {}", self.confidence)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// One source's value for an identifier.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Claim {
+    pub source: String,
+    pub value: bool,
+}
+
+/// An identifier whose sources disagree; claims are sorted by source.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Conflict {
+    pub ident: String,
+    pub claims: Vec<Claim>,
+}
+
+impl Conflict {
+    pub fn sources(&self) -> impl Iterator<Item = &str> {
+        self.claims.iter().map(|c| c.source.as_str())
+    }
+}
+
+impl fmt::Display for Conflict {
+    /// `a: true (crm), false (erp)`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.ident)?;
+        for (i, c) in self.claims.iter().enumerate() {
+            let sep = if i == 0 { "" } else { ", " };
+            write!(f, "{sep}{} ({})", c.value, c.source)?;
+        }
+        Ok(())
+    }
+}
+
+/// What the store knows about one identifier as of some instant.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Witness {
+    /// No source asserts it.
+    Absent,
+    /// Asserted, but no claim is valid at that instant (expired, or not yet in force).
+    Expired(Vec<Fact>),
+    /// Every valid claim agrees; the claims, sorted by source.
+    Fact(Vec<Fact>),
+    Conflict(Conflict),
+}
+
+/// Every contradiction in a store.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsistencyReport {
+    /// Identifiers with at least one valid claim.
+    pub facts: usize,
+    pub sources: Vec<String>,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl ConsistencyReport {
+    pub fn consistent(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// One entry of an identifier's history: a fact, and the transaction time over which the
+/// store held it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    #[serde(flatten)]
+    pub fact: Fact,
+    pub recorded_at: DateTime<Utc>,
+    /// When the fact was retracted or superseded by its source; `None` while current.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retracted_at: Option<DateTime<Utc>>,
+}
+
+impl Record {
+    pub fn current(&self) -> bool {
+        self.retracted_at.is_none()
+    }
+
+    /// Held by the store at transaction time `t`.
+    pub fn known_at(&self, t: DateTime<Utc>) -> bool {
+        self.recorded_at <= t && self.retracted_at.is_none_or(|r| t < r)
+    }
+}
+
+/// The point a lookup is made from: facts must be valid at `valid`, and recorded by `known`
+/// (transaction time; the store's current contents when `None`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AsOf {
+    pub valid: DateTime<Utc>,
+    pub known: Option<DateTime<Utc>>,
+}
+
+impl AsOf {
+    /// What holds at `t`, by what the store knows now.
+    pub fn valid(t: DateTime<Utc>) -> Self {
+        Self { valid: t, known: None }
+    }
+
+    /// What held at `t`, as the store knew it at `t`.
+    pub fn at(t: DateTime<Utc>) -> Self {
+        Self { valid: t, known: Some(t) }
+    }
+}
+
+/// A serialized MEM store with its history. Reads every version: a plain identifier -> bool
+/// map becomes `DEFAULT_SOURCE` facts asserted on load, and version 2 facts count as recorded
+/// when they were asserted.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "MemFile")]
+pub struct MemDoc {
+    pub version: u32,
+    pub facts: Vec<Record>,
+}
+
+impl MemDoc {
+    pub fn new(facts: Vec<Record>) -> Self {
+        Self { version: MEM_FORMAT_VERSION, facts }
+    }
+}
+
+impl Default for MemDoc {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MemFile {
+    Versioned { version: u32, facts: Vec<Entry> },
+    Plain(HashMap<String, bool>),
+}
+
+/// A version 2 or 3 document entry.
+#[derive(Deserialize)]
+struct Entry {
+    #[serde(flatten)]
+    fact: Fact,
+    recorded_at: Option<DateTime<Utc>>,
+    retracted_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<MemFile> for MemDoc {
+    type Error = String;
+
+    fn try_from(file: MemFile) -> Result<Self, String> {
+        let record = |fact: Fact, recorded_at: Option<DateTime<Utc>>, retracted_at| Record {
+            recorded_at: recorded_at.unwrap_or(fact.asserted_at),
+            fact,
+            retracted_at,
+        };
+        match file {
+            MemFile::Versioned { version, .. } if version > MEM_FORMAT_VERSION => {
+                Err(format!("unsupported MEM format version {version}"))
+            }
+            MemFile::Versioned { facts, .. } => Ok(Self::new(
+                facts.into_iter().map(|e| record(e.fact, e.recorded_at, e.retracted_at)).collect(),
+            )),
+            MemFile::Plain(map) => {
+                let mut facts: Vec<Record> = map
+                    .into_iter()
+                    .map(|(k, v)| record(Fact::new(DEFAULT_SOURCE, k, v), None, None))
+                    .collect();
+                facts.sort_by(|a, b| a.fact.ident.cmp(&b.fact.ident));
+                Ok(Self::new(facts))
+            }
+        }
+    }
+}
+
+/// Mutations are stamped with the transaction time `tx` they are given. Clones share
+/// structure (see the module docs).
+#[derive(Debug, Clone, Default)]
+pub struct MemStore {
+    /// identifier -> every record, in transaction order
+    history: Arc<HashMap<String, Arc<Vec<Record>>>>,
+}
+
+impl MemStore {
+    pub fn assert(&mut self, source: &str, name: String, value: bool, tx: DateTime<Utc>) {
+        self.insert(Fact::new(source, name, value).asserted_at(tx), tx);
+    }
+
+    /// Record `fact`, superseding its source's current claim on the identifier.
+    pub fn insert(&mut self, fact: Fact, tx: DateTime<Utc>) {
+        let map = Arc::make_mut(&mut self.history);
+        let records = Arc::make_mut(map.entry(fact.ident.clone()).or_default());
+        close(records.iter_mut().filter(|r| r.fact.source == fact.source), tx);
+        records.push(Record { fact, recorded_at: tx, retracted_at: None });
+    }
+
+    pub fn extend(
+        &mut self,
+        source: &str,
+        facts: impl IntoIterator<Item = (String, bool)>,
+        tx: DateTime<Utc>,
+    ) {
+        for (name, value) in facts {
+            self.assert(source, name, value, tx);
+        }
+    }
+
+    /// Put back a record as it was, history included (`MemDoc` import). A record the store
+    /// already holds (same source and transaction time) is skipped, so importing a document
+    /// twice changes nothing. A current record supersedes the source's older open ones, and
+    /// is itself superseded by a newer one the store already holds.
+    pub fn restore(&mut self, mut record: Record) {
+        let map = Arc::make_mut(&mut self.history);
+        let records = Arc::make_mut(map.entry(record.fact.ident.clone()).or_default());
+        let source = &record.fact.source;
+        let ours: Vec<&mut Record> =
+            records.iter_mut().filter(|r| r.fact.source == *source).collect();
+        if ours.iter().any(|r| r.recorded_at == record.recorded_at) {
+            return;
+        }
+        if record.current() {
+            let newer = ours.iter().map(|r| r.recorded_at).filter(|&t| t > record.recorded_at);
+            record.retracted_at = newer.min();
+            let older = ours.into_iter().filter(|r| r.recorded_at < record.recorded_at);
+            close(older, record.recorded_at);
+        }
+        records.push(record);
+        records.sort_by_key(|r| r.recorded_at);
+    }
+
+    /// Retract every source's claim on `name`.
+    pub fn retract(&mut self, name: &str, tx: DateTime<Utc>) {
+        self.close_in(name, |_| true, tx);
+    }
+
+    /// Retract one source's claim on `name`.
+    pub fn retract_from(&mut self, source: &str, name: &str, tx: DateTime<Utc>) {
+        self.close_in(name, |r| r.fact.source == source, tx);
+    }
+
+    /// Retract everything `source` asserted.
+    pub fn retract_source(&mut self, source: &str, tx: DateTime<Utc>) {
+        self.close_where(|r| r.fact.source == source, tx);
+    }
+
+    /// Retract claims that have expired by `at`; returns how many.
+    pub fn retract_expired(&mut self, at: DateTime<Utc>, tx: DateTime<Utc>) -> usize {
+        self.close_where(|r| r.fact.expired_at(at), tx)
+    }
+
+    /// Retract everything.
+    pub fn clear(&mut self, tx: DateTime<Utc>) {
+        self.close_where(|_| true, tx);
+    }
+
+    /// Forget records retracted at or before `horizon`; lookups known at or after it answer
+    /// as before. Returns how many were dropped.
+    pub fn compact(&mut self, horizon: DateTime<Utc>) -> usize {
+        let old = |r: &Record| r.retracted_at.is_some_and(|t| t <= horizon);
+        if !self.history.values().flat_map(|rs| rs.iter()).any(old) {
+            return 0;
+        }
+        let mut dropped = 0;
+        Arc::make_mut(&mut self.history).retain(|_, records| {
+            if records.iter().any(old) {
+                let records = Arc::make_mut(records);
+                let before = records.len();
+                records.retain(|r| !old(r));
+                dropped += before - records.len();
+            }
+            !records.is_empty()
+        });
+        dropped
+    }
+
+    /// Start staging changes against this store; see `Transaction::commit`.
+    pub fn begin(&self) -> Transaction {
+        Transaction { staged: self.clone(), ops: Vec::new() }
+    }
+
+    /// Every record of `name`, oldest first.
+    pub fn history(&self, name: &str) -> &[Record] {
+        self.history.get(name).map_or(&[], |r| r.as_slice())
+    }
+
+    pub fn lookup(&self, name: &str, as_of: AsOf) -> Witness {
+        witness(name, self.known(name, as_of.known).collect(), as_of.valid)
+    }
+
+    /// `lookup` through `layers` (bottom first): the topmost layer that says anything about
+    /// `name` answers, and the store only when none does. Also says which sheet the answer
+    /// came from: C when a layer gave it.
+    pub fn lookup_through(&self, layers: &[Overlay], name: &str, as_of: AsOf) -> (Witness, Sheet) {
+        match layers.iter().rev().find_map(|o| o.lookup(name, as_of.valid)) {
+            Some(w) => (w, Sheet::C),
+            None => (self.lookup(name, as_of), Sheet::F),
+        }
+    }
+
+    /// Facts every valid claim agrees on; conflicting and expired identifiers are left out.
+    pub fn agreed(&self, as_of: AsOf) -> HashMap<String, bool> {
+        self.history
+            .keys()
+            .filter_map(|name| match self.lookup(name, as_of) {
+                Witness::Fact(f) => Some((name.clone(), f[0].value)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// source -> identifier -> value, for valid claims.
+    pub fn by_source(&self, as_of: AsOf) -> BTreeMap<String, BTreeMap<String, bool>> {
+        let mut out: BTreeMap<String, BTreeMap<String, bool>> = BTreeMap::new();
+        for f in self.valid(as_of) {
+            out.entry(f.source.clone()).or_default().insert(f.ident.clone(), f.value);
+        }
+        out
+    }
+
+    /// Every current claim, expired ones included.
+    pub fn current(&self) -> impl Iterator<Item = &Fact> {
+        self.history.values().flat_map(|rs| rs.iter()).filter(|r| r.current()).map(|r| &r.fact)
+    }
+
+    /// The whole store with its history, sorted by identifier and transaction time.
+    pub fn to_doc(&self) -> MemDoc {
+        let mut names: Vec<&String> = self.history.keys().collect();
+        names.sort();
+        MemDoc::new(names.into_iter().flat_map(|n| self.history[n].iter().cloned()).collect())
+    }
+
+    pub fn report(&self, as_of: AsOf) -> ConsistencyReport {
+        let valid: Vec<&Fact> = self.valid(as_of).collect();
+        let sources: BTreeSet<&String> = valid.iter().map(|f| &f.source).collect();
+        let idents: BTreeSet<&String> = valid.iter().map(|f| &f.ident).collect();
+        let conflicts = idents
+            .iter()
+            .filter_map(|name| match self.lookup(name, as_of) {
+                Witness::Conflict(c) => Some(c),
+                _ => None,
+            })
+            .collect();
+        ConsistencyReport {
+            facts: idents.len(),
+            sources: sources.into_iter().cloned().collect(),
+            conflicts,
+        }
+    }
+
+    /// Claims on `name` the store held at `known` (now, when `None`).
+    fn known(&self, name: &str, known: Option<DateTime<Utc>>) -> impl Iterator<Item = &Fact> {
+        let held = move |r: &&Record| known.map_or(r.current(), |t| r.known_at(t));
+        self.history(name).iter().filter(held).map(|r| &r.fact)
+    }
+
+    fn valid(&self, as_of: AsOf) -> impl Iterator<Item = &Fact> {
+        self.history
+            .keys()
+            .flat_map(move |n| self.known(n, as_of.known))
+            .filter(move |f| f.valid_at(as_of.valid))
+    }
+
+    /// `close_where`, for one identifier.
+    fn close_in(&mut self, name: &str, pick: impl Fn(&Record) -> bool, tx: DateTime<Utc>) {
+        if !self.history(name).iter().any(|r| r.current() && pick(r)) {
+            return;
+        }
+        let records = Arc::make_mut(&mut self.history).get_mut(name).map(Arc::make_mut);
+        close(records.into_iter().flatten().filter(|r| pick(r)), tx);
+    }
+
+    /// Retract the current records `pick` selects; histories with none are left shared.
+    fn close_where(&mut self, pick: impl Fn(&Record) -> bool, tx: DateTime<Utc>) -> usize {
+        let open = |r: &Record| r.current() && pick(r);
+        if !self.history.values().flat_map(|rs| rs.iter()).any(open) {
+            return 0;
+        }
+        let mut closed = 0;
+        for records in Arc::make_mut(&mut self.history).values_mut() {
+            if records.iter().any(open) {
+                closed += close(Arc::make_mut(records).iter_mut().filter(|r| pick(r)), tx);
+            }
+        }
+        closed
+    }
+}
+
+/// A batch of MEM changes, applied by `commit` under one transaction time so no lookup, as
+/// of any instant, sees part of it. Dropping it (or `rollback`) discards the batch.
+#[derive(Debug, Clone)]
+#[must_use = "a transaction does nothing until it is committed"]
+pub struct Transaction {
+    /// The store as of `begin` with the batch applied, for reading back staged changes.
+    staged: MemStore,
+    ops: Vec<Op>,
+}
+
+#[derive(Debug, Clone)]
+enum Op {
+    Insert(Fact),
+    Retract(String),
+    RetractFrom(String, String),
+    RetractSource(String),
+    Clear,
+}
+
+impl Transaction {
+    pub fn assert(&mut self, fact: Fact) -> &mut Self {
+        self.stage(Op::Insert(fact))
+    }
+
+    /// Retract every source's claim on `name`.
+    pub fn retract(&mut self, name: &str) -> &mut Self {
+        self.stage(Op::Retract(name.to_string()))
+    }
+
+    /// Retract one source's claim on `name`.
+    pub fn retract_from(&mut self, source: &str, name: &str) -> &mut Self {
+        self.stage(Op::RetractFrom(source.to_string(), name.to_string()))
+    }
+
+    /// Retract everything `source` asserted.
+    pub fn retract_source(&mut self, source: &str) -> &mut Self {
+        self.stage(Op::RetractSource(source.to_string()))
+    }
+
+    /// Retract everything.
+    pub fn clear(&mut self) -> &mut Self {
+        self.stage(Op::Clear)
+    }
+
+    /// The store as it will read after `commit`, if nobody else writes to it first.
+    pub fn staged(&self) -> &MemStore {
+        &self.staged
+    }
+
+    /// Staged changes.
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Apply the batch to `store` at `tx`, on top of whatever it holds by then. Returns how
+    /// many changes were applied.
+    pub fn commit(self, store: &mut MemStore, tx: DateTime<Utc>) -> usize {
+        for op in &self.ops {
+            op.apply(store, tx);
+        }
+        self.ops.len()
+    }
+
+    /// Discard the batch.
+    pub fn rollback(self) {}
+
+    fn stage(&mut self, op: Op) -> &mut Self {
+        op.apply(&mut self.staged, Utc::now());
+        self.ops.push(op);
+        self
+    }
+}
+
+impl Op {
+    fn apply(&self, store: &mut MemStore, tx: DateTime<Utc>) {
+        match self {
+            Op::Insert(fact) => store.insert(fact.clone(), tx),
+            Op::Retract(name) => store.retract(name, tx),
+            Op::RetractFrom(source, name) => store.retract_from(source, name, tx),
+            Op::RetractSource(source) => store.retract_source(source, tx),
+            Op::Clear => store.clear(tx),
+        }
+    }
+}
+
+/// Hypothetical facts layered over a store. For the identifiers it mentions, an overlay's
+/// claims replace everything below; hidden identifiers are absent. Overlay facts have no
+/// history: they are judged in valid time only.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Overlay {
+    /// Added or overriding claims; a plain identifier -> bool map reads as `SCENARIO_SOURCE`.
+    #[serde(default, deserialize_with = "facts_or_map")]
+    pub facts: Vec<Fact>,
+    /// Identifiers the layers below no longer witness.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub hide: BTreeSet<String>,
+}
+
+impl Overlay {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Claim `name` = `value` as `SCENARIO_SOURCE`.
+    pub fn set(self, name: impl Into<String>, value: bool) -> Self {
+        self.with_fact(Fact::new(SCENARIO_SOURCE, name, value))
+    }
+
+    /// Add `fact`, replacing its source's earlier claim in this layer.
+    pub fn with_fact(mut self, fact: Fact) -> Self {
+        self.hide.remove(&fact.ident);
+        self.facts.retain(|f| (&f.ident, &f.source) != (&fact.ident, &fact.source));
+        self.facts.push(fact);
+        self
+    }
+
+    /// Make `name` absent, whatever the layers below say.
+    pub fn hide(mut self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        self.facts.retain(|f| f.ident != name);
+        self.hide.insert(name);
+        self
+    }
+
+    /// What this layer says about `name` at `valid`; `None` to look below.
+    pub fn lookup(&self, name: &str, valid: DateTime<Utc>) -> Option<Witness> {
+        let claims: Vec<&Fact> = self.facts.iter().filter(|f| f.ident == name).collect();
+        if !claims.is_empty() {
+            return Some(witness(name, claims, valid));
+        }
+        self.hide.contains(name).then_some(Witness::Absent)
+    }
+}
+
+fn facts_or_map<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Vec<Fact>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Facts {
+        List(Vec<Fact>),
+        Map(BTreeMap<String, bool>),
+    }
+    Ok(match Facts::deserialize(d)? {
+        Facts::List(facts) => facts,
+        Facts::Map(map) => map.into_iter().map(|(k, v)| Fact::new(SCENARIO_SOURCE, k, v)).collect(),
+    })
+}
+
+/// What `claims` on `name` amount to at `valid`.
+fn witness(name: &str, claims: Vec<&Fact>, valid: DateTime<Utc>) -> Witness {
+    if claims.is_empty() {
+        return Witness::Absent;
+    }
+    let held: BTreeMap<&str, &Fact> =
+        claims.iter().filter(|f| f.valid_at(valid)).map(|f| (f.source.as_str(), *f)).collect();
+    let held: Vec<&Fact> = held.into_values().collect();
+    let Some(first) = held.first() else {
+        return Witness::Expired(claims.into_iter().cloned().collect());
+    };
+    if held.iter().all(|f| f.value == first.value) {
+        Witness::Fact(held.into_iter().cloned().collect())
+    } else {
+        Witness::Conflict(conflict(name, &held))
+    }
+}
+
+/// Retract the current ones of `records` at `tx`; returns how many.
+fn close<'a>(records: impl Iterator<Item = &'a mut Record>, tx: DateTime<Utc>) -> usize {
+    let mut closed = 0;
+    for r in records.filter(|r| r.current()) {
+        r.retracted_at = Some(tx);
+        closed += 1;
+    }
+    closed
+}
+
+fn conflict(name: &str, claims: &[&Fact]) -> Conflict {
+    Conflict {
+        ident: name.to_string(),
+        claims: claims.iter().map(|f| Claim { source: f.source.clone(), value: f.value }).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn t(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    fn values(w: Witness) -> Vec<(String, bool)> {
+        match w {
+            Witness::Fact(f) => f.into_iter().map(|f| (f.source, f.value)).collect(),
+            other => panic!("not a fact: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn sources_overwrite_themselves_and_conflict_with_others() {
+        let now = Utc::now();
+        let as_of = AsOf::valid(now);
+        let mut s = MemStore::default();
+        s.assert("erp", "paid".into(), false, now);
+        s.assert("erp", "paid".into(), true, now);
+        assert_eq!(values(s.lookup("paid", as_of)), [("erp".to_string(), true)]);
+        s.extend("crm", [("paid".to_string(), false), ("open".to_string(), true)], now);
+
+        let Witness::Conflict(c) = s.lookup("paid", as_of) else { panic!() };
+        assert_eq!(c.sources().collect::<Vec<_>>(), ["crm", "erp"]);
+        assert_eq!(c.to_string(), "paid: false (crm), true (erp)");
+        assert_eq!(s.agreed(as_of), HashMap::from([("open".to_string(), true)]));
+
+        let r = s.report(as_of);
+        assert_eq!((r.facts, r.sources.len(), r.conflicts.len()), (2, 2, 1));
+        s.retract_from("crm", "paid", now);
+        assert!(s.report(as_of).consistent());
+        s.retract("paid", now);
+        assert_eq!(s.lookup("paid", as_of), Witness::Absent);
+    }
+
+    #[test]
+    fn expired_claims_stop_witnessing() {
+        let t0 = t("2026-10-19T08:00:00Z");
+        let mut s = MemStore::default();
+        let sensor = Fact::new("sensor", "door", true).asserted_at(t0);
+        s.insert(sensor.with_ttl(Duration::minutes(5)), t0);
+        s.insert(
+            Fact::new("crm", "door", false).asserted_at(t0).with_ttl(Duration::minutes(1)),
+            t0,
+        );
+
+        assert!(matches!(s.lookup("door", AsOf::valid(t0)), Witness::Conflict(_)));
+        let later = AsOf::valid(t0 + Duration::minutes(2));
+        assert_eq!(values(s.lookup("door", later)), [("sensor".to_string(), true)]);
+        assert!(s.report(later).consistent());
+        let Witness::Expired(stale) = s.lookup("door", AsOf::valid(t0 + Duration::minutes(5)))
+        else {
+            panic!()
+        };
+        assert_eq!(stale.len(), 2);
+        assert_eq!(
+            stale[0].to_string(),
+            "door = true (sensor, asserted 2026-10-19T08:00:00Z, expires 2026-10-19T08:05:00Z)"
+        );
+        assert_eq!(s.retract_expired(later.valid, later.valid), 1);
+    }
+
+    #[test]
+    fn answers_as_of_valid_and_transaction_time() {
+        let (t0, t1, t2) =
+            (t("2026-03-01T09:00:00Z"), t("2026-03-01T10:00:00Z"), t("2026-03-01T11:00:00Z"));
+        let mut s = MemStore::default();
+        s.assert("erp", "paid".into(), false, t0);
+        s.assert("erp", "paid".into(), true, t1);
+        // Learned at t2 that a credit hold had been in force since t0.
+        s.insert(Fact::new("risk", "hold", true).valid_from(t0), t2);
+        s.retract("paid", t2 + Duration::hours(1));
+
+        let at = |s: &MemStore, valid, known| s.agreed(AsOf { valid, known: Some(known) });
+        assert_eq!(at(&s, t0, t0), HashMap::from([("paid".to_string(), false)]));
+        assert!(at(&s, t1, t1)["paid"]);
+        assert!(!at(&s, t1, t1).contains_key("hold"));
+        assert!(at(&s, t1, t2)["hold"]);
+        assert_eq!(s.lookup("paid", AsOf::valid(t2)), Witness::Absent);
+        assert_eq!(s.history("paid").len(), 2);
+
+        assert_eq!(s.compact(t1), 1);
+        assert!(at(&s, t1, t1 + Duration::minutes(1))["paid"]);
+        assert_eq!(s.compact(t2 + Duration::hours(1)), 1);
+        assert!(s.history("paid").is_empty());
+    }
+
+    #[test]
+    fn transactions_commit_at_once_and_snapshots_share_history() {
+        let (t0, t1) = (t("2026-10-19T08:00:00Z"), t("2026-10-19T09:00:00Z"));
+        let mut s = MemStore::default();
+        s.extend("erp", [("paid".to_string(), true), ("open".to_string(), true)], t0);
+        let snapshot = s.clone();
+
+        let mut tx = s.begin();
+        tx.retract("open").assert(Fact::new("erp", "shipped", true));
+        assert!(tx.staged().agreed(AsOf::valid(t1)).contains_key("shipped"));
+        assert!(!s.agreed(AsOf::valid(t1)).contains_key("shipped"));
+        assert_eq!(tx.commit(&mut s, t1), 2);
+
+        // Untouched histories are still shared with the snapshot, which is unchanged.
+        assert!(Arc::ptr_eq(&s.history["paid"], &snapshot.history["paid"]));
+        assert_eq!(snapshot.agreed(AsOf::valid(t1)).len(), 2);
+        let mid_batch = AsOf { valid: t1, known: Some(t1 - Duration::seconds(1)) };
+        assert_eq!(s.agreed(mid_batch).len(), 2);
+        let after = s.agreed(AsOf::at(t1));
+        assert_eq!((after.len(), after.contains_key("open")), (2, false));
+
+        let mut tx = s.begin();
+        tx.clear();
+        tx.rollback();
+        assert_eq!(s.current().count(), 2);
+    }
+
+    #[test]
+    fn reads_every_document_version() {
+        let t0 = t("2026-10-19T08:00:00Z");
+        let mut s = MemStore::default();
+        s.insert(Fact::new("erp", "paid", true).asserted_at(t0).with_confidence(0.9), t0);
+        s.retract("paid", t0 + Duration::hours(1));
+        let json = serde_json::to_string(&s.to_doc()).unwrap();
+        assert_eq!(
+            json,
+            r#"{"version":3,"facts":[{"ident":"paid","value":true,"source":"erp","asserted_at":"2026-10-19T08:00:00Z","confidence":0.9,"recorded_at":"2026-10-19T08:00:00Z","retracted_at":"2026-10-19T09:00:00Z"}]}"#
+        );
+        assert_eq!(serde_json::from_str::<MemDoc>(&json).unwrap(), s.to_doc());
+
+        let v2 = r#"{"version":2,"facts":[{"ident":"paid","value":true,"source":"erp","asserted_at":"2026-10-19T08:00:00Z"}]}"#;
+        let doc: MemDoc = serde_json::from_str(v2).unwrap();
+        assert_eq!((doc.facts[0].recorded_at, doc.facts[0].current()), (t0, true));
+
+        let plain: MemDoc = serde_json::from_str(r#"{"paid": true, "open": false}"#).unwrap();
+        let got: Vec<_> =
+            plain.facts.iter().map(|r| (&*r.fact.ident, r.fact.value, &*r.fact.source)).collect();
+        assert_eq!(got, [("open", false, DEFAULT_SOURCE), ("paid", true, DEFAULT_SOURCE)]);
+        assert!(serde_json::from_str::<MemDoc>(r#"{"version":9,"facts":[]}"#).is_err());
+    }
+
+    #[test]
+    fn restoring_keeps_history_consistent() {
+        let (t0, t1) = (t("2026-10-19T08:00:00Z"), t("2026-10-19T09:00:00Z"));
+        let mut s = MemStore::default();
+        s.assert("erp", "paid".into(), true, t1);
+
+        // An older current record is superseded by the newer one already held.
+        let mut old = MemStore::default();
+        old.assert("erp", "paid".into(), false, t0);
+        for r in old.to_doc().facts {
+            s.restore(r);
+        }
+        let history = s.history("paid");
+        assert_eq!(history.len(), 2);
+        assert_eq!((history[0].retracted_at, history[1].retracted_at), (Some(t1), None));
+        assert!(history.iter().all(|r| r.retracted_at.is_none_or(|x| x >= r.recorded_at)));
+        assert!(!s.agreed(AsOf { valid: t0, known: Some(t0) })["paid"]);
+        assert!(s.agreed(AsOf::at(t1))["paid"]);
+
+        // Importing the same document again changes nothing.
+        let doc = s.to_doc();
+        for r in doc.facts.clone() {
+            s.restore(r);
+        }
+        assert_eq!(s.to_doc(), doc);
+    }
+}
@@ -17,3 +17,33 @@ impl Phase {
         }
     }
 }
+
+/// Which sheet a result lies on: factual (witnessed) or counterfactual (unwitnessed or
+/// what-if). Evaluation starts on F; the sheet never changes a value or phase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Sheet {
+    #[default]
+    F,
+    C,
+}
+impl Sheet {
+    /// The other sheet (`@mem` transport).
+    pub fn flip(self) -> Sheet {
+        match self {
+            Sheet::F => Sheet::C,
+            Sheet::C => Sheet::F,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Sheet::F => "F",
+            Sheet::C => "C",
+        }
+    }
+}
+impl std::fmt::Display for Sheet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,200 @@
+//! Invariant pulse channel (docs/invariant-pulse-channel.md).
+//!
+//! Typed events derived from the evaluation steps, in evaluation (post-)order. They are a
+//! projection of the steps `eval` already takes: recording them never changes a value or phase.
+//!
+//! * **Boundary** — a step whose phase enters JAM (`pre != JAM`, `post == JAM`).
+//! * **Transport** — every `@mem(·)` step (F → C sheet exchange; truth unchanged).
+//! * **Witness** — an identifier that resolves to a value instead of VAC. In this engine the
+//!   witness is a MEM fact, so the step's phase is MEM.
+//! * **Sink** — the step where its implication chain first becomes sunk
+//!   (`EvalResult::first_sink`): the first link, in evaluation order (innermost first), whose
+//!   antecedent is `true` and consequent definite, on F; at most one per chain. The links
+//!   evaluated after it keep `sink = true` (I2) but fire no pulse. A chain is a maximal run of `->` linked through
+//!   consequents, so `a -> b -> c` is one chain and `(a -> b) -> c` is two.
+//! * **Winding** — every negation evaluated off VAC advances θ by π; each full circuit (two
+//!   such negations) fires one Winding carrying the circuit count.
+
+use serde::Serialize;
+
+use crate::ast::{op_name, Expr, PhaseOp};
+use crate::eval::EvalResult;
+use crate::phase::Phase;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PulseKind {
+    Winding,
+    Sink,
+    Boundary,
+    Transport,
+    Witness,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Pulse {
+    pub kind: PulseKind,
+    /// Post-order index of the step that fired (the index into `eval_traced` steps).
+    pub step: usize,
+    pub op: &'static str,
+    /// Phase after the step.
+    pub phase: Phase,
+    /// Sink: implication chain, numbered in source order from 0.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain: Option<usize>,
+    /// Winding: full circuits completed so far.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub winding: Option<u32>,
+    /// Witness: the identifier.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ident: Option<String>,
+}
+
+/// Implication chain of every node, in post-order (`None` off chains).
+fn chains(e: &Expr) -> Vec<Option<usize>> {
+    fn walk(
+        e: &Expr,
+        consequent_of: Option<usize>,
+        next: &mut usize,
+        out: &mut Vec<Option<usize>>,
+    ) {
+        match e {
+            Expr::Imp(a, b) => {
+                let chain = consequent_of.unwrap_or_else(|| {
+                    *next += 1;
+                    *next - 1
+                });
+                walk(a, None, next, out);
+                walk(b, Some(chain), next, out);
+                out.push(Some(chain));
+            }
+            Expr::Bool(_) | Expr::Ident(_) => out.push(None),
+            Expr::Not(x) | Expr::PhaseOp(_, x) => {
+                walk(x, None, next, out);
+                out.push(None);
+            }
+            Expr::And(a, b) | Expr::Or(a, b) => {
+                walk(a, None, next, out);
+                walk(b, None, next, out);
+                out.push(None);
+            }
+        }
+    }
+    let mut out = Vec::new();
+    walk(e, None, &mut 0, &mut out);
+    out
+}
+
+/// Turns the evaluator's step callbacks into pulses.
+pub(crate) struct Recorder {
+    chains: Vec<Option<usize>>,
+    half_turns: u32,
+    next: usize,
+    pub(crate) pulses: Vec<Pulse>,
+}
+
+impl Recorder {
+    pub(crate) fn new(root: &Expr) -> Self {
+        Self { chains: chains(root), half_turns: 0, next: 0, pulses: Vec::new() }
+    }
+
+    pub(crate) fn step(&mut self, node: &Expr, pre: Phase, r: &EvalResult) {
+        let step = self.next;
+        self.next += 1;
+
+        let pulse = |kind| Pulse {
+            kind,
+            step,
+            op: op_name(node),
+            phase: r.phase,
+            chain: None,
+            winding: None,
+            ident: None,
+        };
+        if r.phase == Phase::JAM && pre != Phase::JAM {
+            self.pulses.push(pulse(PulseKind::Boundary));
+        }
+        match node {
+            Expr::Ident(name) if r.phase != Phase::VAC => {
+                self.pulses.push(Pulse { ident: Some(name.clone()), ..pulse(PulseKind::Witness) });
+            }
+            Expr::PhaseOp(PhaseOp::Mem, _) => self.pulses.push(pulse(PulseKind::Transport)),
+            Expr::Not(_) if r.phase != Phase::VAC => {
+                self.half_turns += 1;
+                if self.half_turns.is_multiple_of(2) {
+                    let winding = Some(self.half_turns / 2);
+                    self.pulses.push(Pulse { winding, ..pulse(PulseKind::Winding) });
+                }
+            }
+            Expr::Imp(..) if r.first_sink => {
+                let chain = self.chains[step].expect("implication outside a chain");
+                self.pulses.push(Pulse { chain: Some(chain), ..pulse(PulseKind::Sink) });
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::Evaluator;
+
+    fn b(v: bool) -> Box<Expr> {
+        Box::new(Expr::Bool(v))
+    }
+
+    fn kinds(e: &Expr, ev: &mut Evaluator) -> Vec<PulseKind> {
+        ev.eval_pulsed(e).1.into_iter().map(|p| p.kind).collect()
+    }
+
+    #[test]
+    fn chains_follow_consequents_only() {
+        // true -> (true -> true): one chain; (true -> true) -> true: two.
+        let right = Expr::Imp(b(true), Box::new(Expr::Imp(b(true), b(true))));
+        let left = Expr::Imp(Box::new(Expr::Imp(b(true), b(true))), b(true));
+        let ids = |e: &Expr| chains(e).into_iter().flatten().collect::<Vec<_>>();
+        assert_eq!(ids(&right), [0, 0]);
+        assert_eq!(ids(&left), [1, 0]);
+
+        let mut ev = Evaluator::new(false);
+        assert_eq!(kinds(&right, &mut ev), [PulseKind::Sink]);
+        assert_eq!(kinds(&left, &mut ev), [PulseKind::Sink, PulseKind::Sink]);
+
+        // The inner link sinks first; the head stays sunk without sinking again.
+        let r = ev.eval(&right, None);
+        assert_eq!((r.sink, r.first_sink, r.sinks), (true, false, vec![1]));
+        let r = ev.eval(&left, None);
+        assert_eq!((r.sink, r.sinks), (true, vec![1, 1]));
+        // Sinks are F-local: `@mem` evaluates its operand on C.
+        let mem = Expr::PhaseOp(PhaseOp::Mem, Box::new(right));
+        assert_eq!(ev.eval(&mem, None).sinks, [0]);
+        assert_eq!(kinds(&mem, &mut ev), [PulseKind::Transport]);
+    }
+
+    #[test]
+    fn boundary_transport_witness_winding() {
+        let mut ev = Evaluator::new(true);
+        ev.import_mem([("rain".to_string(), true)].into());
+
+        let jam = Expr::Or(Box::new(Expr::PhaseOp(PhaseOp::Jam, b(true))), b(false));
+        assert_eq!(kinds(&jam, &mut ev), [PulseKind::Boundary]);
+
+        let mem = Expr::PhaseOp(PhaseOp::Mem, Box::new(Expr::Ident("rain".into())));
+        let (_, pulses) = ev.eval_pulsed(&mem);
+        assert_eq!(pulses[0].kind, PulseKind::Witness);
+        assert_eq!(pulses[0].ident.as_deref(), Some("rain"));
+        assert_eq!(pulses[1].kind, PulseKind::Transport);
+
+        let not3 = Expr::Not(Box::new(Expr::Not(Box::new(Expr::Not(b(true))))));
+        let (_, pulses) = ev.eval_pulsed(&not3);
+        assert_eq!(pulses.len(), 1);
+        assert_eq!(
+            (pulses[0].kind, pulses[0].step, pulses[0].winding),
+            (PulseKind::Winding, 2, Some(1))
+        );
+
+        // An unwitnessed identifier: no Witness, and negating VAC does not turn θ.
+        assert!(kinds(&Expr::Not(Box::new(Expr::Ident("x".into()))), &mut ev).is_empty());
+    }
+}
@@ -0,0 +1,195 @@
+//! What-if evaluation over counterfactual overlays (SpecRef: S3.10, sheet C).
+//!
+//! A `Scenario` stacks `Overlay`s over the evaluator's MEM store. `Evaluator::what_if`
+//! evaluates a rulepack — named rules — once against the store as it is (the baseline) and
+//! once per scenario, and reports the rules whose value or phase came out differently.
+//! Scenarios never write to the store. Outcomes carry their sheet, so a conclusion that rests
+//! on a scenario's layers (C) can be told from one the store alone supports (F).
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::ast::Expr;
+use crate::eval::Evaluator;
+use crate::mem::Overlay;
+use crate::phase::{Phase, Sheet};
+use crate::value::V;
+
+/// Named what-if layers, bottom first.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    #[serde(default)]
+    pub layers: Vec<Overlay>,
+}
+
+impl Scenario {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into(), layers: Vec::new() }
+    }
+
+    /// Stack `overlay` on top of the layers so far.
+    pub fn layer(mut self, overlay: Overlay) -> Self {
+        self.layers.push(overlay);
+        self
+    }
+}
+
+/// What one rule came to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Outcome {
+    #[serde(serialize_with = "value_json")]
+    pub value: V,
+    pub phase: Phase,
+    pub sheet: Sheet,
+}
+
+/// A rule whose value or phase under a scenario differs from the baseline.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Delta {
+    pub rule: String,
+    pub baseline: Outcome,
+    pub scenario: Outcome,
+}
+
+impl Delta {
+    pub fn value_changed(&self) -> bool {
+        self.baseline.value != self.scenario.value
+    }
+
+    pub fn phase_changed(&self) -> bool {
+        self.baseline.phase != self.scenario.phase
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ScenarioReport {
+    pub name: String,
+    /// One per rule, in rulepack order.
+    pub outcomes: Vec<Outcome>,
+    pub changes: Vec<Delta>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WhatIfReport {
+    pub rules: Vec<String>,
+    pub baseline: Vec<Outcome>,
+    pub scenarios: Vec<ScenarioReport>,
+}
+
+impl WhatIfReport {
+    /// Whether any scenario changed any rule.
+    pub fn changed(&self) -> bool {
+        self.scenarios.iter().any(|s| !s.changes.is_empty())
+    }
+}
+
+impl Evaluator {
+    /// An evaluator that sees `scenario`'s layers over a snapshot of this one's MEM store.
+    /// Layers only apply with MEM enabled, as imported facts do.
+    pub fn under(&self, scenario: &Scenario) -> Evaluator {
+        let mut ev = self.snapshot();
+        if ev.mem_enabled {
+            ev.overlays.extend(scenario.layers.iter().cloned());
+        }
+        ev
+    }
+
+    /// Evaluate `rules` against the store as it is and under each of `scenarios`. Every
+    /// evaluation is judged at the same instant, so only the layers tell them apart.
+    pub fn what_if(&self, rules: &[(String, Expr)], scenarios: &[Scenario]) -> WhatIfReport {
+        let mut base = self.snapshot();
+        base.at = Some(base.at.unwrap_or_else(Utc::now));
+        let run = |ev: &mut Evaluator| -> Vec<Outcome> {
+            rules
+                .iter()
+                .map(|(_, e)| {
+                    let r = ev.eval(e, None);
+                    Outcome { value: r.value, phase: r.phase, sheet: r.sheet }
+                })
+                .collect()
+        };
+        let baseline = run(&mut base);
+        let scenarios = scenarios
+            .iter()
+            .map(|s| {
+                let outcomes = run(&mut base.under(s));
+                let changes = rules
+                    .iter()
+                    .zip(baseline.iter().zip(&outcomes))
+                    .filter(|(_, (b, o))| b.value != o.value || b.phase != o.phase)
+                    .map(|((name, _), (b, o))| Delta {
+                        rule: name.clone(),
+                        baseline: b.clone(),
+                        scenario: o.clone(),
+                    })
+                    .collect();
+                ScenarioReport { name: s.name.clone(), outcomes, changes }
+            })
+            .collect();
+        WhatIfReport { rules: rules.iter().map(|(n, _)| n.clone()).collect(), baseline, scenarios }
+    }
+}
+
+/// `true`/`false`, `null` for unknown and `"both"`, as the CLI prints values.
+fn value_json<S: Serializer>(v: &V, s: S) -> Result<S::Ok, S::Error> {
+    match v {
+        V::Bool(b) => s.serialize_bool(*b),
+        V::Unknown => s.serialize_none(),
+        V::Both => s.serialize_str("both"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::Fact;
+
+    fn ident(name: &str) -> Expr {
+        Expr::Ident(name.to_string())
+    }
+
+    #[test]
+    fn scenarios_report_changed_rules_without_touching_the_store() {
+        let mut ev = Evaluator::new(true);
+        ev.assert_mem(Fact::new("erp", "paid", true));
+        ev.assert_mem(Fact::new("erp", "shipped", false));
+        let rules = vec![
+            ("paid".to_string(), ident("paid")),
+            ("ship".to_string(), Expr::Imp(Box::new(ident("paid")), Box::new(ident("shipped")))),
+            ("hold".to_string(), ident("hold")),
+        ];
+        let late = Scenario::new("late").layer(Overlay::new().set("paid", false));
+        let hidden = Scenario::new("no-erp").layer(Overlay::new().hide("paid").hide("shipped"));
+        let stacked = late.clone().layer(Overlay::new().set("hold", true).hide("paid"));
+        let same = Scenario::new("same").layer(Overlay::new().set("paid", true));
+
+        let r = ev.what_if(&rules, &[late, hidden, stacked, same]);
+        let fact = |b| Outcome { value: V::Bool(b), phase: Phase::MEM, sheet: Sheet::F };
+        assert_eq!(r.baseline[1], fact(false));
+        let changed: Vec<Vec<&str>> = r
+            .scenarios
+            .iter()
+            .map(|s| s.changes.iter().map(|d| d.rule.as_str()).collect())
+            .collect();
+        assert_eq!(
+            changed,
+            [vec!["paid", "ship"], vec!["paid", "ship"], vec!["paid", "ship", "hold"], vec![]]
+        );
+        // Same value, but now resting on the scenario rather than on the store.
+        assert_eq!(r.scenarios[3].outcomes[0], Outcome { sheet: Sheet::C, ..fact(true) });
+        assert_eq!(r.scenarios[0].outcomes[1], Outcome { sheet: Sheet::C, ..fact(true) });
+        let d = &r.scenarios[1].changes[0];
+        assert!(d.value_changed() && d.phase_changed());
+        assert_eq!(d.scenario, Outcome { value: V::Unknown, phase: Phase::VAC, sheet: Sheet::C });
+        assert_eq!(r.scenarios[2].outcomes[0].phase, Phase::VAC, "upper layer hides");
+
+        assert_eq!(ev.mem_facts().len(), 2);
+        assert_eq!(ev.eval(&ident("paid"), None).value, V::Bool(true));
+        let json = serde_json::to_value(&r.scenarios[1].changes[0]).unwrap();
+        assert_eq!(
+            json["scenario"],
+            serde_json::json!({ "value": null, "phase": "VAC", "sheet": "C" })
+        );
+    }
+}
@@ -1,20 +1,53 @@
 #![allow(dead_code)]
 
+use crate::ast::{op_name, Expr};
+use crate::eval::EvalResult;
 use crate::phase::Phase;
 
 #[derive(serde::Serialize, Debug, Clone)]
 pub struct TraceStep {
-    pub op:   &'static str,     // "not","and","or","implies","@mem","@jam","@vac","@alive","ident"
-    pub pre:  Phase,            // ALIVE | JAM | MEM | VAC
-    pub post: Phase,            // ALIVE | JAM | MEM | VAC
+    pub op: &'static str, // "not","and","or","implies","@mem","@jam","@vac","@alive","ident","bool"
+    pub pre: Phase,       // ALIVE | JAM | MEM | VAC
+    pub post: Phase,      // ALIVE | JAM | MEM | VAC
     #[serde(default)]
-    pub sink: bool,             // fixed-point on →-chains
+    pub sink: bool, // fixed-point on →-chains
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sheet: Option<&'static str>, // "F" | "C"
     #[serde(skip_serializing_if = "Option::is_none")]
     pub theta: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub rho:   Option<f64>,
+    pub rho: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub note:  Option<String>,
-}
\ No newline at end of file
+    pub note: Option<String>,
+}
+
+impl TraceStep {
+    /// Step for one evaluated node, on the sheet its result lies on (geometry fields left
+    /// empty). An identifier notes where its value came from: the MEM facts witnessing it,
+    /// the expired ones it fell back from, or the conflicting claims.
+    pub fn new(node: &Expr, pre: Phase, r: &EvalResult) -> Self {
+        let op = op_name(node);
+        let facts = |fs: &[crate::mem::Fact]| {
+            fs.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+        };
+        let note = match node {
+            Expr::Ident(_) => match (r.conflicts.first(), &r.witnesses[..], &r.expired[..]) {
+                (Some(c), _, _) => Some(format!("conflict {c}")),
+                (None, [], []) => None,
+                (None, [], stale) => Some(format!("expired {}", facts(stale))),
+                (None, live, _) => Some(format!("witness {}", facts(live))),
+            },
+            _ => None,
+        };
+        TraceStep {
+            op,
+            pre,
+            post: r.phase,
+            sink: r.sink,
+            sheet: Some(r.sheet.as_str()),
+            theta: None,
+            rho: None,
+            note,
+        }
+    }
+}
@@ -3,12 +3,14 @@ use serde::{Deserialize, Serialize};
 pub enum V {
     Bool(bool),
     Unknown,
+    /// Belnap's "both true and false"; only produced under `Logic::Four`.
+    Both,
 }
 impl V {
     pub fn to_bool(&self) -> Option<bool> {
         match self {
             V::Bool(b) => Some(*b),
-            V::Unknown => None,
+            V::Unknown | V::Both => None,
         }
     }
 }
@@ -0,0 +1,396 @@
+//! Request authentication and per-key rate limits.
+//!
+//! Modes (`GRIEG_HTTP_AUTH`):
+//!
+//! * `none` (default) — no credentials; with mutual TLS the client certificate is the identity.
+//! * `api-key` — `Authorization: Bearer <secret>` (what `ZCL_GRIEG_HTTP_CLIENT` sends) or
+//!   `X-API-Key: <secret>` (the CPI header mapping).
+//! * `hmac` — `X-Grieg-Key: <id>`, `X-Grieg-Timestamp: <unix seconds>` and
+//!   `X-Grieg-Signature: hex(HMAC-SHA256(secret, "{timestamp}\n{METHOD}\n{path?query}\n" + body))`.
+//!   Timestamps further than `max_skew` from the server clock are rejected.
+//!
+//! Keys come from `GRIEG_HTTP_API_KEYS` or the file named by `GRIEG_HTTP_API_KEYS_FILE`: entries
+//! `id:secret[:requests-per-minute]`, separated by commas or newlines; a line starting with `#`
+//! is a comment. A secret is everything between the first `:` and an optional trailing
+//! `:<digits>` rate, trimmed: any characters but `,` and line breaks, `:` and `#` included. A
+//! secret that itself ends in `:<digits>` must be followed by an explicit rate.
+//! A key's own rate overrides `GRIEG_HTTP_RATE_PER_MIN`; without either, requests are not
+//! rate-limited. Unauthenticated callers share the `anonymous` bucket.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use axum::body::{to_bytes, Body};
+use axum::extract::{Request, State};
+use axum::http::header::{AUTHORIZATION, RETRY_AFTER};
+use axum::http::{HeaderMap, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha2::Sha256;
+
+use crate::AppState;
+
+pub const KEY_HEADER: &str = "x-grieg-key";
+pub const TIMESTAMP_HEADER: &str = "x-grieg-timestamp";
+pub const SIGNATURE_HEADER: &str = "x-grieg-signature";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AuthMode {
+    #[default]
+    None,
+    ApiKey,
+    Hmac,
+}
+
+impl FromStr for AuthMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" | "off" => Ok(AuthMode::None),
+            "api-key" | "apikey" | "key" => Ok(AuthMode::ApiKey),
+            "hmac" => Ok(AuthMode::Hmac),
+            other => Err(format!("unknown auth mode '{other}' (expected none, api-key or hmac)")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiKey {
+    pub id: String,
+    pub secret: String,
+    /// Overrides [`AuthConfig::rate_per_min`] for this key.
+    pub rate_per_min: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AuthConfig {
+    pub mode: AuthMode,
+    pub keys: Vec<ApiKey>,
+    /// Requests per minute per key (burst of the same size); `None` disables rate limiting.
+    pub rate_per_min: Option<u32>,
+    /// Accepted clock difference for HMAC timestamps.
+    pub max_skew: Duration,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            mode: AuthMode::None,
+            keys: Vec::new(),
+            rate_per_min: None,
+            max_skew: Duration::from_secs(300),
+        }
+    }
+}
+
+/// Parse `id:secret[:rate]` entries separated by commas or newlines (see the module docs).
+pub fn parse_keys(text: &str) -> Result<Vec<ApiKey>, String> {
+    let mut keys = Vec::new();
+    let lines = text.lines().filter(|l| !l.trim_start().starts_with('#'));
+    for entry in lines.flat_map(|l| l.split(',')).map(str::trim) {
+        if entry.is_empty() {
+            continue;
+        }
+        let Some((id, rest)) = entry.split_once(':') else {
+            return Err(format!("api key '{entry}': expected id:secret[:rate]"));
+        };
+        let (secret, rate_per_min) = match rest.rsplit_once(':') {
+            Some((secret, r)) if !r.is_empty() && r.bytes().all(|b| b.is_ascii_digit()) => {
+                let rate = r.parse().map_err(|_| format!("api key '{id}': invalid rate '{r}'"))?;
+                (secret, Some(rate))
+            }
+            _ => (rest, None),
+        };
+        let (id, secret) = (id.trim(), secret.trim());
+        if id.is_empty() || secret.is_empty() {
+            return Err(format!("api key '{id}': empty id or secret"));
+        }
+        keys.push(ApiKey { id: id.into(), secret: secret.into(), rate_per_min });
+    }
+    Ok(keys)
+}
+
+/// SHA-256 fingerprint (hex) of a verified client certificate, attached to every request of a
+/// mutual-TLS connection.
+#[derive(Debug, Clone)]
+pub struct ClientCert(pub String);
+
+// ---------------------------------------------------------------------------
+// Rate limiting
+// ---------------------------------------------------------------------------
+
+struct Bucket {
+    tokens: f64,
+    last: Instant,
+}
+
+/// Token buckets keyed by caller identity.
+#[derive(Default)]
+pub struct RateLimiter {
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    /// Take one token from `who`'s bucket, or return how long until one is available.
+    pub fn take(&self, who: &str, per_min: u32) -> Result<(), Duration> {
+        let rate = f64::from(per_min.max(1)) / 60.0;
+        let capacity = f64::from(per_min.max(1));
+        let mut map = self.buckets.lock().unwrap();
+        let now = Instant::now();
+        let b = map.entry(who.to_string()).or_insert(Bucket { tokens: capacity, last: now });
+        b.tokens = (b.tokens + now.duration_since(b.last).as_secs_f64() * rate).min(capacity);
+        b.last = now;
+        if b.tokens >= 1.0 {
+            b.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - b.tokens) / rate))
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Verification
+// ---------------------------------------------------------------------------
+
+/// Authenticator shared by all requests.
+pub struct Guard {
+    cfg: AuthConfig,
+    limiter: RateLimiter,
+}
+
+impl Guard {
+    pub fn new(cfg: AuthConfig) -> Self {
+        Self { cfg, limiter: RateLimiter::default() }
+    }
+
+    fn key(&self, id: &str) -> Option<&ApiKey> {
+        self.cfg.keys.iter().find(|k| k.id == id)
+    }
+
+    /// The key whose secret is presented as a bearer token or `X-API-Key`.
+    fn api_key(&self, headers: &HeaderMap) -> Result<&ApiKey, String> {
+        let bearer = headers
+            .get(AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "));
+        let presented = bearer
+            .or_else(|| headers.get("x-api-key").and_then(|v| v.to_str().ok()))
+            .ok_or("missing API key (Authorization: Bearer or X-API-Key)")?;
+        // Compare against every key so timing does not reveal which prefix matched.
+        let mut found = None;
+        for k in &self.cfg.keys {
+            if ct_eq(k.secret.as_bytes(), presented.trim().as_bytes()) {
+                found = Some(k);
+            }
+        }
+        found.ok_or_else(|| "invalid API key".into())
+    }
+
+    fn hmac(
+        &self,
+        headers: &HeaderMap,
+        method: &str,
+        path: &str,
+        body: &[u8],
+    ) -> Result<&ApiKey, String> {
+        let header = |name: &str| {
+            headers.get(name).and_then(|v| v.to_str().ok()).ok_or(format!("missing {name} header"))
+        };
+        let id = header(KEY_HEADER)?;
+        let ts = header(TIMESTAMP_HEADER)?;
+        let sig = header(SIGNATURE_HEADER)?;
+
+        let t: u64 = ts.parse().map_err(|_| "invalid timestamp")?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        if now.abs_diff(t) > self.cfg.max_skew.as_secs() {
+            return Err("timestamp outside the accepted window".into());
+        }
+        // An unknown key id is checked against an empty secret and fails the same way as a bad
+        // signature, so callers cannot probe for valid ids.
+        const INVALID: &str = "invalid key or signature";
+        let key = self.key(id);
+        let sig = hex::decode(sig.trim()).map_err(|_| INVALID)?;
+        let secret = key.map_or(&[][..], |k| k.secret.as_bytes());
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+        mac.update(format!("{ts}\n{method}\n{path}\n").as_bytes());
+        mac.update(body);
+        let valid = mac.verify_slice(&sig).is_ok();
+        key.filter(|_| valid).ok_or_else(|| INVALID.into())
+    }
+
+    fn limit(&self, who: &str, key: Option<&ApiKey>) -> Result<(), Duration> {
+        match key.and_then(|k| k.rate_per_min).or(self.cfg.rate_per_min) {
+            Some(per_min) => self.limiter.take(who, per_min),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Sign a request the way [`AuthMode::Hmac`] expects (for clients and tests).
+pub fn sign(secret: &str, timestamp: u64, method: &str, path: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(format!("{timestamp}\n{method}\n{path}\n").as_bytes());
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn unauthorized(msg: String) -> Response {
+    (StatusCode::UNAUTHORIZED, Json(json!({ "error": msg }))).into_response()
+}
+
+/// Middleware: authenticate, then charge the caller's rate-limit bucket.
+pub async fn require(State(app): State<Arc<AppState>>, req: Request, next: Next) -> Response {
+    let guard = &app.auth;
+    let (key, req) = match guard.cfg.mode {
+        AuthMode::None => (None, req),
+        AuthMode::ApiKey => match guard.api_key(req.headers()) {
+            Ok(k) => (Some(k), req),
+            Err(msg) => return unauthorized(msg),
+        },
+        AuthMode::Hmac => {
+            // The signature covers the body, so it is buffered (up to the body limit).
+            let (parts, body) = req.into_parts();
+            let bytes = match to_bytes(body, app.limits.max_bytes).await {
+                Ok(b) => b,
+                Err(_) => {
+                    let msg = format!("body exceeds {} bytes", app.limits.max_bytes);
+                    let body = Json(json!({ "error": msg }));
+                    return (StatusCode::PAYLOAD_TOO_LARGE, body).into_response();
+                }
+            };
+            let path = parts.uri.path_and_query().map_or("/", |p| p.as_str());
+            match guard.hmac(&parts.headers, parts.method.as_str(), path, &bytes) {
+                Ok(k) => (Some(k), Request::from_parts(parts, Body::from(bytes))),
+                Err(msg) => return unauthorized(msg),
+            }
+        }
+    };
+
+    let who = match (key, req.extensions().get::<ClientCert>()) {
+        (Some(k), _) => k.id.clone(),
+        (None, Some(ClientCert(fp))) => format!("cert:{}", &fp[..16.min(fp.len())]),
+        (None, None) => "anonymous".into(),
+    };
+    if let Err(wait) = guard.limit(&who, key) {
+        let secs = wait.as_secs() + 1;
+        let body = Json(json!({ "error": format!("rate limit exceeded for '{who}'") }));
+        return (StatusCode::TOO_MANY_REQUESTS, [(RETRY_AFTER, secs.to_string())], body)
+            .into_response();
+    }
+    next.run(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::to_bytes;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::{app, Config};
+
+    fn server(mode: AuthMode, rate: Option<u32>) -> Router {
+        let keys = parse_keys("# SAP and the CPI tenant\nsap:s3cret, cpi:other:2").unwrap();
+        let auth = AuthConfig { mode, keys, rate_per_min: rate, ..AuthConfig::default() };
+        app(&Config { auth, ..Config::default() })
+    }
+
+    async fn send(app: &Router, req: axum::http::request::Builder, body: &str) -> StatusCode {
+        let req = req.header("content-type", "application/json").body(Body::from(body.to_string()));
+        let res = app.clone().oneshot(req.unwrap()).await.unwrap();
+        let status = res.status();
+        to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        status
+    }
+
+    const BODY: &str = r#"{"expr":"true"}"#;
+
+    #[test]
+    fn keys_parse_with_optional_rate() {
+        let keys = parse_keys("a:x\n  # comment\nb:y:30,").unwrap();
+        assert_eq!(keys.len(), 2);
+        assert_eq!((keys[0].rate_per_min, keys[1].rate_per_min), (None, Some(30)));
+        assert!(parse_keys("nosecret").is_err());
+        assert!(parse_keys("a::30").is_err());
+        assert!(parse_keys("a:b:99999999999").is_err());
+
+        // `:` and `#` belong to the secret; only a trailing `:<digits>` is a rate.
+        let keys = parse_keys("a:p#ss:w0rd, b:x:y:60, c:t1me:12:0").unwrap();
+        let parsed: Vec<_> = keys.iter().map(|k| (k.secret.as_str(), k.rate_per_min)).collect();
+        assert_eq!(parsed, [("p#ss:w0rd", None), ("x:y", Some(60)), ("t1me:12", Some(0))]);
+    }
+
+    #[tokio::test]
+    async fn api_key_from_bearer_or_header() {
+        let app = server(AuthMode::ApiKey, None);
+        let post = || Request::post("/eval");
+        assert_eq!(send(&app, post(), BODY).await, StatusCode::UNAUTHORIZED);
+        let bearer = post().header("authorization", "Bearer s3cret");
+        assert_eq!(send(&app, bearer, BODY).await, StatusCode::OK);
+        assert_eq!(send(&app, post().header("x-api-key", "other"), BODY).await, StatusCode::OK);
+        let wrong = post().header("x-api-key", "s3cre");
+        assert_eq!(send(&app, wrong, BODY).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn hmac_signature_covers_method_path_and_body() {
+        let app = server(AuthMode::Hmac, None);
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let signed = |ts: u64, body: &str| {
+            Request::post("/eval?x=1")
+                .header(KEY_HEADER, "sap")
+                .header(TIMESTAMP_HEADER, ts.to_string())
+                .header(SIGNATURE_HEADER, sign("s3cret", ts, "POST", "/eval?x=1", body.as_bytes()))
+        };
+        assert_eq!(send(&app, signed(now, BODY), BODY).await, StatusCode::OK);
+        let tampered = r#"{"expr":"false"}"#;
+        assert_eq!(send(&app, signed(now, BODY), tampered).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(send(&app, signed(now - 3600, BODY), BODY).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn hmac_unknown_key_and_bad_signature_look_alike() {
+        let app = server(AuthMode::Hmac, None);
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let mut bodies = Vec::new();
+        for (key, secret) in [("nobody", "s3cret"), ("sap", "wrong")] {
+            let req = Request::post("/eval")
+                .header(KEY_HEADER, key)
+                .header(TIMESTAMP_HEADER, now.to_string())
+                .header(SIGNATURE_HEADER, sign(secret, now, "POST", "/eval", BODY.as_bytes()))
+                .body(Body::from(BODY))
+                .unwrap();
+            let res = app.clone().oneshot(req).await.unwrap();
+            assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+            bodies.push(to_bytes(res.into_body(), usize::MAX).await.unwrap());
+        }
+        assert_eq!(bodies[0], bodies[1]);
+    }
+
+    #[tokio::test]
+    async fn rate_limits_are_per_key() {
+        let app = server(AuthMode::ApiKey, Some(100));
+        let as_cpi = || Request::post("/eval").header("x-api-key", "other");
+        assert_eq!(send(&app, as_cpi(), BODY).await, StatusCode::OK);
+        assert_eq!(send(&app, as_cpi(), BODY).await, StatusCode::OK);
+        let res = app.clone().oneshot(as_cpi().body(Body::from(BODY)).unwrap()).await.unwrap();
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(res.headers().contains_key(RETRY_AFTER));
+        // The global rate still applies to the other key.
+        let as_sap = Request::post("/eval").header("x-api-key", "s3cret");
+        assert_eq!(send(&app, as_sap, BODY).await, StatusCode::OK);
+    }
+}
@@ -0,0 +1,396 @@
+//! Bulk evaluation: `POST /eval/batch` and `POST /eval/stream`.
+//!
+//! Both take a JSON array or NDJSON. An item is an object with the `/eval` fields (`expr`,
+//! `expression`, `E`, `mem`, `memory`, `ast`) or a bare expression (a JSON string, or any
+//! NDJSON line that is not JSON). Query parameters `mem`, `ast` and `trace` set defaults for
+//! items that don't say otherwise. Per-item parse errors are reported in that item's result
+//! (`error` + `diagnostics`) and never fail the request.
+//!
+//! `/eval/batch` answers once, in input order: a JSON array for array input, NDJSON for NDJSON
+//! input. `/eval/stream` reads the body incrementally and emits events as lines complete —
+//! `trace` steps (unless `trace=false`), then `result`, per item, and a final `end` — as NDJSON,
+//! or as Server-Sent Events when the client accepts `text/event-stream`. Limits exceeded
+//! mid-stream end the stream with an `error` event.
+
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use axum::body::{Body, Bytes};
+use axum::extract::rejection::BytesRejection;
+use axum::extract::{Query, State};
+use axum::http::header::{ACCEPT, CONTENT_TYPE};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::sse::{Event, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::StreamExt;
+
+use grieg_engine::Logic;
+
+use crate::eval::{self, bounded, EvalRequest};
+use crate::{AppState, Limits};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Defaults {
+    #[serde(default)]
+    mem: bool,
+    #[serde(default)]
+    logic: Logic,
+    #[serde(default)]
+    ast: bool,
+    /// Stream trace steps (stream endpoint only; default on).
+    #[serde(default)]
+    trace: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct ItemIn {
+    #[serde(alias = "expression", alias = "E")]
+    expr: String,
+    #[serde(default, alias = "memory")]
+    mem: Option<bool>,
+    #[serde(default)]
+    logic: Option<Logic>,
+    #[serde(default)]
+    ast: Option<bool>,
+}
+
+fn item_from_json(v: Value, d: &Defaults) -> Result<EvalRequest, String> {
+    match v {
+        Value::String(expr) => Ok(EvalRequest { expr, mem: d.mem, logic: d.logic, ast: d.ast }),
+        v => serde_json::from_value::<ItemIn>(v)
+            .map(|i| EvalRequest {
+                expr: i.expr,
+                mem: i.mem.unwrap_or(d.mem),
+                logic: i.logic.unwrap_or(d.logic),
+                ast: i.ast.unwrap_or(d.ast),
+            })
+            .map_err(|e| format!("invalid item: {e}")),
+    }
+}
+
+fn item_from_line(line: &str, d: &Defaults) -> Result<EvalRequest, String> {
+    match serde_json::from_str::<Value>(line) {
+        Ok(v @ (Value::Object(_) | Value::String(_))) => item_from_json(v, d),
+        _ => Ok(EvalRequest { expr: line.to_string(), mem: d.mem, logic: d.logic, ast: d.ast }),
+    }
+}
+
+/// Result object for an item that could not even be read.
+fn invalid(msg: String) -> Map<String, Value> {
+    let mut m = Map::new();
+    m.insert("input".into(), Value::Null);
+    m.insert("value".into(), Value::Null);
+    m.insert("phase".into(), Value::Null);
+    m.insert("sheet".into(), Value::Null);
+    m.insert("error".into(), Value::String(msg));
+    m
+}
+
+fn evaluate_item(item: Result<EvalRequest, String>, trace: bool, limits: &Limits) -> eval::Outcome {
+    let checked = item.and_then(|req| limits.check_expr(&req.expr).map(|()| req));
+    match checked {
+        Ok(req) => eval::run(&req, trace),
+        Err(msg) => eval::Outcome { body: invalid(msg), parsed: false, trace: Vec::new() },
+    }
+}
+
+fn too_large(msg: String) -> Response {
+    (StatusCode::PAYLOAD_TOO_LARGE, Json(json!({ "error": msg }))).into_response()
+}
+
+fn is_array(body: &[u8]) -> bool {
+    body.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'[')
+}
+
+// ---------------------------------------------------------------------------
+// POST /eval/batch
+// ---------------------------------------------------------------------------
+
+pub async fn batch(
+    State(app): State<Arc<AppState>>,
+    Query(d): Query<Defaults>,
+    body: Result<Bytes, BytesRejection>,
+) -> Response {
+    let limits = &app.limits;
+    let body = match body {
+        Ok(b) => b,
+        Err(e) => return (e.status(), Json(json!({ "error": e.body_text() }))).into_response(),
+    };
+
+    if is_array(&body) {
+        let items: Vec<Value> = match serde_json::from_slice(&body) {
+            Ok(v) => v,
+            Err(e) => {
+                return eval::ApiError::BadRequest(format!("invalid JSON array: {e}"))
+                    .into_response()
+            }
+        };
+        if items.len() > limits.max_items {
+            return too_large(format!("{} items; limit is {}", items.len(), limits.max_items));
+        }
+        let state = app.clone();
+        let run = move || -> Vec<Value> {
+            items
+                .into_iter()
+                .map(|v| item_from_json(v, &d))
+                .map(|item| Value::Object(evaluate_item(item, false, &state.limits).body))
+                .collect()
+        };
+        return match bounded(limits.eval_timeout, run).await {
+            Ok(out) => Json(out).into_response(),
+            Err(e) => e.into_response(),
+        };
+    }
+
+    let Ok(text) = std::str::from_utf8(&body) else {
+        return eval::ApiError::BadRequest("body is not UTF-8".into()).into_response();
+    };
+    let lines: Vec<String> =
+        text.lines().map(str::trim).filter(|l| !l.is_empty()).map(String::from).collect();
+    if lines.len() > limits.max_items {
+        return too_large(format!("{} items; limit is {}", lines.len(), limits.max_items));
+    }
+    let state = app.clone();
+    let run = move || {
+        let mut out = String::new();
+        for line in lines {
+            let o = evaluate_item(item_from_line(&line, &d), false, &state.limits);
+            out.push_str(&Value::Object(o.body).to_string());
+            out.push('\n');
+        }
+        out
+    };
+    match bounded(limits.eval_timeout, run).await {
+        Ok(out) => ([(CONTENT_TYPE, "application/x-ndjson")], out).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// POST /eval/stream
+// ---------------------------------------------------------------------------
+
+/// `(event name, JSON payload)`; framed as NDJSON or SSE by the handler.
+type Emitted = (&'static str, Value);
+
+pub async fn stream(
+    State(app): State<Arc<AppState>>,
+    Query(d): Query<Defaults>,
+    headers: HeaderMap,
+    body: Body,
+) -> Response {
+    let sse = headers
+        .get(ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|a| a.contains("text/event-stream"));
+    let (tx, rx) = mpsc::channel::<Emitted>(64);
+    tokio::spawn(produce(body, d, app, tx));
+
+    let events = ReceiverStream::new(rx);
+    if sse {
+        let events = events.map(|(name, payload)| {
+            Ok::<_, Infallible>(Event::default().event(name).data(payload.to_string()))
+        });
+        return Sse::new(events).into_response();
+    }
+    let lines = events.map(|(name, payload)| {
+        let mut m = Map::new();
+        m.insert("event".into(), Value::String(name.into()));
+        if let Value::Object(fields) = payload {
+            m.extend(fields);
+        }
+        Ok::<_, Infallible>(format!("{}\n", Value::Object(m)))
+    });
+    ([(CONTENT_TYPE, "application/x-ndjson")], Body::from_stream(lines)).into_response()
+}
+
+/// Read the request body chunk by chunk and emit events for every completed item.
+async fn produce(body: Body, d: Defaults, app: Arc<AppState>, tx: mpsc::Sender<Emitted>) {
+    let limits = &app.limits;
+    let trace = d.trace.unwrap_or(true);
+    let mut state = Producer { tx, trace, index: 0, errors: 0, limits: limits.clone() };
+    let mut chunks = body.into_data_stream();
+    let mut buf: Vec<u8> = Vec::new();
+    let mut seen = 0usize;
+    let mut array: Option<bool> = None;
+
+    while let Some(chunk) = chunks.next().await {
+        let chunk = match chunk {
+            Ok(c) => c,
+            Err(e) => return state.fail(format!("body: {e}")).await,
+        };
+        seen += chunk.len();
+        if seen > limits.max_bytes {
+            return state.fail(format!("body exceeds {} bytes", limits.max_bytes)).await;
+        }
+        buf.extend_from_slice(&chunk);
+        if array.is_none() && buf.iter().any(|b| !b.is_ascii_whitespace()) {
+            array = Some(is_array(&buf));
+        }
+        if array != Some(false) {
+            continue; // undecided, or a JSON array (only complete at the end)
+        }
+        while let Some(nl) = buf.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = buf.drain(..=nl).collect();
+            if !state.line(&line, &d).await {
+                return;
+            }
+        }
+    }
+
+    if array == Some(true) {
+        match serde_json::from_slice::<Vec<Value>>(&buf) {
+            Ok(items) => {
+                for v in items {
+                    if !state.item(item_from_json(v, &d)).await {
+                        return;
+                    }
+                }
+            }
+            Err(e) => return state.fail(format!("invalid JSON array: {e}")).await,
+        }
+    } else if !state.line(&buf, &d).await {
+        return;
+    }
+    let end = json!({ "items": state.index, "errors": state.errors });
+    let _ = state.tx.send(("end", end)).await;
+}
+
+struct Producer {
+    tx: mpsc::Sender<Emitted>,
+    trace: bool,
+    index: usize,
+    errors: usize,
+    limits: Limits,
+}
+
+impl Producer {
+    /// Returns `false` once the stream is over (limit hit or client gone).
+    async fn line(&mut self, raw: &[u8], d: &Defaults) -> bool {
+        let line = String::from_utf8_lossy(raw);
+        let line = line.trim();
+        if line.is_empty() {
+            return true;
+        }
+        self.item(item_from_line(line, d)).await
+    }
+
+    async fn item(&mut self, item: Result<EvalRequest, String>) -> bool {
+        if self.index == self.limits.max_items {
+            self.fail(format!("more than {} items", self.limits.max_items)).await;
+            return false;
+        }
+        let (trace, limits) = (self.trace, self.limits.clone());
+        let o =
+            match bounded(limits.eval_timeout, move || evaluate_item(item, trace, &limits)).await {
+                Ok(o) => o,
+                Err(_) => {
+                    let ms = self.limits.eval_timeout.as_millis();
+                    self.fail(format!("item {} exceeded {ms} ms", self.index)).await;
+                    return false;
+                }
+            };
+        for step in &o.trace {
+            let ev = json!({ "index": self.index, "step": step });
+            if self.tx.send(("trace", ev)).await.is_err() {
+                return false;
+            }
+        }
+        if !o.parsed {
+            self.errors += 1;
+        }
+        let mut body = o.body;
+        body.insert("index".into(), json!(self.index));
+        self.index += 1;
+        self.tx.send(("result", Value::Object(body))).await.is_ok()
+    }
+
+    async fn fail(&mut self, msg: String) {
+        let _ = self.tx.send(("error", json!({ "error": msg, "items": self.index }))).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::to_bytes;
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::{app, Config, Limits};
+
+    async fn post(limits: Limits, uri: &str, body: &str) -> (StatusCode, String) {
+        let cfg = Config { limits, ..Config::default() };
+        let req = Request::post(uri).body(Body::from(body.to_string())).unwrap();
+        let res = app(&cfg).oneshot(req).await.unwrap();
+        let status = res.status();
+        let bytes = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    fn phases(lines: &str, event: Option<&str>) -> Vec<String> {
+        lines
+            .lines()
+            .map(|l| serde_json::from_str::<Value>(l).unwrap())
+            .filter(|v| event.is_none() || v["event"] == event.unwrap())
+            .map(|v| v["phase"].as_str().unwrap_or("-").to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn batch_keeps_order_for_array_and_ndjson() {
+        let body = r#"["a", {"expression":"@jam(true)"}, {"E":"x","memory":true}, "a & )", 7]"#;
+        let (status, out) = post(Limits::default(), "/eval/batch?ast=true", body).await;
+        assert_eq!(status, StatusCode::OK);
+        let out: Vec<Value> = serde_json::from_str(&out).unwrap();
+        let got: Vec<&str> = out.iter().map(|v| v["phase"].as_str().unwrap_or("-")).collect();
+        assert_eq!(got, ["VAC", "JAM", "VAC", "-", "-"]);
+        assert_eq!(out[1]["ast"], "(@jam true)");
+        assert_eq!(out[3]["diagnostics"]["col"], 5);
+        assert!(out[4]["error"].as_str().unwrap().starts_with("invalid item"));
+
+        let (_, out) =
+            post(Limits::default(), "/eval/batch", "true\n\n{\"expr\":\"@mem(a)\"}\n").await;
+        assert_eq!(phases(&out, None), ["ALIVE", "MEM"]);
+    }
+
+    #[tokio::test]
+    async fn batch_enforces_limits() {
+        let limits = Limits { max_items: 2, max_bytes: 64, ..Limits::default() };
+        let (status, _) = post(limits.clone(), "/eval/batch", "a\nb\nc\n").await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        let (status, _) = post(limits, "/eval/batch", &"a".repeat(65)).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn stream_emits_trace_then_result_per_item() {
+        let (status, out) = post(Limits::default(), "/eval/stream", "a -> b\n@jam(true)").await;
+        assert_eq!(status, StatusCode::OK);
+        let events: Vec<String> = out
+            .lines()
+            .map(|l| serde_json::from_str::<Value>(l).unwrap()["event"].as_str().unwrap().into())
+            .collect();
+        assert_eq!(
+            events,
+            ["trace", "trace", "trace", "result", "trace", "trace", "result", "end"]
+        );
+        assert_eq!(phases(&out, Some("result")), ["VAC", "JAM"]);
+
+        let (_, out) = post(Limits::default(), "/eval/stream?trace=false", "[\"a\"]").await;
+        assert_eq!(out.lines().count(), 2);
+    }
+
+    #[tokio::test]
+    async fn stream_stops_at_item_limit() {
+        let limits = Limits { max_items: 1, ..Limits::default() };
+        let (_, out) = post(limits, "/eval/stream?trace=false", "true\nfalse\n").await;
+        let last: Value = serde_json::from_str(out.lines().last().unwrap()).unwrap();
+        assert_eq!((last["event"].as_str(), last["items"].as_u64()), (Some("error"), Some(1)));
+    }
+}
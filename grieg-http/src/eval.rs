@@ -0,0 +1,163 @@
+//! In-process evaluation shared by the HTTP handlers.
+//!
+//! Responses use the `grieg-cli --expr` column set (`input`, `ast` when requested, `value`,
+//! `phase`, `sheet`, `error`), so a client can switch between the CLI and the service without
+//! re-mapping fields.
+
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde_json::{json, Map, Value};
+
+use grieg_engine::ast::to_sexpr;
+use grieg_engine::phase::Phase;
+use grieg_engine::trace::TraceStep;
+use grieg_engine::{Evaluator, Logic};
+use grieg_output::{columns, Row};
+use grieg_parser::parse_expr;
+
+use crate::metrics;
+
+/// What a client asked to evaluate.
+#[derive(Debug, Clone, Default)]
+pub struct EvalRequest {
+    pub expr: String,
+    pub mem: bool,
+    pub logic: Logic,
+    pub ast: bool,
+}
+
+/// Errors surfaced as JSON bodies with a 4xx status.
+#[derive(Debug)]
+pub enum ApiError {
+    /// The expression did not parse; carries the CLI-shaped row plus diagnostics.
+    Parse { body: Map<String, Value> },
+    /// Malformed request (bad JSON, missing field).
+    BadRequest(String),
+    /// An expression over the size or nesting limit (413).
+    TooLarge(String),
+    /// Evaluation did not finish within the configured budget (503).
+    Timeout(Duration),
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, msg) = match self {
+            ApiError::Parse { body } => {
+                return (StatusCode::BAD_REQUEST, Json(body)).into_response()
+            }
+            ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            ApiError::TooLarge(msg) => (StatusCode::PAYLOAD_TOO_LARGE, msg),
+            ApiError::Timeout(t) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                format!("evaluation exceeded {} ms", t.as_millis()),
+            ),
+        };
+        (status, Json(json!({ "error": msg }))).into_response()
+    }
+}
+
+/// Run blocking evaluation work off the async workers, giving up after `timeout`.
+///
+/// The engine cannot be interrupted, so a timed-out closure still runs to completion on its
+/// blocking thread; the client just stops waiting for it. Size and nesting limits keep that
+/// bounded.
+pub async fn bounded<T, F>(timeout: Duration, f: F) -> Result<T, ApiError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    match tokio::time::timeout(timeout, tokio::task::spawn_blocking(f)).await {
+        Ok(Ok(v)) => Ok(v),
+        Ok(Err(e)) => std::panic::resume_unwind(e.into_panic()),
+        Err(_) => Err(ApiError::Timeout(timeout)),
+    }
+}
+
+fn phase_to_str(p: &Phase) -> &'static str {
+    match p {
+        Phase::ALIVE => "ALIVE",
+        Phase::JAM => "JAM",
+        Phase::MEM => "MEM",
+        Phase::VAC => "VAC",
+    }
+}
+
+/// One evaluated item: the response object, whether it parsed, and its trace (if asked for).
+#[derive(Debug)]
+pub struct Outcome {
+    pub body: Map<String, Value>,
+    pub parsed: bool,
+    pub trace: Vec<TraceStep>,
+}
+
+/// Parse and evaluate one expression with a fresh evaluator.
+pub fn evaluate(req: &EvalRequest) -> Result<Map<String, Value>, ApiError> {
+    let o = run(req, false);
+    if o.parsed {
+        Ok(o.body)
+    } else {
+        Err(ApiError::Parse { body: o.body })
+    }
+}
+
+/// Like [`evaluate`], but parse errors stay in the body (`error` + `diagnostics`) and the
+/// per-node trace is collected when `trace` is set.
+pub fn run(req: &EvalRequest, trace: bool) -> Outcome {
+    run_with(&mut Evaluator::new(req.mem), req, trace)
+}
+
+/// [`run`] against an existing evaluator (a session's MEM store); `req.mem` is ignored.
+pub fn run_with(ev: &mut Evaluator, req: &EvalRequest, trace: bool) -> Outcome {
+    ev.logic = req.logic;
+    let cols = columns(req.ast, false);
+    let mut row = Row { input: Some(req.expr.clone()), ..Row::default() };
+    match parse_expr(&req.expr) {
+        Ok(ast) => {
+            let (res, steps) =
+                if trace { ev.eval_traced(&ast) } else { (ev.eval(&ast, None), Vec::new()) };
+            row.ast = req.ast.then(|| to_sexpr(&ast));
+            row.value = grieg_output::value(&res.value);
+            row.phase = Some(phase_to_str(&res.phase).to_string());
+            row.sheet = Some(res.sheet.to_string());
+            metrics::global().record_eval(Some(res.phase));
+            let mut body = row.to_object(&cols);
+            if !res.conflicts.is_empty() {
+                body.insert("conflicts".into(), json!(res.conflicts));
+            }
+            if !res.witnesses.is_empty() {
+                body.insert("witnesses".into(), json!(res.witnesses));
+            }
+            if !res.expired.is_empty() {
+                body.insert("expired".into(), json!(res.expired));
+            }
+            Outcome { body, parsed: true, trace: steps }
+        }
+        Err(e) => {
+            metrics::global().record_eval(None);
+            let (line, col) = e.line_col();
+            row.error = Some(format!("parse: {e}"));
+            let mut body = row.to_object(&cols);
+            body.insert(
+                "diagnostics".into(),
+                json!({ "line": line, "col": col, "message": e.to_string() }),
+            );
+            Outcome { body, parsed: false, trace: Vec::new() }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn bounded_gives_up_after_timeout() {
+        let slow = || std::thread::sleep(Duration::from_millis(200));
+        let err = bounded(Duration::from_millis(10), slow).await.unwrap_err();
+        assert!(matches!(err, ApiError::Timeout(_)));
+        assert_eq!(bounded(Duration::from_secs(5), || 7).await.unwrap(), 7);
+    }
+}
@@ -0,0 +1,358 @@
+//! HTTP adapter for the Grieg engine.
+//!
+//! Requests are parsed and evaluated in-process; see [`eval`] for the response shape and
+//! [`sap`] for the `/eval` contract used by the SAP pack and [`batch`] for bulk requests.
+//! [`auth`] and [`tls`] cover authentication, rate limits and HTTPS; [`ops`], [`metrics`] and
+//! [`openapi`] the operational endpoints.
+
+pub mod auth;
+pub mod batch;
+pub mod eval;
+pub mod metrics;
+pub mod openapi;
+pub mod ops;
+pub mod sap;
+pub mod session;
+pub mod tls;
+
+use std::env;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{DefaultBodyLimit, State};
+use axum::middleware;
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use grieg_engine::Logic;
+
+use crate::eval::{bounded, evaluate, ApiError, EvalRequest};
+
+#[derive(Deserialize)]
+struct EvaluateIn {
+    #[serde(alias = "expr")]
+    prompt: String,
+    #[serde(default)]
+    mem: bool,
+    #[serde(default)]
+    logic: Logic,
+    #[serde(default)]
+    ast: bool,
+}
+
+/// Per-request limits.
+#[derive(Debug, Clone)]
+pub struct Limits {
+    /// Items per `/eval/batch` or `/eval/stream` request.
+    pub max_items: usize,
+    /// Request body size, for every endpoint.
+    pub max_bytes: usize,
+    /// Size of a single expression.
+    pub max_expr_bytes: usize,
+    /// Operators plus bracket depth of a single expression. Bounds both the parser's recursion
+    /// and the depth of the AST, so a hostile expression cannot overflow the stack.
+    pub max_nesting: usize,
+    /// Wall-clock budget for evaluating one request (a whole batch, or one streamed item).
+    pub eval_timeout: Duration,
+    /// MEM sessions live at once; `POST /sessions` answers 503 beyond it.
+    pub max_sessions: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_items: 10_000,
+            max_bytes: 8 << 20,
+            max_expr_bytes: 64 << 10,
+            max_nesting: 256,
+            eval_timeout: Duration::from_secs(5),
+            max_sessions: 10_000,
+        }
+    }
+}
+
+impl Limits {
+    /// Reject an expression before it reaches the parser.
+    pub fn check_expr(&self, expr: &str) -> Result<(), String> {
+        if expr.len() > self.max_expr_bytes {
+            let n = expr.len();
+            return Err(format!("expression is {n} bytes; limit is {}", self.max_expr_bytes));
+        }
+        let (mut depth, mut max_depth, mut ops) = (0usize, 0usize, 0usize);
+        let mut prev = '\0';
+        for c in expr.chars() {
+            match c {
+                '(' => {
+                    depth += 1;
+                    max_depth = max_depth.max(depth);
+                }
+                ')' => depth = depth.saturating_sub(1),
+                '&' | '|' | '~' | '@' => ops += 1,
+                '>' if prev == '-' => ops += 1,
+                _ => {}
+            }
+            prev = c;
+        }
+        if ops + max_depth > self.max_nesting {
+            let n = ops + max_depth;
+            return Err(format!("expression nesting is {n}; limit is {}", self.max_nesting));
+        }
+        Ok(())
+    }
+}
+
+/// Server settings. The listen address defaults to `127.0.0.1:8077`, the target of the SAP
+/// pack and the Postman collection. Environment overrides: `GRIEG_HTTP_BIND`,
+/// `GRIEG_HTTP_PORT`, `GRIEG_HTTP_MAX_ITEMS`, `GRIEG_HTTP_MAX_BYTES`,
+/// `GRIEG_HTTP_MAX_EXPR_BYTES`, `GRIEG_HTTP_MAX_NESTING`, `GRIEG_HTTP_EVAL_TIMEOUT_MS`,
+/// `GRIEG_HTTP_MAX_SESSIONS`, `GRIEG_HTTP_SESSION_TTL_S`, `GRIEG_HTTP_DRAIN_TIMEOUT_S`, plus the variables read by
+/// [`auth`] and [`tls`].
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub bind: String,
+    pub port: u16,
+    pub limits: Limits,
+    /// Idle time after which a MEM session expires.
+    pub session_ttl: Duration,
+    /// How long a SIGTERM waits for in-flight requests before exiting anyway.
+    pub drain_timeout: Duration,
+    pub auth: auth::AuthConfig,
+    /// Serve HTTPS instead of HTTP.
+    pub tls: Option<tls::TlsConfig>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            bind: "127.0.0.1".into(),
+            port: 8077,
+            limits: Limits::default(),
+            session_ttl: Duration::from_secs(900),
+            drain_timeout: Duration::from_secs(30),
+            auth: auth::AuthConfig::default(),
+            tls: None,
+        }
+    }
+}
+
+/// Shared by all handlers.
+pub struct AppState {
+    pub limits: Limits,
+    pub sessions: session::SessionStore,
+    pub auth: auth::Guard,
+    /// Set once shutdown has begun; `/readyz` then answers 503.
+    pub draining: AtomicBool,
+    pub openapi: Value,
+}
+
+impl Config {
+    pub fn from_env() -> Result<Self, String> {
+        let mut c = Config::default();
+        if let Ok(b) = env::var("GRIEG_HTTP_BIND") {
+            c.bind = b;
+        }
+        if let Ok(p) = env::var("GRIEG_HTTP_PORT") {
+            c.port = p.parse().map_err(|_| format!("GRIEG_HTTP_PORT: invalid port '{p}'"))?;
+        }
+        for (var, slot) in [
+            ("GRIEG_HTTP_MAX_ITEMS", &mut c.limits.max_items),
+            ("GRIEG_HTTP_MAX_BYTES", &mut c.limits.max_bytes),
+            ("GRIEG_HTTP_MAX_EXPR_BYTES", &mut c.limits.max_expr_bytes),
+            ("GRIEG_HTTP_MAX_NESTING", &mut c.limits.max_nesting),
+            ("GRIEG_HTTP_MAX_SESSIONS", &mut c.limits.max_sessions),
+        ] {
+            if let Ok(v) = env::var(var) {
+                *slot = v.parse().map_err(|_| format!("{var}: invalid number '{v}'"))?;
+            }
+        }
+        if let Ok(v) = env::var("GRIEG_HTTP_EVAL_TIMEOUT_MS") {
+            let ms = v.parse().map_err(|_| format!("GRIEG_HTTP_EVAL_TIMEOUT_MS: invalid '{v}'"))?;
+            c.limits.eval_timeout = Duration::from_millis(ms);
+        }
+        for (var, slot) in [
+            ("GRIEG_HTTP_SESSION_TTL_S", &mut c.session_ttl),
+            ("GRIEG_HTTP_DRAIN_TIMEOUT_S", &mut c.drain_timeout),
+        ] {
+            if let Ok(v) = env::var(var) {
+                let secs = v.parse().map_err(|_| format!("{var}: invalid '{v}'"))?;
+                *slot = Duration::from_secs(secs);
+            }
+        }
+
+        if let Ok(v) = env::var("GRIEG_HTTP_AUTH") {
+            c.auth.mode = v.parse()?;
+        }
+        if let Ok(v) = env::var("GRIEG_HTTP_API_KEYS") {
+            c.auth.keys.extend(auth::parse_keys(&v)?);
+        }
+        if let Ok(path) = env::var("GRIEG_HTTP_API_KEYS_FILE") {
+            let text = std::fs::read_to_string(&path).map_err(|e| format!("{path}: {e}"))?;
+            c.auth.keys.extend(auth::parse_keys(&text).map_err(|e| format!("{path}: {e}"))?);
+        }
+        if let Ok(v) = env::var("GRIEG_HTTP_RATE_PER_MIN") {
+            let n = v.parse().map_err(|_| format!("GRIEG_HTTP_RATE_PER_MIN: invalid '{v}'"))?;
+            c.auth.rate_per_min = Some(n);
+        }
+        if let Ok(v) = env::var("GRIEG_HTTP_HMAC_SKEW_S") {
+            let secs = v.parse().map_err(|_| format!("GRIEG_HTTP_HMAC_SKEW_S: invalid '{v}'"))?;
+            c.auth.max_skew = Duration::from_secs(secs);
+        }
+
+        let path = |var| env::var(var).ok().map(PathBuf::from);
+        match (path("GRIEG_HTTP_TLS_CERT"), path("GRIEG_HTTP_TLS_KEY")) {
+            (Some(cert), Some(key)) => {
+                let client_ca = path("GRIEG_HTTP_TLS_CLIENT_CA");
+                c.tls = Some(tls::TlsConfig { cert, key, client_ca });
+            }
+            (None, None) => {}
+            _ => return Err("GRIEG_HTTP_TLS_CERT and GRIEG_HTTP_TLS_KEY go together".into()),
+        }
+        Ok(c)
+    }
+
+    /// Settings that cannot work together.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.auth.mode != auth::AuthMode::None && self.auth.keys.is_empty() {
+            return Err("authentication is on but no API keys are configured".into());
+        }
+        Ok(())
+    }
+}
+
+/// Router with default settings.
+pub fn router() -> Router {
+    app(&Config::default())
+}
+
+pub fn app(cfg: &Config) -> Router {
+    build(cfg).0
+}
+
+/// The router and its state; the server keeps the state to flag draining on shutdown.
+///
+/// Operational endpoints (`/healthz`, `/readyz`, `/metrics`, `/openapi.json`) bypass
+/// authentication and are not counted in the request metrics.
+pub fn build(cfg: &Config) -> (Router, Arc<AppState>) {
+    let state = Arc::new(AppState {
+        limits: cfg.limits.clone(),
+        sessions: session::SessionStore::new(cfg.session_ttl, cfg.limits.max_sessions),
+        auth: auth::Guard::new(cfg.auth.clone()),
+        draining: AtomicBool::new(false),
+        openapi: openapi::document(cfg),
+    });
+    let router = Router::new()
+        .route("/eval", post(sap::eval))
+        .route("/eval/batch", post(batch::batch))
+        .route("/eval/stream", post(batch::stream))
+        .route("/sessions", post(session::create))
+        .route("/sessions/:id", delete(session::close))
+        .route("/sessions/:id/eval", post(session::eval))
+        .route("/sessions/:id/facts", get(session::get_facts).post(session::assert_facts))
+        .route("/sessions/:id/facts/:name", get(session::history).delete(session::retract_fact))
+        .route("/sessions/:id/consistency", get(session::consistency))
+        .route("/sessions/:id/what-if", post(session::what_if))
+        .route("/api/v1/evaluate", post(evaluate_v1))
+        .layer(DefaultBodyLimit::max(cfg.limits.max_bytes))
+        .layer(middleware::from_fn_with_state(state.clone(), auth::require))
+        .layer(middleware::from_fn(metrics::track))
+        .route("/healthz", get(ops::healthz))
+        .route("/readyz", get(ops::readyz))
+        .route("/metrics", get(metrics::handler))
+        .route("/openapi.json", get(openapi::handler))
+        .with_state(state.clone());
+    (router, state)
+}
+
+async fn evaluate_v1(
+    State(app): State<Arc<AppState>>,
+    body: Result<Json<EvaluateIn>, JsonRejection>,
+) -> Result<Json<Map<String, Value>>, ApiError> {
+    let Json(inp) = body.map_err(|e| ApiError::BadRequest(e.body_text()))?;
+    let req = EvalRequest { expr: inp.prompt, mem: inp.mem, logic: inp.logic, ast: inp.ast };
+    app.limits.check_expr(&req.expr).map_err(ApiError::TooLarge)?;
+    bounded(app.limits.eval_timeout, move || evaluate(&req)).await?.map(Json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Request, StatusCode};
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    async fn post_json(uri: &str, body: &str) -> (StatusCode, Value) {
+        let req = Request::post(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let res = router().oneshot(req).await.unwrap();
+        let status = res.status();
+        let bytes = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn evaluates_in_process_with_cli_shape() {
+        let (status, v) =
+            post_json("/api/v1/evaluate", r#"{"prompt":"@mem(true -> false)","mem":true}"#).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            v,
+            serde_json::json!({
+                "input": "@mem(true -> false)", "value": false, "phase": "MEM", "sheet": "F",
+                "error": null
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn logic_is_chosen_per_request() {
+        let (_, v) = post_json("/api/v1/evaluate", r#"{"prompt":"x -> x"}"#).await;
+        assert_eq!(v["value"], Value::Null);
+        let (_, v) = post_json("/api/v1/evaluate", r#"{"prompt":"x -> x","logic":"l3"}"#).await;
+        assert_eq!(v["value"], true);
+        let (_, v) = post_json("/api/v1/evaluate", r#"{"prompt":"@jam(a)","logic":"four"}"#).await;
+        assert_eq!((&v["value"], &v["phase"]), (&json!("both"), &json!("JAM")));
+        let (status, _) = post_json("/api/v1/evaluate", r#"{"prompt":"a","logic":"fuzzy"}"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn parse_errors_are_400_with_diagnostics() {
+        let (status, v) = post_json("/api/v1/evaluate", r#"{"prompt":"a & )"}"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(v["phase"], Value::Null);
+        assert_eq!(
+            (v["diagnostics"]["line"].as_u64(), v["diagnostics"]["col"].as_u64()),
+            (Some(1), Some(5))
+        );
+
+        let (status, v) = post_json("/api/v1/evaluate", r#"{"mem":true}"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(v["error"].as_str().unwrap().contains("prompt"));
+    }
+
+    #[tokio::test]
+    async fn oversized_and_deep_expressions_are_413() {
+        let limits = Limits::default();
+        assert!(limits.check_expr(&"a".repeat(limits.max_expr_bytes + 1)).is_err());
+        assert!(limits.check_expr(&"~".repeat(limits.max_nesting + 1)).is_err());
+        assert!(limits.check_expr("a -> (b & ~c)").is_ok());
+
+        let deep = format!("{}a{}", "(".repeat(5000), ")".repeat(5000));
+        let (status, v) = post_json("/eval", &json!({ "expr": deep }).to_string()).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(v["error"].as_str().unwrap().contains("nesting"));
+
+        let n = limits.max_nesting;
+        let at_limit = format!("{}a{}", "(".repeat(n), ")".repeat(n));
+        let (status, v) = post_json("/eval", &json!({ "expr": at_limit }).to_string()).await;
+        assert_eq!((status, v["phase"].as_str()), (StatusCode::OK, Some("VAC")));
+    }
+}
@@ -1,93 +1,99 @@
-use axum::{routing::post, Json, Router};
-use serde::Deserialize;
-use serde_json::{json, Value};
 use std::env;
-use std::process::Stdio;
-use tokio::io::AsyncWriteExt;
-use tokio::process::Command;
+use std::path::PathBuf;
+use std::process::exit;
+use std::sync::atomic::Ordering;
 
-#[derive(Deserialize)]
-struct EvalIn {
-    prompt: String,
-    mem: Option<bool>,
-    ast: Option<bool>,
-}
-
-#[tokio::main]
-async fn main() {
-    let app = Router::new().route("/api/v1/evaluate", post(evaluate));
+use tokio::sync::oneshot;
 
-    // Bind with a tuple to avoid type-annotation issues
-    let listener = tokio::net::TcpListener::bind(("127.0.0.1", 8000))
-        .await
-        .expect("bind 127.0.0.1:8000");
-    println!("grieg-http listening on {}", listener.local_addr().unwrap());
+use grieg_http::tls::TlsConfig;
+use grieg_http::Config;
 
-    axum::serve(listener, app).await.unwrap();
+fn usage() -> ! {
+    eprintln!(
+        "Usage: grieg-http [--bind <ADDR>] [--port <PORT>]\n\
+         \x20                 [--tls-cert <PEM> --tls-key <PEM> [--tls-client-ca <PEM>]]\n\
+         Defaults: 127.0.0.1:8077 (env GRIEG_HTTP_BIND / GRIEG_HTTP_PORT)\n\
+         Auth: GRIEG_HTTP_AUTH=none|api-key|hmac, GRIEG_HTTP_API_KEYS[_FILE],\n\
+         \x20     GRIEG_HTTP_RATE_PER_MIN"
+    );
+    exit(2);
 }
 
-async fn evaluate(Json(inp): Json<EvalIn>) -> Json<Value> {
-    match run_cli(&inp.prompt).await {
-        Ok(v) => Json(v),
-        Err(e) => Json(json!({ "rc": 1, "error": e })),
-    }
+fn fail(msg: String) -> ! {
+    eprintln!("{msg}");
+    exit(2);
 }
 
-/// Calls the real Rust CLI. You can override with env vars:
-///   GRIEG_CLI_CMD  (default: "grieg-cli")
-///   GRIEG_CLI_ARGS (default: empty; space-separated)
-async fn run_cli(prompt: &str) -> Result<Value, String> {
-    let cmd = env::var("GRIEG_CLI_CMD").unwrap_or_else(|_| "grieg-cli".into());
-    let extra = env::var("GRIEG_CLI_ARGS").unwrap_or_default();
-    let extra_parts: Vec<&str> =
-        if extra.trim().is_empty() { vec![] } else { extra.split_whitespace().collect() };
-
-    // Try common layouts; adjust later if your CLI differs
-    let layouts: Vec<Vec<String>> = vec![
-        vec!["eval".into(), "--json".into(), prompt.into()],
-        vec!["--json".into(), "eval".into(), prompt.into()],
-        vec!["eval".into(), prompt.into()],
-        vec![prompt.into()],
-    ];
-
-    for args in layouts {
-        let mut full: Vec<&str> = extra_parts.clone();
-        for s in &args { full.push(s.as_str()); }
-        if let Ok(out) = capture(&cmd, &full, None).await {
-            if !out.status.success() { continue; }
-            if let Ok(js) = serde_json::from_slice::<Value>(&out.stdout) { return Ok(js); }
-            let text = String::from_utf8_lossy(&out.stdout).trim().to_string();
-            return Ok(json!({"rc":0,"text":text}));
+fn main() {
+    let mut cfg = Config::from_env().unwrap_or_else(|e| fail(e));
+    let (mut cert, mut key, mut client_ca) = (None, None, None);
+    let mut args = env::args().skip(1);
+    while let Some(a) = args.next() {
+        match (a.as_str(), args.next()) {
+            ("--bind", Some(v)) => cfg.bind = v,
+            ("--port", Some(v)) => cfg.port = v.parse().unwrap_or_else(|_| usage()),
+            ("--tls-cert", Some(v)) => cert = Some(PathBuf::from(v)),
+            ("--tls-key", Some(v)) => key = Some(PathBuf::from(v)),
+            ("--tls-client-ca", Some(v)) => client_ca = Some(PathBuf::from(v)),
+            _ => usage(),
         }
     }
-
-    // stdin fallback (no args; prompt via stdin)
-    let out = capture(&cmd, &extra_parts, Some(prompt)).await?;
-    if !out.status.success() {
-        return Err(format!(
-            "cli exit {}: {}",
-            out.status.code().unwrap_or(-1),
-            String::from_utf8_lossy(&out.stderr)
-        ));
-    }
-    if let Ok(js) = serde_json::from_slice::<Value>(&out.stdout) { Ok(js) } else {
-        let text = String::from_utf8_lossy(&out.stdout).trim().to_string();
-        Ok(json!({"rc":0,"text":text}))
+    match (cert, key) {
+        (Some(cert), Some(key)) => cfg.tls = Some(TlsConfig { cert, key, client_ca }),
+        (None, None) if client_ca.is_none() => {}
+        (None, None) => match cfg.tls.as_mut() {
+            Some(t) => t.client_ca = client_ca,
+            None => fail("--tls-client-ca needs --tls-cert and --tls-key".into()),
+        },
+        _ => fail("--tls-cert and --tls-key go together".into()),
     }
+    cfg.validate().unwrap_or_else(|e| fail(e));
+
+    // Parsing and evaluation recurse; give worker and blocking threads room for
+    // GRIEG_HTTP_MAX_NESTING above the default.
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .thread_stack_size(8 << 20)
+        .build()
+        .expect("tokio runtime")
+        .block_on(serve(cfg));
 }
 
-struct Capt { status: std::process::ExitStatus, stdout: Vec<u8>, stderr: Vec<u8> }
+async fn serve(cfg: Config) {
+    let tls = cfg.tls.as_ref().map(|t| t.server_config().unwrap_or_else(|e| fail(e)));
 
-async fn capture(cmd: &str, args: &[&str], stdin_text: Option<&str>) -> Result<Capt, String> {
-    let mut c = Command::new(cmd);
-    c.args(args).stdout(Stdio::piped()).stderr(Stdio::piped());
-    if stdin_text.is_some() { c.stdin(Stdio::piped()); }
-    let mut child = c.spawn().map_err(|e| format!("spawn {cmd}: {e}"))?;
-    if let Some(text) = stdin_text {
-        if let Some(mut stdin) = child.stdin.take() {
-            stdin.write_all(text.as_bytes()).await.map_err(|e| e.to_string())?;
+    // Bind with a tuple to avoid type-annotation issues
+    let listener = tokio::net::TcpListener::bind((cfg.bind.as_str(), cfg.port))
+        .await
+        .unwrap_or_else(|e| panic!("bind {}:{}: {e}", cfg.bind, cfg.port));
+    let scheme = if tls.is_some() { "https" } else { "http" };
+    println!("grieg-http listening on {scheme}://{}", listener.local_addr().unwrap());
+
+    // On SIGTERM: fail /readyz, stop accepting, let in-flight requests finish, but never wait
+    // longer than the drain timeout.
+    let (app, state) = grieg_http::build(&cfg);
+    let (signalled, drain_started) = oneshot::channel::<()>();
+    let shutdown = async move {
+        grieg_http::ops::shutdown_signal().await;
+        eprintln!("grieg-http: draining (up to {} s)", cfg.drain_timeout.as_secs());
+        state.draining.store(true, Ordering::Relaxed);
+        let _ = signalled.send(());
+    };
+    let server = async {
+        match tls {
+            Some(tls) => grieg_http::tls::serve(listener, app, tls, shutdown).await,
+            None => axum::serve(listener, app).with_graceful_shutdown(shutdown).await,
+        }
+    };
+    let deadline = async {
+        if drain_started.await.is_ok() {
+            tokio::time::sleep(cfg.drain_timeout).await;
+        } else {
+            std::future::pending::<()>().await;
         }
+    };
+    tokio::select! {
+        r = server => r.unwrap(),
+        _ = deadline => eprintln!("grieg-http: drain timeout; exiting with requests in flight"),
     }
-    let out = child.wait_with_output().await.map_err(|e| e.to_string())?;
-    Ok(Capt{ status: out.status, stdout: out.stdout, stderr: out.stderr })
 }
@@ -0,0 +1,159 @@
+//! Prometheus metrics (text exposition format 0.0.4) for `GET /metrics`.
+//!
+//! * `grieg_http_requests_total{method,route,status}` — answered requests.
+//! * `grieg_http_request_duration_seconds{route}` — latency histogram, measured until the
+//!   response head is ready (for `/eval/stream` that is before the first event).
+//! * `grieg_http_requests_in_flight` — requests being handled.
+//! * `grieg_http_eval_results_total{phase}` — evaluated expressions per result phase, from
+//!   every endpoint; `grieg_http_eval_errors_total` counts expressions that did not parse.
+//! * `grieg_http_sessions` — live MEM sessions.
+//!
+//! Counters are process-wide, like the evaluator they describe.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::header::CONTENT_TYPE;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+use grieg_engine::phase::Phase;
+
+use crate::AppState;
+
+/// Upper bounds (seconds) of the latency buckets.
+const BUCKETS: [f64; 12] = [0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+#[derive(Default)]
+struct Histogram {
+    counts: [u64; BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+#[derive(Default)]
+pub struct Metrics {
+    requests: Mutex<BTreeMap<(String, String, u16), u64>>,
+    latency: Mutex<BTreeMap<String, Histogram>>,
+    in_flight: AtomicU64,
+    /// ALIVE, JAM, MEM, VAC.
+    phases: [AtomicU64; 4],
+    eval_errors: AtomicU64,
+}
+
+const PHASES: [(Phase, &str); 4] =
+    [(Phase::ALIVE, "ALIVE"), (Phase::JAM, "JAM"), (Phase::MEM, "MEM"), (Phase::VAC, "VAC")];
+
+/// The process-wide registry.
+pub fn global() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(Metrics::default)
+}
+
+impl Metrics {
+    /// Count one evaluation result; `None` for an expression that did not parse.
+    pub fn record_eval(&self, phase: Option<Phase>) {
+        match phase.and_then(|p| PHASES.iter().position(|(q, _)| *q == p)) {
+            Some(i) => self.phases[i].fetch_add(1, Ordering::Relaxed),
+            None => self.eval_errors.fetch_add(1, Ordering::Relaxed),
+        };
+    }
+
+    fn record_request(&self, method: &str, route: &str, status: u16, secs: f64) {
+        let key = (method.to_string(), route.to_string(), status);
+        *self.requests.lock().unwrap().entry(key).or_default() += 1;
+        let mut latency = self.latency.lock().unwrap();
+        let h = latency.entry(route.to_string()).or_default();
+        for (i, le) in BUCKETS.iter().enumerate() {
+            if secs <= *le {
+                h.counts[i] += 1;
+            }
+        }
+        h.count += 1;
+        h.sum += secs;
+    }
+
+    pub fn phase_count(&self, phase: Phase) -> u64 {
+        let i = PHASES.iter().position(|(q, _)| *q == phase).unwrap();
+        self.phases[i].load(Ordering::Relaxed)
+    }
+
+    /// Render in the Prometheus text format.
+    pub fn render(&self, sessions: usize) -> String {
+        let mut out = String::new();
+        out.push_str("# HELP grieg_http_requests_total HTTP requests answered.\n");
+        out.push_str("# TYPE grieg_http_requests_total counter\n");
+        for ((method, route, status), n) in self.requests.lock().unwrap().iter() {
+            let labels = format!("method=\"{method}\",route=\"{route}\",status=\"{status}\"");
+            let _ = writeln!(out, "grieg_http_requests_total{{{labels}}} {n}");
+        }
+
+        out.push_str("# HELP grieg_http_request_duration_seconds Time to response head.\n");
+        out.push_str("# TYPE grieg_http_request_duration_seconds histogram\n");
+        for (route, h) in self.latency.lock().unwrap().iter() {
+            let name = "grieg_http_request_duration_seconds";
+            for (le, n) in BUCKETS.iter().zip(h.counts) {
+                let _ = writeln!(out, "{name}_bucket{{route=\"{route}\",le=\"{le}\"}} {n}");
+            }
+            let _ = writeln!(out, "{name}_bucket{{route=\"{route}\",le=\"+Inf\"}} {}", h.count);
+            let _ = writeln!(out, "{name}_sum{{route=\"{route}\"}} {}", h.sum);
+            let _ = writeln!(out, "{name}_count{{route=\"{route}\"}} {}", h.count);
+        }
+
+        out.push_str("# HELP grieg_http_requests_in_flight Requests being handled.\n");
+        out.push_str("# TYPE grieg_http_requests_in_flight gauge\n");
+        let in_flight = self.in_flight.load(Ordering::Relaxed);
+        let _ = writeln!(out, "grieg_http_requests_in_flight {in_flight}");
+
+        out.push_str("# HELP grieg_http_eval_results_total Evaluated expressions by phase.\n");
+        out.push_str("# TYPE grieg_http_eval_results_total counter\n");
+        for (i, (_, name)) in PHASES.iter().enumerate() {
+            let n = self.phases[i].load(Ordering::Relaxed);
+            let _ = writeln!(out, "grieg_http_eval_results_total{{phase=\"{name}\"}} {n}");
+        }
+        out.push_str("# HELP grieg_http_eval_errors_total Expressions that did not parse.\n");
+        out.push_str("# TYPE grieg_http_eval_errors_total counter\n");
+        let errors = self.eval_errors.load(Ordering::Relaxed);
+        let _ = writeln!(out, "grieg_http_eval_errors_total {errors}");
+
+        out.push_str("# HELP grieg_http_sessions Live MEM sessions.\n");
+        out.push_str("# TYPE grieg_http_sessions gauge\n");
+        let _ = writeln!(out, "grieg_http_sessions {sessions}");
+        out
+    }
+}
+
+/// Decrements the in-flight gauge even when the client goes away mid-request.
+struct InFlight;
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        global().in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Middleware: count and time every request by its route template.
+pub async fn track(req: Request, next: Next) -> Response {
+    let m = global();
+    let method = req.method().to_string();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| "unmatched".to_string(), |p| p.as_str().to_string());
+    let start = Instant::now();
+    m.in_flight.fetch_add(1, Ordering::Relaxed);
+    let guard = InFlight;
+    let res = next.run(req).await;
+    drop(guard);
+    m.record_request(&method, &route, res.status().as_u16(), start.elapsed().as_secs_f64());
+    res
+}
+
+pub async fn handler(State(app): State<Arc<AppState>>) -> Response {
+    let body = global().render(app.sessions.len());
+    ([(CONTENT_TYPE, "text/plain; version=0.0.4")], body).into_response()
+}
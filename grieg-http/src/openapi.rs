@@ -0,0 +1,689 @@
+//! OpenAPI 3 description of the service, served at `GET /openapi.json`.
+//!
+//! The document is built from [`OPERATIONS`] and the running [`Config`]: the server URL follows
+//! the bind address and TLS setting, and a security requirement is added when authentication is
+//! on. A test walks [`OPERATIONS`] against the router so the two stay in step.
+
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::Json;
+use serde_json::{json, Map, Value};
+
+use crate::auth::AuthMode;
+use crate::{AppState, Config};
+
+/// One documented operation.
+pub struct Operation {
+    pub method: &'static str,
+    pub path: &'static str,
+    pub id: &'static str,
+    pub summary: &'static str,
+    pub tag: &'static str,
+    /// `(media type, schema name)` of the request body.
+    pub request: Option<(&'static str, &'static str)>,
+    /// `(status, description, media type, schema name)`.
+    pub responses: &'static [Response],
+    /// Also answers with the evaluation errors ([`EVAL_ERRORS`]).
+    pub evaluates: bool,
+    /// Served without authentication.
+    pub public: bool,
+}
+
+const JSON: &str = "application/json";
+const NDJSON: &str = "application/x-ndjson";
+const TEXT: &str = "text/plain";
+
+type Response = (u16, &'static str, &'static str, &'static str);
+
+const EVAL_ERRORS: [Response; 4] = [
+    (400, "Parse error or malformed request", JSON, "EvalResult"),
+    (413, "Expression over the size or nesting limit", JSON, "Error"),
+    (429, "Rate limit exceeded", JSON, "Error"),
+    (503, "Evaluation timed out", JSON, "Error"),
+];
+
+pub const OPERATIONS: &[Operation] = &[
+    Operation {
+        method: "post",
+        path: "/eval",
+        id: "eval",
+        summary: "Evaluate one expression (SAP contract; `value` is a string)",
+        tag: "eval",
+        request: Some((JSON, "SapEvalRequest")),
+        responses: &[(200, "Result", JSON, "SapEvalResult")],
+        evaluates: true,
+        public: false,
+    },
+    Operation {
+        method: "post",
+        path: "/eval/batch",
+        id: "evalBatch",
+        summary: "Evaluate a JSON array or NDJSON of items, in order",
+        tag: "eval",
+        request: Some((JSON, "BatchRequest")),
+        responses: &[(
+            200,
+            "One result per item (array in, array out; NDJSON in, NDJSON out)",
+            JSON,
+            "BatchResponse",
+        )],
+        evaluates: true,
+        public: false,
+    },
+    Operation {
+        method: "post",
+        path: "/eval/stream",
+        id: "evalStream",
+        summary: "Evaluate items as the body arrives; NDJSON or SSE events",
+        tag: "eval",
+        request: Some((NDJSON, "BatchItem")),
+        responses: &[(200, "trace, result, end and error events", NDJSON, "StreamEvent")],
+        evaluates: false,
+        public: false,
+    },
+    Operation {
+        method: "post",
+        path: "/api/v1/evaluate",
+        id: "evaluate",
+        summary: "Evaluate one expression (CLI-shaped result)",
+        tag: "eval",
+        request: Some((JSON, "EvaluateRequest")),
+        responses: &[(200, "Result", JSON, "EvalResult")],
+        evaluates: true,
+        public: false,
+    },
+    Operation {
+        method: "post",
+        path: "/sessions",
+        id: "createSession",
+        summary: "Open a MEM session, optionally with facts",
+        tag: "sessions",
+        request: Some((JSON, "Facts")),
+        responses: &[
+            (201, "Session created", JSON, "Session"),
+            (503, "Session limit reached", JSON, "Error"),
+        ],
+        evaluates: false,
+        public: false,
+    },
+    Operation {
+        method: "delete",
+        path: "/sessions/{id}",
+        id: "closeSession",
+        summary: "Close a session",
+        tag: "sessions",
+        request: None,
+        responses: &[(204, "Closed", JSON, ""), (404, "Unknown or expired", JSON, "Error")],
+        evaluates: false,
+        public: false,
+    },
+    Operation {
+        method: "post",
+        path: "/sessions/{id}/eval",
+        id: "sessionEval",
+        summary: "Evaluate against the session's facts",
+        tag: "sessions",
+        request: Some((JSON, "SessionEvalRequest")),
+        responses: &[
+            (200, "Result", JSON, "EvalResult"),
+            (404, "Unknown or expired", JSON, "Error"),
+        ],
+        evaluates: true,
+        public: false,
+    },
+    Operation {
+        method: "get",
+        path: "/sessions/{id}/facts",
+        id: "getFacts",
+        summary: "List the session's facts",
+        tag: "sessions",
+        request: None,
+        responses: &[(200, "Facts", JSON, "Session"), (404, "Unknown or expired", JSON, "Error")],
+        evaluates: false,
+        public: false,
+    },
+    Operation {
+        method: "post",
+        path: "/sessions/{id}/facts",
+        id: "assertFacts",
+        summary: "Assert facts",
+        tag: "sessions",
+        request: Some((JSON, "Facts")),
+        responses: &[
+            (200, "Facts after the update", JSON, "Session"),
+            (404, "Unknown or expired", JSON, "Error"),
+        ],
+        evaluates: false,
+        public: false,
+    },
+    Operation {
+        method: "get",
+        path: "/sessions/{id}/consistency",
+        id: "sessionConsistency",
+        summary: "List the facts whose sources disagree",
+        tag: "sessions",
+        request: None,
+        responses: &[
+            (200, "Consistency report", JSON, "Consistency"),
+            (404, "Unknown or expired", JSON, "Error"),
+        ],
+        evaluates: false,
+        public: false,
+    },
+    Operation {
+        method: "get",
+        path: "/sessions/{id}/facts/{name}",
+        id: "factHistory",
+        summary: "List every assertion and retraction of one identifier",
+        tag: "sessions",
+        request: None,
+        responses: &[
+            (200, "History, oldest first", JSON, "FactHistory"),
+            (404, "Unknown or expired", JSON, "Error"),
+        ],
+        evaluates: false,
+        public: false,
+    },
+    Operation {
+        method: "post",
+        path: "/sessions/{id}/what-if",
+        id: "sessionWhatIf",
+        summary: "Evaluate a rulepack under the session's facts and under named scenarios",
+        tag: "sessions",
+        request: Some((JSON, "WhatIfRequest")),
+        responses: &[
+            (200, "Rules whose value or phase each scenario changes", JSON, "WhatIfReport"),
+            (400, "A rule does not parse", JSON, "Error"),
+            (404, "Unknown or expired", JSON, "Error"),
+        ],
+        evaluates: true,
+        public: false,
+    },
+    Operation {
+        method: "delete",
+        path: "/sessions/{id}/facts/{name}",
+        id: "retractFact",
+        summary: "Retract one fact from every source, or from `?source=` only",
+        tag: "sessions",
+        request: None,
+        responses: &[
+            (200, "Facts after the update", JSON, "Session"),
+            (404, "Unknown or expired", JSON, "Error"),
+        ],
+        evaluates: false,
+        public: false,
+    },
+    Operation {
+        method: "get",
+        path: "/healthz",
+        id: "healthz",
+        summary: "Liveness: the process answers",
+        tag: "ops",
+        request: None,
+        responses: &[(200, "Alive", JSON, "Status")],
+        evaluates: false,
+        public: true,
+    },
+    Operation {
+        method: "get",
+        path: "/readyz",
+        id: "readyz",
+        summary: "Readiness: the engine evaluates and the server is not draining",
+        tag: "ops",
+        request: None,
+        responses: &[
+            (200, "Ready", JSON, "Status"),
+            (503, "Draining or engine check failed", JSON, "Status"),
+        ],
+        evaluates: false,
+        public: true,
+    },
+    Operation {
+        method: "get",
+        path: "/metrics",
+        id: "metrics",
+        summary: "Prometheus metrics (text format 0.0.4)",
+        tag: "ops",
+        request: None,
+        responses: &[(200, "Metrics", TEXT, "")],
+        evaluates: false,
+        public: true,
+    },
+    Operation {
+        method: "get",
+        path: "/openapi.json",
+        id: "openapi",
+        summary: "This document",
+        tag: "ops",
+        request: None,
+        responses: &[(200, "OpenAPI 3 document", JSON, "")],
+        evaluates: false,
+        public: true,
+    },
+];
+
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{name}") })
+}
+
+fn schemas() -> Value {
+    let phase = json!({ "type": "string", "enum": ["ALIVE", "JAM", "MEM", "VAC"] });
+    let expr_fields = |extra: Value| {
+        let mut props = json!({
+            "expr": { "type": "string", "description": "Expression; aliases `expression`, `E`" },
+            "ast": { "type": "boolean", "default": false, "description": "Include the AST" },
+            "logic": schema_ref("Logic"),
+        });
+        props.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+        props
+    };
+    let mut schemas = json!({
+        "Phase": phase,
+        "Sheet": {
+            "type": "string",
+            "enum": ["F", "C"],
+            "description": "Factual (witnessed) or counterfactual (unwitnessed or what-if)"
+        },
+        "Logic": {
+            "type": "string",
+            "enum": ["k3", "l3", "four", "classical"],
+            "default": "k3",
+            "description": "Truth domain: Kleene, Łukasiewicz, Belnap FOUR or classical"
+        },
+        "Conflict": {
+            "type": "object",
+            "description": "An identifier whose fact sources disagree",
+            "properties": {
+                "ident": { "type": "string" },
+                "claims": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "source": { "type": "string" },
+                            "value": { "type": "boolean" }
+                        }
+                    }
+                }
+            }
+        },
+        "Consistency": {
+            "type": "object",
+            "properties": {
+                "session": { "type": "string" },
+                "facts": { "type": "integer" },
+                "sources": { "type": "array", "items": { "type": "string" } },
+                "conflicts": { "type": "array", "items": schema_ref("Conflict") }
+            }
+        },
+        "Error": {
+            "type": "object",
+            "required": ["error"],
+            "properties": { "error": { "type": "string" } }
+        },
+        "Diagnostics": {
+            "type": "object",
+            "properties": {
+                "line": { "type": "integer" },
+                "col": { "type": "integer" },
+                "message": { "type": "string" }
+            }
+        },
+        "EvalResult": {
+            "type": "object",
+            "description": "The `grieg-cli --expr` column set",
+            "properties": {
+                "input": { "type": "string" },
+                "ast": { "type": "string", "description": "S-expression, when requested" },
+                "value": {
+                    "oneOf": [{ "type": "boolean" }, { "type": "string", "enum": ["both"] }],
+                    "nullable": true,
+                    "description": "`\"both\"` only under logic `four`"
+                },
+                "phase": { "allOf": [schema_ref("Phase")], "nullable": true },
+                "sheet": { "allOf": [schema_ref("Sheet")], "nullable": true },
+                "error": { "type": "string", "nullable": true },
+                "diagnostics": schema_ref("Diagnostics"),
+                "conflicts": { "type": "array", "items": schema_ref("Conflict") },
+                "witnesses": {
+                    "type": "array",
+                    "items": schema_ref("Fact"),
+                    "description": "MEM facts that resolved identifiers"
+                },
+                "expired": {
+                    "type": "array",
+                    "items": schema_ref("Fact"),
+                    "description": "MEM facts skipped because they had expired"
+                },
+                "trace": { "type": "array", "items": schema_ref("TraceStep") }
+            }
+        },
+        "SapEvalResult": {
+            "type": "object",
+            "properties": {
+                "input": { "type": "string" },
+                "ast": { "type": "string" },
+                "value": { "type": "string", "enum": ["true", "false", "null", "both"] },
+                "phase": { "allOf": [schema_ref("Phase")], "nullable": true },
+                "sheet": { "allOf": [schema_ref("Sheet")], "nullable": true },
+                "error": { "type": "string", "nullable": true },
+                "diagnostics": schema_ref("Diagnostics")
+            }
+        },
+        "TraceStep": {
+            "type": "object",
+            "properties": {
+                "op": { "type": "string" },
+                "pre": schema_ref("Phase"),
+                "post": schema_ref("Phase"),
+                "sink": { "type": "boolean" },
+                "sheet": schema_ref("Sheet")
+            }
+        },
+        "SapEvalRequest": {
+            "type": "object",
+            "required": ["expr"],
+            "properties": expr_fields(json!({
+                "mem": { "type": "boolean", "default": false, "description": "Alias `memory`" },
+                "pretty": { "type": "boolean", "default": false }
+            }))
+        },
+        "EvaluateRequest": {
+            "type": "object",
+            "required": ["prompt"],
+            "properties": {
+                "prompt": { "type": "string", "description": "Expression; alias `expr`" },
+                "mem": { "type": "boolean", "default": false },
+                "logic": schema_ref("Logic"),
+                "ast": { "type": "boolean", "default": false }
+            }
+        },
+        "BatchItem": {
+            "oneOf": [
+                { "type": "string", "description": "A bare expression" },
+                {
+                    "type": "object",
+                    "required": ["expr"],
+                    "properties": expr_fields(json!({ "mem": { "type": "boolean" } }))
+                }
+            ]
+        },
+        "BatchRequest": { "type": "array", "items": schema_ref("BatchItem") },
+        "BatchResponse": { "type": "array", "items": schema_ref("EvalResult") },
+        "StreamEvent": {
+            "type": "object",
+            "required": ["event"],
+            "properties": {
+                "event": { "type": "string", "enum": ["trace", "result", "end", "error"] },
+                "index": { "type": "integer" },
+                "step": schema_ref("TraceStep"),
+                "items": { "type": "integer" },
+                "errors": { "type": "integer" },
+                "error": { "type": "string" }
+            }
+        },
+        "Facts": {
+            "type": "object",
+            "properties": {
+                "facts": { "type": "object", "additionalProperties": { "type": "boolean" } },
+                "retract": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Identifiers whose claims by `source` are retracted in the same transaction"
+                },
+                "source": {
+                    "type": "string",
+                    "default": "default",
+                    "description": "Who asserts the facts; disagreeing sources make an identifier JAM"
+                },
+                "expires_in_s": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "Seconds until the facts expire and their identifiers are VAC again"
+                },
+                "confidence": { "type": "number", "minimum": 0, "maximum": 1 }
+            }
+        },
+        "Fact": {
+            "type": "object",
+            "description": "A MEM fact with its provenance",
+            "properties": {
+                "ident": { "type": "string" },
+                "value": { "type": "boolean" },
+                "source": { "type": "string" },
+                "asserted_at": { "type": "string", "format": "date-time" },
+                "valid_from": { "type": "string", "format": "date-time" },
+                "expires_at": { "type": "string", "format": "date-time" },
+                "confidence": { "type": "number" }
+            }
+        },
+        "Record": {
+            "description": "A fact with the transaction times at which it was recorded and retracted",
+            "allOf": [
+                schema_ref("Fact"),
+                {
+                    "type": "object",
+                    "properties": {
+                        "recorded_at": { "type": "string", "format": "date-time" },
+                        "retracted_at": { "type": "string", "format": "date-time" }
+                    }
+                }
+            ]
+        },
+        "FactHistory": {
+            "type": "object",
+            "properties": {
+                "session": { "type": "string" },
+                "name": { "type": "string" },
+                "history": { "type": "array", "items": schema_ref("Record") }
+            }
+        },
+        "Session": {
+            "type": "object",
+            "properties": {
+                "session": { "type": "string" },
+                "facts": { "type": "object", "additionalProperties": { "type": "boolean" } },
+                "records": {
+                    "type": "array",
+                    "items": schema_ref("Fact"),
+                    "description": "Every stored fact, expired ones included (GET only)"
+                },
+                "ttl_s": { "type": "integer" }
+            }
+        },
+        "SessionEvalRequest": {
+            "type": "object",
+            "required": ["expr"],
+            "properties": expr_fields(json!({
+                "trace": { "type": "boolean", "default": false },
+                "as_of": {
+                    "type": "string",
+                    "format": "date-time",
+                    "description": "Evaluate against the facts valid and known at this time"
+                },
+                "known_at": {
+                    "type": "string",
+                    "format": "date-time",
+                    "description": "Transaction time, when it should differ from `as_of`"
+                }
+            }))
+        },
+        "Status": {
+            "type": "object",
+            "properties": { "status": { "type": "string" } }
+        }
+    });
+    schemas.as_object_mut().unwrap().extend(what_if_schemas().as_object().unwrap().clone());
+    schemas
+}
+
+/// Scenario evaluation (`/sessions/{id}/what-if`); kept apart to stay under `json!`'s
+/// recursion limit.
+fn what_if_schemas() -> Value {
+    json!({
+        "Overlay": {
+            "type": "object",
+            "description": "What-if layer: its facts replace lower layers' for their identifiers",
+            "properties": {
+                "facts": {
+                    "oneOf": [
+                        { "type": "object", "additionalProperties": { "type": "boolean" } },
+                        { "type": "array", "items": schema_ref("Fact") }
+                    ]
+                },
+                "hide": { "type": "array", "items": { "type": "string" } }
+            }
+        },
+        "Scenario": {
+            "type": "object",
+            "required": ["name"],
+            "properties": {
+                "name": { "type": "string" },
+                "layers": {
+                    "type": "array",
+                    "items": schema_ref("Overlay"),
+                    "description": "Bottom layer first"
+                }
+            }
+        },
+        "WhatIfRequest": {
+            "type": "object",
+            "required": ["rules"],
+            "properties": {
+                "rules": {
+                    "type": "object",
+                    "additionalProperties": { "type": "string" },
+                    "description": "Rulepack: rule name -> expression"
+                },
+                "scenarios": { "type": "array", "items": schema_ref("Scenario") },
+                "logic": schema_ref("Logic")
+            }
+        },
+        "Outcome": {
+            "type": "object",
+            "properties": {
+                "value": { "type": "boolean", "nullable": true },
+                "phase": schema_ref("Phase"),
+                "sheet": schema_ref("Sheet")
+            }
+        },
+        "WhatIfReport": {
+            "type": "object",
+            "properties": {
+                "session": { "type": "string" },
+                "rules": { "type": "array", "items": { "type": "string" } },
+                "baseline": { "type": "array", "items": schema_ref("Outcome") },
+                "scenarios": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "name": { "type": "string" },
+                            "outcomes": { "type": "array", "items": schema_ref("Outcome") },
+                            "changes": {
+                                "type": "array",
+                                "items": {
+                                    "type": "object",
+                                    "properties": {
+                                        "rule": { "type": "string" },
+                                        "baseline": schema_ref("Outcome"),
+                                        "scenario": schema_ref("Outcome")
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+fn operation(op: &Operation, secured: bool) -> Value {
+    let mut o = json!({ "operationId": op.id, "summary": op.summary, "tags": [op.tag] });
+    let params: Vec<Value> = op
+        .path
+        .split('/')
+        .filter_map(|seg| seg.strip_prefix('{')?.strip_suffix('}'))
+        .map(|name| {
+            json!({ "name": name, "in": "path", "required": true, "schema": { "type": "string" } })
+        })
+        .collect();
+    if !params.is_empty() {
+        o["parameters"] = Value::Array(params);
+    }
+    if let Some((media, schema)) = op.request {
+        o["requestBody"] = json!({ "content": { media: { "schema": schema_ref(schema) } } });
+    }
+    let mut responses = Map::new();
+    let errors: &[Response] = if op.evaluates { &EVAL_ERRORS } else { &[] };
+    for &(status, description, media, schema) in op.responses.iter().chain(errors) {
+        let mut r = json!({ "description": description });
+        if !schema.is_empty() {
+            r["content"] = json!({ media: { "schema": schema_ref(schema) } });
+        } else if media == TEXT {
+            r["content"] = json!({ media: { "schema": { "type": "string" } } });
+        }
+        responses.insert(status.to_string(), r);
+    }
+    if secured && !op.public {
+        responses.insert("401".into(), json!({ "description": "Missing or invalid credentials" }));
+    }
+    o["responses"] = Value::Object(responses);
+    if op.public {
+        o["security"] = json!([]);
+    }
+    o
+}
+
+/// The OpenAPI document for a server running with `cfg`.
+pub fn document(cfg: &Config) -> Value {
+    let secured = cfg.auth.mode != AuthMode::None;
+    let mut paths = Map::new();
+    for op in OPERATIONS {
+        let entry = paths.entry(op.path).or_insert_with(|| json!({}));
+        entry[op.method] = operation(op, secured);
+    }
+    let scheme = if cfg.tls.is_some() { "https" } else { "http" };
+    let mut doc = json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "grieg-http",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Phase-aware evaluation of Grieg expressions (ALIVE, JAM, MEM, VAC)."
+        },
+        "servers": [{ "url": format!("{scheme}://{}:{}", cfg.bind, cfg.port) }],
+        "paths": paths,
+        "components": { "schemas": schemas() }
+    });
+    let security_schemes = match cfg.auth.mode {
+        AuthMode::None => None,
+        AuthMode::ApiKey => Some((
+            json!({
+                "bearer": { "type": "http", "scheme": "bearer" },
+                "apiKey": { "type": "apiKey", "in": "header", "name": "X-API-Key" }
+            }),
+            json!([{ "bearer": [] }, { "apiKey": [] }]),
+        )),
+        AuthMode::Hmac => Some((
+            json!({
+                "hmac": {
+                    "type": "apiKey", "in": "header", "name": "X-Grieg-Signature",
+                    "description": "hex(HMAC-SHA256(secret, \"{ts}\\n{METHOD}\\n{path}\\n\" \
+                                    + body)) with X-Grieg-Key and X-Grieg-Timestamp"
+                }
+            }),
+            json!([{ "hmac": [] }]),
+        )),
+    };
+    if let Some((schemes, requirement)) = security_schemes {
+        doc["components"]["securitySchemes"] = schemes;
+        doc["security"] = requirement;
+    }
+    doc
+}
+
+pub async fn handler(State(app): State<Arc<AppState>>) -> Json<Value> {
+    Json(app.openapi.clone())
+}
@@ -0,0 +1,148 @@
+//! Liveness, readiness and shutdown.
+//!
+//! `/healthz` answers as long as the process does. `/readyz` evaluates a fixed expression and
+//! answers 503 once a SIGTERM (or Ctrl-C) has started draining, so a load balancer stops routing
+//! here while in-flight requests finish.
+
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde_json::json;
+
+use grieg_engine::phase::Phase;
+use grieg_engine::Evaluator;
+use grieg_parser::parse_expr;
+
+use crate::AppState;
+
+pub async fn healthz() -> Response {
+    Json(json!({ "status": "ok", "version": env!("CARGO_PKG_VERSION") })).into_response()
+}
+
+/// `@mem(true -> true)` must come back as MEM with MEM on; anything else is a broken engine.
+fn engine_ok() -> bool {
+    let Ok(e) = parse_expr("@mem(true -> true)") else { return false };
+    let r = Evaluator::new(true).eval(&e, None);
+    r.phase == Phase::MEM && r.value.to_bool() == Some(true)
+}
+
+pub async fn readyz(State(app): State<Arc<AppState>>) -> Response {
+    let (status, label) = if app.draining.load(Ordering::Relaxed) {
+        (StatusCode::SERVICE_UNAVAILABLE, "draining")
+    } else if !engine_ok() {
+        (StatusCode::SERVICE_UNAVAILABLE, "engine check failed")
+    } else {
+        (StatusCode::OK, "ready")
+    };
+    (status, Json(json!({ "status": label }))).into_response()
+}
+
+/// Resolves on SIGTERM or Ctrl-C.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+    #[cfg(unix)]
+    let term = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut s) => {
+                s.recv().await;
+            }
+            Err(_) => std::future::pending().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let term = std::future::pending::<()>();
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = term => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::auth::{parse_keys, AuthConfig, AuthMode};
+    use crate::{build, openapi, Config};
+
+    async fn get(app: &axum::Router, uri: &str) -> (StatusCode, String) {
+        let res = app.clone().oneshot(Request::get(uri).body(Body::empty()).unwrap()).await;
+        let res = res.unwrap();
+        let status = res.status();
+        let bytes = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn probes_bypass_auth_and_readyz_fails_while_draining() {
+        let keys = parse_keys("sap:s3cret").unwrap();
+        let auth = AuthConfig { mode: AuthMode::ApiKey, keys, ..AuthConfig::default() };
+        let (app, state) = build(&Config { auth, ..Config::default() });
+        assert_eq!(get(&app, "/healthz").await.0, StatusCode::OK);
+        assert_eq!(get(&app, "/readyz").await.0, StatusCode::OK);
+        assert_eq!(get(&app, "/sessions/x/facts").await.0, StatusCode::UNAUTHORIZED);
+
+        state.draining.store(true, Ordering::Relaxed);
+        let (status, body) = get(&app, "/readyz").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(body.contains("draining"));
+    }
+
+    #[tokio::test]
+    async fn metrics_count_requests_and_phases() {
+        let (app, _) = build(&Config::default());
+        let before = crate::metrics::global().phase_count(Phase::JAM);
+        let req = Request::post("/api/v1/evaluate")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"prompt":"@jam(true)"}"#))
+            .unwrap();
+        assert_eq!(app.clone().oneshot(req).await.unwrap().status(), StatusCode::OK);
+        assert!(crate::metrics::global().phase_count(Phase::JAM) > before);
+
+        let (status, text) = get(&app, "/metrics").await;
+        assert_eq!(status, StatusCode::OK);
+        let counter =
+            r#"grieg_http_requests_total{method="POST",route="/api/v1/evaluate",status="200"}"#;
+        let line = text.lines().find(|l| l.starts_with(counter)).expect("request counter");
+        assert!(line.rsplit(' ').next().unwrap().parse::<u64>().unwrap() >= 1);
+        assert!(text.contains(
+            "grieg_http_request_duration_seconds_bucket{route=\"/api/v1/evaluate\",le=\"+Inf\"}"
+        ));
+        assert!(text.contains("grieg_http_eval_results_total{phase=\"JAM\"}"));
+    }
+
+    #[tokio::test]
+    async fn openapi_lists_every_routed_operation() {
+        let (app, _) = build(&Config::default());
+        let (status, body) = get(&app, "/openapi.json").await;
+        assert_eq!(status, StatusCode::OK);
+        let doc: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(doc["openapi"], "3.0.3");
+        for op in openapi::OPERATIONS {
+            assert!(doc["paths"][op.path][op.method].is_object(), "{} {}", op.method, op.path);
+            // The router must know the path and method. Its own 404 has an empty body; an
+            // unknown session answers 404 with a JSON error.
+            let uri = op.path.replace("{id}", "none").replace("{name}", "x");
+            let req = Request::builder()
+                .method(op.method.to_ascii_uppercase().as_str())
+                .uri(&uri)
+                .body(Body::empty())
+                .unwrap();
+            let res = app.clone().oneshot(req).await.unwrap();
+            let status = res.status();
+            let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+            let routed = status != StatusCode::METHOD_NOT_ALLOWED
+                && (status != StatusCode::NOT_FOUND || !body.is_empty());
+            assert!(routed, "{} {uri} -> {status}", op.method);
+        }
+    }
+}
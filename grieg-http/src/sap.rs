@@ -0,0 +1,103 @@
+//! `POST /eval`: the contract of the SAP pack (`grieg-sap/`).
+//!
+//! Accepts what `cpi/Normalizer.groovy` accepts: a JSON object with `expr` (or `expression`,
+//! `E`), `mem` (or `memory`), `ast` and `pretty`, or a plain-text body holding the expression.
+//!
+//! `ZCL_GRIEG_HTTP_CLIENT` falls back to regex extraction when `/ui2/cl_json` is missing:
+//!
+//! ```text
+//! "value"\s*:\s*("?[^"]*"?|null)     "phase"\s*:\s*"?([^"]*)
+//! ```
+//!
+//! A bare `true,` or `null,` would be captured together with the following comma and quote,
+//! so `value` is sent as a JSON string (`"true"`, `"false"` or `"null"`); `phase` is already a
+//! string. Everything else matches the CLI column set.
+
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use grieg_engine::Logic;
+
+use crate::eval::{bounded, evaluate, ApiError, EvalRequest};
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct SapEvalIn {
+    #[serde(alias = "expression", alias = "E")]
+    pub expr: String,
+    #[serde(default, alias = "memory")]
+    pub mem: bool,
+    #[serde(default)]
+    pub logic: Logic,
+    #[serde(default)]
+    pub ast: bool,
+    #[serde(default)]
+    pub pretty: bool,
+}
+
+impl SapEvalIn {
+    /// JSON when declared or when the body looks like an object, else the body is the expression.
+    pub fn from_body(headers: &HeaderMap, body: &[u8]) -> Result<Self, ApiError> {
+        let text = std::str::from_utf8(body)
+            .map_err(|_| ApiError::BadRequest("body is not UTF-8".into()))?
+            .trim();
+        let json_ct = headers
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|ct| ct.to_ascii_lowercase().contains("application/json"));
+        if json_ct || text.starts_with('{') {
+            return serde_json::from_str(text)
+                .map_err(|e| ApiError::BadRequest(format!("invalid request: {e}")));
+        }
+        if text.is_empty() {
+            return Err(ApiError::BadRequest("missing expression".into()));
+        }
+        Ok(SapEvalIn {
+            expr: text.to_string(),
+            mem: false,
+            logic: Logic::default(),
+            ast: false,
+            pretty: false,
+        })
+    }
+}
+
+pub async fn eval(State(app): State<Arc<AppState>>, headers: HeaderMap, body: Bytes) -> Response {
+    let inp = match SapEvalIn::from_body(&headers, &body) {
+        Ok(inp) => inp,
+        Err(e) => return e.into_response(),
+    };
+    if let Err(msg) = app.limits.check_expr(&inp.expr) {
+        return ApiError::TooLarge(msg).into_response();
+    }
+    let req = EvalRequest { expr: inp.expr, mem: inp.mem, logic: inp.logic, ast: inp.ast };
+    let result = match bounded(app.limits.eval_timeout, move || evaluate(&req)).await {
+        Ok(r) => r,
+        Err(e) => return e.into_response(),
+    };
+    let (status, mut obj) = match result {
+        Ok(obj) => (StatusCode::OK, obj),
+        Err(ApiError::Parse { body }) => (StatusCode::BAD_REQUEST, body),
+        Err(e) => return e.into_response(),
+    };
+    abap_shape(&mut obj);
+    let text =
+        if inp.pretty { serde_json::to_string_pretty(&obj) } else { serde_json::to_string(&obj) };
+    (status, [(CONTENT_TYPE, "application/json")], text.unwrap()).into_response()
+}
+
+/// Render `value` as a string token the ABAP fallback regex can capture.
+pub fn abap_shape(obj: &mut Map<String, Value>) {
+    if let Some(v) = obj.get_mut("value") {
+        if !v.is_string() {
+            *v = Value::String(v.to_string());
+        }
+    }
+}
@@ -0,0 +1,617 @@
+//! Session-scoped MEM stores.
+//!
+//! A session owns one MEM-enabled `Evaluator`, so facts asserted by one call are witnesses for
+//! the next. Sessions expire after `ttl` without use; expired sessions answer 404 and are
+//! purged whenever a session is created. At most `max_sessions` are live at once; creating
+//! another answers 503.
+//!
+//! Each `POST .../facts` is one transaction: its retractions and assertions share a
+//! transaction time and become visible together. Evaluations run against a snapshot of the
+//! session's store, outside its lock.
+//!
+//! | method | path                          | body                                 |
+//! |--------|-------------------------------|--------------------------------------|
+//! | POST   | `/sessions`                   | `{"facts": {"a": true}}`             |
+//! | POST   | `/sessions/{id}/eval`         | `{"expr": "...", "ast": b}`          |
+//! | GET    | `/sessions/{id}/facts`        |                                      |
+//! | POST   | `/sessions/{id}/facts`        | `{"facts": {..}, "retract": [..]}`   |
+//! | GET    | `/sessions/{id}/facts/{name}` |                                      |
+//! | DELETE | `/sessions/{id}/facts/{name}` | `?source=crm`, else every source     |
+//! | POST   | `/sessions/{id}/what-if`      | `{"rules": {..}, "scenarios": [..]}` |
+//! | DELETE | `/sessions/{id}`              |                                      |
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::body::Bytes;
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+use grieg_engine::mem::{AsOf, Fact, DEFAULT_SOURCE};
+use grieg_engine::scenario::Scenario;
+use grieg_engine::{Evaluator, Expr, Logic};
+use grieg_parser::parse_expr;
+
+use crate::eval::{self, bounded, ApiError, EvalRequest};
+use crate::AppState;
+
+struct Session {
+    ev: Mutex<Evaluator>,
+    /// Milliseconds since the store's `epoch`; read without taking `ev`'s lock.
+    last_used: AtomicU64,
+}
+
+/// Live sessions, each behind its own lock: a slow call on one session never blocks another.
+/// The map lock is only held to find, add or drop a session and never waits on a session's:
+/// idle times are atomics, and purged sessions are dropped after the map lock is released.
+pub struct SessionStore {
+    ttl: Duration,
+    max_sessions: usize,
+    epoch: Instant,
+    sessions: Mutex<HashMap<String, Arc<Session>>>,
+}
+
+impl SessionStore {
+    pub fn new(ttl: Duration, max_sessions: usize) -> Self {
+        Self { ttl, max_sessions, epoch: Instant::now(), sessions: Mutex::new(HashMap::new()) }
+    }
+
+    /// A new session holding `facts`; `None` when `max_sessions` are already live.
+    pub fn create(&self, facts: Vec<Fact>) -> Option<String> {
+        let mut ev = Evaluator::new(true);
+        for f in facts {
+            ev.assert_mem(f);
+        }
+        let id = Uuid::new_v4().to_string();
+        let session = Session { ev: Mutex::new(ev), last_used: AtomicU64::new(self.now()) };
+        let mut map = self.sessions.lock().unwrap();
+        let stale: Vec<String> =
+            map.iter().filter(|(_, s)| !self.live(s)).map(|(k, _)| k.clone()).collect();
+        let expired: Vec<_> = stale.iter().filter_map(|k| map.remove(k)).collect();
+        let created = map.len() < self.max_sessions;
+        if created {
+            map.insert(id.clone(), Arc::new(session));
+        }
+        drop(map);
+        drop(expired);
+        created.then_some(id)
+    }
+
+    /// Run `f` on a live session's evaluator and refresh its idle timer.
+    pub fn with<R>(&self, id: &str, f: impl FnOnce(&mut Evaluator) -> R) -> Option<R> {
+        let session = self.sessions.lock().unwrap().get(id).cloned()?;
+        if self.live(&session) {
+            session.last_used.fetch_max(self.now(), Ordering::Relaxed);
+            return Some(f(&mut session.ev.lock().unwrap()));
+        }
+        self.sessions.lock().unwrap().remove(id);
+        None
+    }
+
+    pub fn remove(&self, id: &str) -> bool {
+        self.sessions.lock().unwrap().remove(id).is_some()
+    }
+
+    /// Sessions not yet expired.
+    pub fn len(&self) -> usize {
+        self.sessions.lock().unwrap().values().filter(|s| self.live(s)).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    fn now(&self) -> u64 {
+        self.epoch.elapsed().as_millis() as u64
+    }
+
+    fn live(&self, s: &Session) -> bool {
+        let idle = self.now().saturating_sub(s.last_used.load(Ordering::Relaxed));
+        Duration::from_millis(idle) < self.ttl
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Handlers
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+pub struct FactsIn {
+    #[serde(default)]
+    facts: HashMap<String, bool>,
+    /// Identifiers whose claims by `source` are retracted, in the same transaction.
+    #[serde(default)]
+    retract: Vec<String>,
+    /// Who asserts `facts`.
+    #[serde(default = "default_source")]
+    source: String,
+    /// Seconds until `facts` expire and their identifiers are VAC again.
+    #[serde(default)]
+    expires_in_s: Option<u64>,
+    /// How much `source` vouches for `facts`, in `[0, 1]`.
+    #[serde(default)]
+    confidence: Option<f64>,
+}
+
+impl Default for FactsIn {
+    fn default() -> Self {
+        Self {
+            facts: HashMap::new(),
+            retract: Vec::new(),
+            source: default_source(),
+            expires_in_s: None,
+            confidence: None,
+        }
+    }
+}
+
+impl FactsIn {
+    /// Retractions first, then assertions, committed together.
+    fn apply(self, ev: &mut Evaluator) {
+        let mut tx = ev.begin();
+        for name in &self.retract {
+            tx.retract_from(&self.source, name);
+        }
+        for f in self.into_facts() {
+            tx.assert(f);
+        }
+        ev.commit(tx);
+    }
+
+    fn into_facts(self) -> Vec<Fact> {
+        let (source, ttl, confidence) = (self.source, self.expires_in_s, self.confidence);
+        let fact = move |(name, value)| {
+            let f = Fact::new(&source, name, value);
+            let f = match ttl {
+                Some(secs) => f.with_ttl_secs(secs),
+                None => f,
+            };
+            match confidence {
+                Some(c) => f.with_confidence(c),
+                None => f,
+            }
+        };
+        self.facts.into_iter().map(fact).collect()
+    }
+}
+
+fn default_source() -> String {
+    DEFAULT_SOURCE.to_string()
+}
+
+#[derive(Deserialize)]
+pub struct SessionEvalIn {
+    #[serde(alias = "expression", alias = "E")]
+    expr: String,
+    #[serde(default)]
+    logic: Logic,
+    #[serde(default)]
+    ast: bool,
+    #[serde(default)]
+    trace: bool,
+    /// Evaluate against what was valid and known at this time instead of now.
+    #[serde(default)]
+    as_of: Option<DateTime<Utc>>,
+    /// Transaction time, when it should differ from `as_of`: what the session knew then.
+    #[serde(default)]
+    known_at: Option<DateTime<Utc>>,
+}
+
+impl SessionEvalIn {
+    fn view(&self) -> Option<AsOf> {
+        match (self.as_of, self.known_at) {
+            (None, None) => None,
+            (valid, known) => {
+                Some(AsOf { valid: valid.unwrap_or_else(Utc::now), known: known.or(valid) })
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct WhatIfIn {
+    /// Rulepack: rule name -> expression.
+    rules: Map<String, Value>,
+    #[serde(default)]
+    scenarios: Vec<Scenario>,
+    #[serde(default)]
+    logic: Logic,
+}
+
+impl WhatIfIn {
+    fn parse_rules(&self) -> Result<Vec<(String, Expr)>, String> {
+        let parse = |(name, e): (&String, &Value)| {
+            let e = e.as_str().ok_or_else(|| format!("rule {name:?}: expected a string"))?;
+            parse_expr(e).map(|x| (name.clone(), x)).map_err(|err| format!("rule {name:?}: {err}"))
+        };
+        self.rules.iter().map(parse).collect()
+    }
+}
+
+fn unknown(id: &str) -> Response {
+    let msg = format!("unknown or expired session '{id}'");
+    (StatusCode::NOT_FOUND, Json(json!({ "error": msg }))).into_response()
+}
+
+fn bad_json(e: JsonRejection) -> Response {
+    ApiError::BadRequest(e.body_text()).into_response()
+}
+
+/// Facts as a JSON object with sorted keys (stable output).
+fn facts_json(ev: &Evaluator) -> Value {
+    let mut facts: Vec<_> = ev.export_mem().into_iter().collect();
+    facts.sort();
+    Value::Object(facts.into_iter().map(|(k, v)| (k, Value::Bool(v))).collect::<Map<_, _>>())
+}
+
+/// The body is optional; an empty one creates a session without facts.
+pub async fn create(State(app): State<Arc<AppState>>, body: Bytes) -> Response {
+    let inp: FactsIn = if body.iter().all(u8::is_ascii_whitespace) {
+        FactsIn::default()
+    } else {
+        match serde_json::from_slice(&body) {
+            Ok(f) => f,
+            Err(e) => return ApiError::BadRequest(format!("invalid request: {e}")).into_response(),
+        }
+    };
+    let Some(id) = app.sessions.create(inp.into_facts()) else {
+        let msg = format!("session limit of {} reached", app.limits.max_sessions);
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(json!({ "error": msg }))).into_response();
+    };
+    let facts = app.sessions.with(&id, |ev| facts_json(ev)).unwrap_or_default();
+    let body = json!({ "session": id, "facts": facts, "ttl_s": app.sessions.ttl().as_secs() });
+    (StatusCode::CREATED, Json(body)).into_response()
+}
+
+pub async fn eval(
+    State(app): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    body: Result<Json<SessionEvalIn>, JsonRejection>,
+) -> Response {
+    let inp = match body {
+        Ok(Json(i)) => i,
+        Err(e) => return bad_json(e),
+    };
+    if let Err(msg) = app.limits.check_expr(&inp.expr) {
+        return ApiError::TooLarge(msg).into_response();
+    }
+    let (trace, as_of) = (inp.trace, inp.view());
+    let req = EvalRequest { expr: inp.expr, mem: true, logic: inp.logic, ast: inp.ast };
+    let (state, sid) = (app.clone(), id.clone());
+    // Evaluate a snapshot, outside the store's lock: writers to this session (and every
+    // other one) do not wait for it, and it never sees half of their batches.
+    let run = move || {
+        let mut ev = state.sessions.with(&sid, |ev| ev.snapshot())?;
+        if let Some(v) = as_of {
+            (ev.at, ev.known_at) = (Some(v.valid), v.known);
+        }
+        Some(eval::run_with(&mut ev, &req, trace))
+    };
+    let o = match bounded(app.limits.eval_timeout, run).await {
+        Ok(Some(o)) => o,
+        Ok(None) => return unknown(&id),
+        Err(e) => return e.into_response(),
+    };
+    let status = if o.parsed { StatusCode::OK } else { StatusCode::BAD_REQUEST };
+    let mut body = o.body;
+    if inp.trace {
+        body.insert("trace".into(), json!(o.trace));
+    }
+    (status, Json(body)).into_response()
+}
+
+pub async fn get_facts(State(app): State<Arc<AppState>>, Path(id): Path<String>) -> Response {
+    match app.sessions.with(&id, |ev| (facts_json(ev), ev.mem_facts())) {
+        Some((facts, records)) => {
+            Json(json!({ "session": id, "facts": facts, "records": records })).into_response()
+        }
+        None => unknown(&id),
+    }
+}
+
+pub async fn assert_facts(
+    State(app): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    body: Result<Json<FactsIn>, JsonRejection>,
+) -> Response {
+    let inp = match body {
+        Ok(Json(f)) => f,
+        Err(e) => return bad_json(e),
+    };
+    let facts = app.sessions.with(&id, |ev| {
+        inp.apply(ev);
+        facts_json(ev)
+    });
+    match facts {
+        Some(facts) => Json(json!({ "session": id, "facts": facts })).into_response(),
+        None => unknown(&id),
+    }
+}
+
+/// Every assertion and retraction of one identifier, oldest first.
+pub async fn history(
+    State(app): State<Arc<AppState>>,
+    Path((id, name)): Path<(String, String)>,
+) -> Response {
+    match app.sessions.with(&id, |ev| ev.mem_history(&name)) {
+        Some(history) => {
+            Json(json!({ "session": id, "name": name, "history": history })).into_response()
+        }
+        None => unknown(&id),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RetractIn {
+    /// Only this source's claim; every source's when absent (unlike `POST .../facts`, whose
+    /// `retract` defaults to the default source).
+    #[serde(default)]
+    source: Option<String>,
+}
+
+pub async fn retract_fact(
+    State(app): State<Arc<AppState>>,
+    Path((id, name)): Path<(String, String)>,
+    Query(q): Query<RetractIn>,
+) -> Response {
+    let facts = app.sessions.with(&id, |ev| {
+        match &q.source {
+            Some(source) => ev.retract_mem_from(source, [name.as_str()]),
+            None => ev.retract_mem([name.as_str()]),
+        }
+        facts_json(ev)
+    });
+    match facts {
+        Some(facts) => Json(json!({ "session": id, "facts": facts })).into_response(),
+        None => unknown(&id),
+    }
+}
+
+/// The rulepack under the session's facts and under each scenario; the session is unchanged.
+pub async fn what_if(
+    State(app): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    body: Result<Json<WhatIfIn>, JsonRejection>,
+) -> Response {
+    let inp = match body {
+        Ok(Json(i)) => i,
+        Err(e) => return bad_json(e),
+    };
+    let runs = inp.rules.len() * (inp.scenarios.len() + 1);
+    if runs > app.limits.max_items {
+        let msg = format!("{runs} evaluations; limit is {}", app.limits.max_items);
+        return ApiError::TooLarge(msg).into_response();
+    }
+    for e in inp.rules.values().filter_map(Value::as_str) {
+        if let Err(msg) = app.limits.check_expr(e) {
+            return ApiError::TooLarge(msg).into_response();
+        }
+    }
+    let rules = match inp.parse_rules() {
+        Ok(r) => r,
+        Err(msg) => return ApiError::BadRequest(msg).into_response(),
+    };
+    let Some(ev) = app.sessions.with(&id, |ev| ev.snapshot()) else {
+        return unknown(&id);
+    };
+    let (scenarios, logic) = (inp.scenarios, inp.logic);
+    let run = move || ev.with_logic(logic).what_if(&rules, &scenarios);
+    match bounded(app.limits.eval_timeout, run).await {
+        Ok(report) => {
+            let mut body = json!(report);
+            body["session"] = json!(id);
+            Json(body).into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn consistency(State(app): State<Arc<AppState>>, Path(id): Path<String>) -> Response {
+    match app.sessions.with(&id, |ev| ev.consistency()) {
+        Some(report) => {
+            let mut body = json!(report);
+            body["session"] = json!(id);
+            Json(body).into_response()
+        }
+        None => unknown(&id),
+    }
+}
+
+pub async fn close(State(app): State<Arc<AppState>>, Path(id): Path<String>) -> Response {
+    if app.sessions.remove(&id) {
+        StatusCode::NO_CONTENT.into_response()
+    } else {
+        unknown(&id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    async fn call(app: &Router, method: &str, uri: &str, body: &str) -> (StatusCode, Value) {
+        let req = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let res = app.clone().oneshot(req).await.unwrap();
+        let status = res.status();
+        let bytes = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+    }
+
+    /// Open a session holding `facts` and return its path.
+    async fn open(app: &Router, facts: &str) -> String {
+        let (status, v) = call(app, "POST", "/sessions", facts).await;
+        assert_eq!(status, StatusCode::CREATED);
+        format!("/sessions/{}", v["session"].as_str().unwrap())
+    }
+
+    #[tokio::test]
+    async fn facts_persist_across_calls_until_retracted() {
+        let app = crate::router();
+        let base = open(&app, r#"{"facts":{"paid":true}}"#).await;
+        let eval = format!("{base}/eval");
+        let (_, v) = call(&app, "POST", &eval, r#"{"expr":"paid & shipped"}"#).await;
+        assert_eq!(v["phase"], "VAC");
+        call(&app, "POST", &format!("{base}/facts"), r#"{"facts":{"shipped":true}}"#).await;
+        let (_, v) = call(&app, "POST", &eval, r#"{"expr":"paid & shipped"}"#).await;
+        assert_eq!((v["value"].as_bool(), v["phase"].as_str()), (Some(true), Some("MEM")));
+
+        let (_, v) = call(&app, "DELETE", &format!("{base}/facts/paid"), "").await;
+        assert_eq!(v["facts"], json!({ "shipped": true }));
+        let (_, v) = call(&app, "POST", &eval, r#"{"expr":"paid"}"#).await;
+        assert_eq!(v["phase"], "VAC");
+
+        assert_eq!(call(&app, "DELETE", &base, "").await.0, StatusCode::NO_CONTENT);
+        assert_eq!(call(&app, "POST", &eval, r#"{"expr":"a"}"#).await.0, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn disagreeing_sources_jam_and_are_reported() {
+        let app = crate::router();
+        let base = open(&app, r#"{"facts":{"paid":true}}"#).await;
+        let facts = format!("{base}/facts");
+        call(&app, "POST", &facts, r#"{"facts":{"paid":false},"source":"crm"}"#).await;
+        let (_, v) = call(&app, "POST", &format!("{base}/eval"), r#"{"expr":"paid"}"#).await;
+        assert_eq!(v["phase"], "JAM");
+        assert_eq!(v["conflicts"][0]["claims"][0], json!({ "source": "crm", "value": false }));
+        let (_, v) = call(&app, "GET", &format!("{base}/consistency"), "").await;
+        assert_eq!(v["sources"], json!(["crm", "default"]));
+        assert_eq!(v["conflicts"][0]["ident"], "paid");
+    }
+
+    #[tokio::test]
+    async fn expired_facts_are_vac() {
+        let app = crate::router();
+        let base = open(&app, "").await;
+        let facts = format!("{base}/facts");
+        let body = r#"{"facts":{"door":true},"source":"sensor","expires_in_s":0}"#;
+        call(&app, "POST", &facts, body).await;
+        let (_, v) = call(&app, "POST", &format!("{base}/eval"), r#"{"expr":"door"}"#).await;
+        assert_eq!((&v["phase"], &v["expired"][0]["source"]), (&json!("VAC"), &json!("sensor")));
+        let (_, v) = call(&app, "GET", &facts, "").await;
+        assert!(v["records"].as_array().unwrap().iter().any(|r| r["expires_at"].is_string()));
+    }
+
+    #[tokio::test]
+    async fn delete_retracts_one_source_or_all() {
+        let app = crate::router();
+        let base = open(&app, r#"{"facts":{"paid":true}}"#).await;
+        call(&app, "POST", &format!("{base}/facts"), r#"{"facts":{"paid":false},"source":"crm"}"#)
+            .await;
+        call(&app, "DELETE", &format!("{base}/facts/paid?source=crm"), "").await;
+        let (_, v) = call(&app, "POST", &format!("{base}/eval"), r#"{"expr":"paid"}"#).await;
+        assert_eq!((v["value"].as_bool(), v["phase"].as_str()), (Some(true), Some("MEM")));
+        let (_, v) = call(&app, "DELETE", &format!("{base}/facts/paid"), "").await;
+        assert_eq!(v["facts"], json!({}));
+    }
+
+    #[test]
+    fn expired_sessions_are_purged_on_create() {
+        let store = SessionStore::new(Duration::from_millis(20), 1);
+        let id = store.create(Vec::new()).unwrap();
+        assert!(store.create(Vec::new()).is_none());
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(store.with(&id, |_| ()).is_none());
+        assert!(store.create(Vec::new()).is_some());
+        assert_eq!(store.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn history_and_as_of_evaluation() {
+        let app = crate::router();
+        let base = open(&app, r#"{"facts":{"paid":true}}"#).await;
+        let (facts, eval) = (format!("{base}/facts"), format!("{base}/eval"));
+        call(&app, "POST", &facts, r#"{"facts":{"paid":false},"source":"crm"}"#).await;
+        let before = Utc::now();
+        call(&app, "DELETE", &format!("{base}/facts/paid"), "").await;
+        let (_, v) = call(&app, "GET", &format!("{base}/facts/paid"), "").await;
+        let history = v["history"].as_array().unwrap();
+        assert_eq!(history.len(), 2);
+        assert!(history.iter().all(|r| r["retracted_at"].is_string()));
+        let body = json!({ "expr": "paid", "as_of": before }).to_string();
+        let (_, v) = call(&app, "POST", &eval, &body).await;
+        assert_eq!(v["phase"], "JAM", "as of before the retraction, both sources still disagree");
+        let body = json!({ "expr": "paid", "known_at": before }).to_string();
+        let (_, v) = call(&app, "POST", &eval, &body).await;
+        assert_eq!(v["phase"], "JAM", "now, by what the session knew before the retraction");
+        let (_, v) = call(&app, "POST", &eval, r#"{"expr":"paid"}"#).await;
+        assert_eq!(v["phase"], "VAC");
+    }
+
+    #[tokio::test]
+    async fn one_update_is_one_transaction() {
+        let app = crate::router();
+        let base = open(&app, r#"{"facts":{"shipped":false}}"#).await;
+        let facts = format!("{base}/facts");
+        call(&app, "POST", &facts, r#"{"facts":{"door":true},"source":"sensor"}"#).await;
+        let body = r#"{"facts":{"shipped":true},"retract":["door"],"source":"sensor"}"#;
+        call(&app, "POST", &facts, body).await;
+        let (_, door) = call(&app, "GET", &format!("{base}/facts/door"), "").await;
+        let (_, shipped) = call(&app, "GET", &format!("{base}/facts/shipped"), "").await;
+        let closed = door["history"].as_array().unwrap().last().unwrap()["retracted_at"].clone();
+        assert_eq!(closed, shipped["history"][1]["recorded_at"], "one transaction time");
+    }
+
+    #[tokio::test]
+    async fn what_if_leaves_the_session_alone() {
+        let app = crate::router();
+        let base = open(&app, r#"{"facts":{"paid":true,"shipped":true}}"#).await;
+        let body = r#"{"rules": {"ship": "paid -> shipped", "open": "~shipped"},
+            "scenarios": [{"name": "returned", "layers": [{"facts": {"shipped": false}}]}]}"#;
+        let (status, v) = call(&app, "POST", &format!("{base}/what-if"), body).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(v["rules"], json!(["ship", "open"]));
+        let changes = &v["scenarios"][0]["changes"];
+        assert_eq!(changes[0]["scenario"], json!({ "value": false, "phase": "MEM", "sheet": "C" }));
+        assert_eq!(changes[1]["rule"], "open");
+        let (_, v) = call(&app, "POST", &format!("{base}/eval"), r#"{"expr":"shipped"}"#).await;
+        assert_eq!(v["value"], true, "scenarios leave the session alone");
+    }
+
+    #[tokio::test]
+    async fn sessions_beyond_the_limit_are_refused() {
+        let cfg = crate::Config {
+            limits: crate::Limits { max_sessions: 1, ..Default::default() },
+            ..Default::default()
+        };
+        let app = crate::app(&cfg);
+        let base = open(&app, "").await;
+        let (status, v) = call(&app, "POST", "/sessions", "").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(v["error"], "session limit of 1 reached");
+        call(&app, "DELETE", &base, "").await;
+        assert_eq!(call(&app, "POST", "/sessions", "").await.0, StatusCode::CREATED);
+    }
+
+    #[test]
+    fn sessions_expire_after_idle_ttl() {
+        let store = SessionStore::new(Duration::from_millis(30), 10);
+        let id = store.create(vec![Fact::new(DEFAULT_SOURCE, "a", true)]).unwrap();
+        assert_eq!(store.with(&id, |ev| ev.export_mem().len()), Some(1));
+        std::thread::sleep(Duration::from_millis(40));
+        assert!(store.with(&id, |_| ()).is_none());
+        assert!(!store.remove(&id), "expired session is dropped on access");
+    }
+}
@@ -0,0 +1,136 @@
+//! Contract tests generated from `grieg-sap/postman/Grieg.postman_collection.json`.
+//!
+//! Every request in the collection is replayed against the router, once as written and once
+//! per field alias that `cpi/Normalizer.groovy` accepts. Responses must be parsable by the
+//! fallback regexes of `ZCL_GRIEG_HTTP_CLIENT` (copied verbatim below).
+
+use axum::body::{to_bytes, Body};
+use axum::http::{Request, StatusCode};
+use regex::Regex;
+use serde_json::{Map, Value};
+use tower::ServiceExt;
+
+const COLLECTION: &str = include_str!("../../grieg-sap/postman/Grieg.postman_collection.json");
+
+const ABAP_VALUE: &str = r#""value"\s*:\s*("?[^"]*"?|null)"#;
+const ABAP_PHASE: &str = r#""phase"\s*:\s*"?([^"]*)"#;
+const ABAP_AST: &str = r#""ast"\s*:\s*"?([^"]*)"#;
+
+struct Call {
+    name: String,
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+fn calls() -> Vec<Call> {
+    let coll: Value = serde_json::from_str(COLLECTION).unwrap();
+    let items = coll["item"].as_array().unwrap();
+    assert!(!items.is_empty(), "collection has no requests");
+    items
+        .iter()
+        .map(|it| {
+            let r = &it["request"];
+            let path = r["url"]["path"].as_array().unwrap();
+            Call {
+                name: it["name"].as_str().unwrap().to_string(),
+                method: r["method"].as_str().unwrap().to_string(),
+                path: format!(
+                    "/{}",
+                    path.iter().map(|p| p.as_str().unwrap()).collect::<Vec<_>>().join("/")
+                ),
+                headers: r["header"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|h| {
+                        (h["key"].as_str().unwrap().into(), h["value"].as_str().unwrap().into())
+                    })
+                    .collect(),
+                body: r["body"]["raw"].as_str().unwrap().to_string(),
+            }
+        })
+        .collect()
+}
+
+async fn send(c: &Call, body: String) -> (StatusCode, String) {
+    let mut req = Request::builder().method(c.method.as_str()).uri(&c.path);
+    for (k, v) in &c.headers {
+        req = req.header(k, v);
+    }
+    let res = grieg_http::router().oneshot(req.body(Body::from(body)).unwrap()).await.unwrap();
+    let status = res.status();
+    let bytes = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    (status, String::from_utf8(bytes.to_vec()).unwrap())
+}
+
+/// What the ABAP client would extract from `raw` without a JSON library.
+fn abap_fallback(raw: &str) -> (String, String, Option<String>) {
+    let cap = |re: &str| Regex::new(re).unwrap().captures(raw).map(|c| c[1].to_string());
+    (cap(ABAP_VALUE).unwrap(), cap(ABAP_PHASE).unwrap(), cap(ABAP_AST))
+}
+
+fn renamed(body: &Map<String, Value>, from: &str, to: &str) -> String {
+    let mut b = body.clone();
+    if let Some(v) = b.remove(from) {
+        b.insert(to.into(), v);
+    }
+    Value::Object(b).to_string()
+}
+
+#[tokio::test]
+async fn collection_requests_satisfy_abap_client() {
+    for c in calls() {
+        let body: Map<String, Value> = serde_json::from_str(&c.body).unwrap();
+        let expr = body["expr"].as_str().unwrap();
+        let variants = [
+            c.body.clone(),
+            renamed(&body, "expr", "expression"),
+            renamed(&body, "expr", "E"),
+            renamed(&body, "mem", "memory"),
+        ];
+
+        let mut seen = Vec::new();
+        for v in variants {
+            let (status, raw) = send(&c, v.clone()).await;
+            assert_eq!(status, StatusCode::OK, "{}: {v} -> {raw}", c.name);
+
+            let (value, phase, ast) = abap_fallback(&raw);
+            assert!(["\"true\"", "\"false\"", "\"null\""].contains(&value.as_str()), "{raw}");
+            assert!(["ALIVE", "JAM", "MEM", "VAC"].contains(&phase.as_str()), "{raw}");
+            if body.get("ast") == Some(&Value::Bool(true)) {
+                assert!(ast.is_some_and(|a| a.starts_with('(')), "{raw}");
+            }
+
+            // A JSON client sees the same fields.
+            let json: Value = serde_json::from_str(&raw).unwrap();
+            assert_eq!(json["input"], expr);
+            assert_eq!(json["value"].as_str(), Some(value.trim_matches('"')));
+            assert_eq!(json["phase"], phase.as_str());
+            seen.push((value, phase));
+        }
+        seen.dedup();
+        assert_eq!(seen.len(), 1, "{}: aliases changed the result: {seen:?}", c.name);
+    }
+}
+
+#[tokio::test]
+async fn plain_text_body_and_parse_errors() {
+    let c = Call {
+        name: "plain".into(),
+        method: "POST".into(),
+        path: "/eval".into(),
+        headers: vec![("Content-Type".into(), "text/plain".into())],
+        body: String::new(),
+    };
+    let (status, raw) = send(&c, "@jam(true) -> true".into()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(abap_fallback(&raw).1, "JAM");
+
+    let (status, raw) = send(&c, "a & )".into()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let json: Value = serde_json::from_str(&raw).unwrap();
+    assert_eq!(json["diagnostics"]["col"], 5);
+    assert!(json["error"].as_str().unwrap().starts_with("parse:"));
+}
@@ -0,0 +1,114 @@
+//! HTTPS and mutual TLS against certificates generated on the fly.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use rcgen::{BasicConstraints, CertificateParams, ExtendedKeyUsagePurpose, IsCa, KeyPair};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+use tokio_rustls::TlsConnector;
+
+use grieg_http::tls::{self, TlsConfig};
+use grieg_http::{app, Config};
+
+struct Pki {
+    dir: PathBuf,
+    ca: CertificateDer<'static>,
+    client: (CertificateDer<'static>, PrivateKeyDer<'static>),
+}
+
+fn pki(name: &str) -> Pki {
+    let dir = std::env::temp_dir().join(format!("grieg-http-{name}-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let ca_key = KeyPair::generate().unwrap();
+    let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    let ca = ca_params.self_signed(&ca_key).unwrap();
+
+    let issue = |eku: ExtendedKeyUsagePurpose, names: Vec<String>| {
+        let key = KeyPair::generate().unwrap();
+        let mut params = CertificateParams::new(names).unwrap();
+        params.extended_key_usages = vec![eku];
+        (params.signed_by(&key, &ca, &ca_key).unwrap(), key)
+    };
+    let (server, server_key) = issue(ExtendedKeyUsagePurpose::ServerAuth, vec!["localhost".into()]);
+    let (client, client_key) = issue(ExtendedKeyUsagePurpose::ClientAuth, Vec::new());
+
+    std::fs::write(dir.join("ca.pem"), ca.pem()).unwrap();
+    std::fs::write(dir.join("server.pem"), server.pem()).unwrap();
+    std::fs::write(dir.join("server.key"), server_key.serialize_pem()).unwrap();
+    let client_key = PrivateKeyDer::try_from(client_key.serialize_der()).unwrap();
+    Pki { dir, ca: ca.der().clone(), client: (client.der().clone(), client_key) }
+}
+
+async fn start(pki: &Pki, mutual: bool) -> u16 {
+    let cfg = TlsConfig {
+        cert: pki.dir.join("server.pem"),
+        key: pki.dir.join("server.key"),
+        client_ca: mutual.then(|| pki.dir.join("ca.pem")),
+    };
+    let server_config = cfg.server_config().unwrap();
+    std::fs::remove_dir_all(&pki.dir).ok();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(tls::serve(
+        listener,
+        app(&Config::default()),
+        server_config,
+        std::future::pending(),
+    ));
+    port
+}
+
+/// POST `/eval` over TLS; `None` when the handshake or the exchange fails.
+async fn post_eval(pki: &Pki, port: u16, with_cert: bool) -> Option<String> {
+    let mut roots = RootCertStore::empty();
+    roots.add(pki.ca.clone()).unwrap();
+    let builder = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots);
+    let config = if with_cert {
+        let (cert, key) = (pki.client.0.clone(), pki.client.1.clone_key());
+        builder.with_client_auth_cert(vec![cert], key).unwrap()
+    } else {
+        builder.with_no_client_auth()
+    };
+    let tcp = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+    let name = ServerName::try_from("localhost").unwrap();
+    let mut tls = TlsConnector::from(Arc::new(config)).connect(name, tcp).await.ok()?;
+
+    let body = r#"{"expr":"@mem(true)","mem":true}"#;
+    let req = format!(
+        "POST /eval HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    tls.write_all(req.as_bytes()).await.ok()?;
+    let mut out = String::new();
+    tls.read_to_string(&mut out).await.ok()?;
+    Some(out)
+}
+
+#[tokio::test]
+async fn serves_https_from_pem_files() {
+    let pki = pki("tls");
+    let port = start(&pki, false).await;
+    let out = post_eval(&pki, port, false).await.expect("TLS exchange");
+    assert!(out.starts_with("HTTP/1.1 200"), "{out}");
+    assert!(out.contains(r#""phase":"MEM""#), "{out}");
+}
+
+#[tokio::test]
+async fn mutual_tls_requires_a_client_certificate() {
+    let pki = pki("mtls");
+    let port = start(&pki, true).await;
+    let out = post_eval(&pki, port, true).await.expect("TLS exchange with client cert");
+    assert!(out.starts_with("HTTP/1.1 200"), "{out}");
+    let refused = post_eval(&pki, port, false).await;
+    assert!(refused.is_none_or(|o| o.is_empty()), "served without a client certificate");
+}
@@ -3,15 +3,28 @@ use std::io::{self, Write};
 
 use chrono::Utc;
 use grieg_engine::eval::Evaluator;
+use grieg_engine::logic::Logic;
 use grieg_engine::phase::Phase;
-use grieg_engine::value::V;
+use grieg_output::{columns, Format, Row, RowWriter};
 use grieg_parser::parse_expr;
-use grieg_telemetry::{JsonlSink, TelemetryEvent, TelemetrySink};
+use grieg_telemetry::{
+    load_signing_key, ChainedSink, JsonlSink, PulseRecord, TelemetryEvent, TelemetrySink,
+};
 
 fn print_usage() {
     eprintln!(r#"Usage:
-  grieg-ipc-cli --expr "<expr>" [--mem] [--ast] [--pretty] [--jsonl <file>]
-  grieg-ipc-cli --jsonl <file> --repl [--mem] [--ast] [--pretty]
+  grieg-ipc-cli --expr "<expr>" [--mem] [--logic <logic>] [--ast] [--format <fmt>] [--jsonl <file>]
+  grieg-ipc-cli --jsonl <file> --repl [--mem] [--logic <logic>] [--ast] [--format <fmt>]
+
+Logics: k3 (default), l3, four, classical; telemetry records the one used.
+
+With --jsonl: --chain hash-chains the records; --sign-key <file> also signs a checkpoint
+(every 1000 records and at exit) with an ed25519 key from `grieg-telemetry keygen`.
+Check the result with `grieg-telemetry verify <file>`.
+
+Formats: ndjson (default), pretty, table, csv, yaml; --pretty is short for --format pretty.
+Parse errors are reported in the error column.
+Columns (same as grieg-cli --expr): input, [ast], value, phase, sheet, error
 
 Examples:
   grieg-ipc-cli --expr "@mem(true -> false)" --mem --pretty --jsonl ./ipc.jsonl
@@ -19,8 +32,6 @@ Examples:
 "#);
 }
 
-fn v_to_bool(v: &V) -> Option<bool> { v.to_bool() }
-
 fn phase_to_str(p: &Phase) -> &'static str {
     match p {
         Phase::ALIVE => "ALIVE",
@@ -35,18 +46,45 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut expr_arg: Option<String> = None;
     let mut repl = false;
     let mut mem = false;
+    let mut logic = Logic::K3;
     let mut ast = false;
-    let mut pretty = false;
+    let mut format = Format::Ndjson;
     let mut jsonl_path: Option<String> = None;
+    let mut chain = false;
+    let mut sign_key: Option<String> = None;
 
     while let Some(a) = args.next() {
         match a.as_str() {
             "--expr" => expr_arg = args.next(),
             "--repl" => repl = true,
             "--mem" => mem = true,
+            "--logic" => match args.next().map(|l| l.parse::<Logic>()) {
+                Some(Ok(l)) => logic = l,
+                Some(Err(e)) => {
+                    eprintln!("{}", e);
+                    std::process::exit(2);
+                }
+                None => {
+                    eprintln!("--logic requires a value");
+                    std::process::exit(2);
+                }
+            },
             "--ast" => ast = true,
-            "--pretty" => pretty = true,
+            "--pretty" => format = Format::Pretty,
+            "--format" => match args.next().map(|f| f.parse::<Format>()) {
+                Some(Ok(f)) => format = f,
+                Some(Err(e)) => {
+                    eprintln!("{}", e);
+                    std::process::exit(2);
+                }
+                None => {
+                    eprintln!("--format requires a value");
+                    std::process::exit(2);
+                }
+            },
             "--jsonl" => jsonl_path = args.next(),
+            "--chain" => chain = true,
+            "--sign-key" => sign_key = args.next(),
             _ => {
                 eprintln!("Unknown arg: {}", a);
                 print_usage();
@@ -60,16 +98,26 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         std::process::exit(2);
     }
 
-    let mut sink_opt: Option<JsonlSink> = match jsonl_path {
-        Some(p) => Some(JsonlSink::create(p)?),
+    let mut sink_opt: Option<Box<dyn TelemetrySink>> = match jsonl_path {
+        Some(p) if chain || sign_key.is_some() => {
+            let sink = ChainedSink::new(JsonlSink::create(p)?);
+            match sign_key {
+                Some(k) => Some(Box::new(sink.signed(load_signing_key(k.as_ref())?, 1000))),
+                None => Some(Box::new(sink)),
+            }
+        }
+        Some(p) => Some(Box::new(JsonlSink::create(p)?)),
         None => None,
     };
 
-    let mut ev = Evaluator::new(mem);
+    let mut ev = Evaluator::new(mem).with_logic(logic);
+    let mut out = RowWriter::new(io::stdout().lock(), format, columns(ast, false));
 
     if let Some(s) = expr_arg {
-        run_once(&mut ev, &s, ast, pretty, sink_opt.as_mut())?;
+        let parsed = run_once(&mut ev, &s, ast, &mut out, sink_opt.as_mut())?;
+        out.finish()?;
         if let Some(sink) = sink_opt.as_mut() { sink.flush()?; }
+        if !parsed { std::process::exit(1); }
         return Ok(());
     }
 
@@ -84,11 +132,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             let line = input.trim();
             if line == ":q" || line == ":quit" { break; }
             if !line.is_empty() {
-                if let Err(e) = run_once(&mut ev, line, ast, pretty, sink_opt.as_mut()) {
+                if let Err(e) = run_once(&mut ev, line, ast, &mut out, sink_opt.as_mut()) {
                     eprintln!("error: {}", e);
                 }
             }
         }
+        // One writer for the whole session: a table is printed once, at the end.
+        out.finish()?;
         if let Some(sink) = sink_opt.as_mut() { sink.flush()?; }
         return Ok(());
     }
@@ -96,47 +146,54 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Evaluate one line and write its row; returns whether it parsed. A parse error is written
+/// to the row's `error` column, as `grieg-cli` does, and records no telemetry.
 fn run_once(
     ev: &mut Evaluator,
     input: &str,
     show_ast: bool,
-    pretty: bool,
-    sink_opt: Option<&mut JsonlSink>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let expr = parse_expr(input)?;
-    let ast_s = grieg_engine::ast::to_sexpr(&expr);
-    let res = ev.eval(&expr, None);
-
-    if pretty {
-        println!("Input: {}", input);
-        if show_ast { println!("AST:   {}", ast_s); }
-        match v_to_bool(&res.value) {
-            Some(b) => println!("Value: {}", b),
-            None => println!("Value: null"),
+    out: &mut RowWriter<impl Write>,
+    sink_opt: Option<&mut Box<dyn TelemetrySink>>,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let expr = match parse_expr(input) {
+        Ok(expr) => expr,
+        Err(e) => {
+            let row = Row {
+                input: Some(input.to_string()),
+                error: Some(format!("parse: {e}")),
+                ..Row::default()
+            };
+            out.write(&row)?;
+            return Ok(false);
         }
-        println!("Phase: {}", phase_to_str(&res.phase));
-    } else {
-        let json = serde_json::json!({
-            "input": input,
-            "ast": if show_ast { serde_json::Value::String(ast_s.clone()) } else { serde_json::Value::Null },
-            "value": v_to_bool(&res.value),
-            "phase": phase_to_str(&res.phase),
-        });
-        println!("{}", serde_json::to_string(&json)?);
-    }
+    };
+    let ast_s = grieg_engine::ast::to_sexpr(&expr);
+    let (res, pulses) = ev.eval_pulsed(&expr);
+
+    let row = Row {
+        input: Some(input.to_string()),
+        ast: if show_ast { Some(ast_s.clone()) } else { None },
+        value: grieg_output::value(&res.value),
+        phase: Some(phase_to_str(&res.phase).to_string()),
+        sheet: Some(res.sheet.to_string()),
+        ..Row::default()
+    };
+    out.write(&row)?;
 
     if let Some(sink) = sink_opt {
-        let mut tev = TelemetryEvent::new();
+        let pulses = pulses.iter().map(PulseRecord::from).collect();
+        let mut tev = TelemetryEvent::new().with_pulses(pulses);
         tev.ts = Utc::now();
         tev.expr = Some(input.to_string());
         if show_ast { tev.ast = Some(ast_s); }
         tev.phase = Some(phase_to_str(&res.phase).to_string());
-        tev.value_bool = v_to_bool(&res.value);
+        tev.sheet = Some(res.sheet.to_string());
+        tev.logic = Some(ev.logic.to_string());
+        tev.value_bool = res.value.to_bool();
         tev.value_text = Some(format!("{:?}", res.value));
-        tev.jam = matches!(res.phase, Phase::JAM);
-        tev.sink = false;
+        tev.jam |= matches!(res.phase, Phase::JAM);
         sink.record(&tev)?;
     }
 
-    Ok(())
+    Ok(true)
 }
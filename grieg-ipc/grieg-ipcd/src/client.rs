@@ -0,0 +1,210 @@
+//! Blocking client for `grieg-ipcd`.
+//!
+//! ```no_run
+//! let mut c = grieg_ipcd::Client::connect(grieg_ipcd::default_socket_path())?;
+//! let r = c.eval("A -> B")?;
+//! println!("{:?} {:?}", r.value, r.phase);
+//! # Ok::<(), grieg_ipcd::ClientError>(())
+//! ```
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use grieg_engine::mem::{ConsistencyReport, Fact, Record};
+use grieg_telemetry::TelemetryEvent;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use thiserror::Error;
+
+use crate::protocol::{Evaluation, EventParams, Request, Response, Traced, EVENT};
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("io: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid message: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("rpc error {code}: {message}")]
+    Rpc { code: i64, message: String, data: Option<Value> },
+    #[error("connection closed by the daemon")]
+    Closed,
+}
+
+pub struct Client {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+    next_id: u64,
+    /// Events that arrived while waiting for a reply.
+    pending: VecDeque<EventParams>,
+}
+
+impl Client {
+    pub fn connect(path: impl AsRef<Path>) -> Result<Self, ClientError> {
+        let writer = UnixStream::connect(path)?;
+        let reader = BufReader::new(writer.try_clone()?);
+        Ok(Self { reader, writer, next_id: 0, pending: VecDeque::new() })
+    }
+
+    /// Call `method` and wait for its reply.
+    pub fn call(&mut self, method: &str, params: Value) -> Result<Value, ClientError> {
+        self.next_id += 1;
+        let id = self.next_id;
+        let mut line = serde_json::to_string(&Request::new(id, method, params))?;
+        line.push('\n');
+        self.writer.write_all(line.as_bytes())?;
+        loop {
+            let msg = self.read()?;
+            if msg.get("method").and_then(Value::as_str) == Some(EVENT) {
+                self.pending.push_back(serde_json::from_value(msg["params"].clone())?);
+                continue;
+            }
+            let res: Response = serde_json::from_value(msg)?;
+            // A null id answers a request the daemon could not read at all.
+            if res.id != json!(id) && !res.id.is_null() {
+                continue;
+            }
+            return match res.error {
+                Some(e) => Err(ClientError::Rpc { code: e.code, message: e.message, data: e.data }),
+                None => Ok(res.result.unwrap_or(Value::Null)),
+            };
+        }
+    }
+
+    fn call_as<T: DeserializeOwned>(
+        &mut self,
+        method: &str,
+        params: Value,
+    ) -> Result<T, ClientError> {
+        Ok(serde_json::from_value(self.call(method, params)?)?)
+    }
+
+    fn read(&mut self) -> Result<Value, ClientError> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(ClientError::Closed);
+        }
+        Ok(serde_json::from_str(&line)?)
+    }
+
+    pub fn eval(&mut self, expr: &str) -> Result<Evaluation, ClientError> {
+        self.call_as("eval", json!({ "expr": expr }))
+    }
+
+    /// [`eval`](Self::eval), also returning the S-expression AST.
+    pub fn eval_with_ast(&mut self, expr: &str) -> Result<Evaluation, ClientError> {
+        self.call_as("eval", json!({ "expr": expr, "ast": true }))
+    }
+
+    /// Evaluate in order; an expression that does not parse gets a row with `error` set.
+    pub fn batch(&mut self, exprs: &[&str]) -> Result<Vec<Evaluation>, ClientError> {
+        self.call_as("batch", json!({ "items": exprs }))
+    }
+
+    pub fn trace(&mut self, expr: &str) -> Result<Traced, ClientError> {
+        self.call_as("trace", json!({ "expr": expr }))
+    }
+
+    /// Assert facts; returns every fact now held.
+    pub fn set_facts(
+        &mut self,
+        facts: &HashMap<String, bool>,
+    ) -> Result<BTreeMap<String, bool>, ClientError> {
+        let res: Value = self.call("facts.set", json!({ "facts": facts }))?;
+        Ok(serde_json::from_value(res["facts"].clone())?)
+    }
+
+    /// Assert facts on behalf of `source`; where another source disagrees, the identifier
+    /// evaluates to JAM. Returns every fact the sources agree on.
+    pub fn set_facts_from(
+        &mut self,
+        source: &str,
+        facts: &HashMap<String, bool>,
+    ) -> Result<BTreeMap<String, bool>, ClientError> {
+        let res: Value = self.call("facts.set", json!({ "facts": facts, "source": source }))?;
+        Ok(serde_json::from_value(res["facts"].clone())?)
+    }
+
+    /// Every contradiction between fact sources.
+    pub fn consistency(&mut self) -> Result<ConsistencyReport, ClientError> {
+        self.call_as("facts.consistency", Value::Null)
+    }
+
+    /// Retract facts; retracted identifiers evaluate to VAC again.
+    pub fn retract_facts(&mut self, names: &[&str]) -> Result<BTreeMap<String, bool>, ClientError> {
+        let facts: BTreeMap<&str, Option<bool>> = names.iter().map(|n| (*n, None)).collect();
+        let res: Value = self.call("facts.set", json!({ "facts": facts }))?;
+        Ok(serde_json::from_value(res["facts"].clone())?)
+    }
+
+    /// Every fact, or just `names` (unknown ones map to `None`).
+    pub fn get_facts(
+        &mut self,
+        names: Option<&[&str]>,
+    ) -> Result<BTreeMap<String, Option<bool>>, ClientError> {
+        let res: Value = self.call("facts.get", json!({ "names": names }))?;
+        Ok(serde_json::from_value(res["facts"].clone())?)
+    }
+
+    /// Every current fact, or those for `names`, with provenance; expired ones included.
+    pub fn fact_records(&mut self, names: Option<&[&str]>) -> Result<Vec<Fact>, ClientError> {
+        let res: Value = self.call("facts.get", json!({ "names": names, "provenance": true }))?;
+        Ok(serde_json::from_value(res["records"].clone())?)
+    }
+
+    /// Every assertion and retraction of `name`, oldest first.
+    pub fn fact_history(&mut self, name: &str) -> Result<Vec<Record>, ClientError> {
+        let res: Value = self.call("facts.history", json!({ "name": name }))?;
+        Ok(serde_json::from_value(res["history"].clone())?)
+    }
+
+    /// Evaluate against the facts valid, and known to the daemon, at `as_of`.
+    pub fn eval_as_of(
+        &mut self,
+        expr: &str,
+        as_of: DateTime<Utc>,
+    ) -> Result<Evaluation, ClientError> {
+        self.call_as("eval", json!({ "expr": expr, "as_of": as_of }))
+    }
+
+    /// Turn this connection into a stream of telemetry for every evaluation on the daemon.
+    pub fn subscribe(mut self) -> Result<Subscription, ClientError> {
+        let res = self.call("subscribe", Value::Null)?;
+        let id = serde_json::from_value(res["subscription"].clone())?;
+        Ok(Subscription { client: self, id })
+    }
+}
+
+/// Telemetry events, in evaluation order, until the daemon closes the connection.
+pub struct Subscription {
+    client: Client,
+    pub id: u64,
+}
+
+impl Iterator for Subscription {
+    type Item = Result<TelemetryEvent, ClientError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(p) = self.client.pending.pop_front() {
+                if p.subscription == self.id {
+                    return Some(Ok(p.event));
+                }
+                continue;
+            }
+            let msg = match self.client.read() {
+                Ok(m) => m,
+                Err(ClientError::Closed) => return None,
+                Err(e) => return Some(Err(e)),
+            };
+            if msg.get("method").and_then(Value::as_str) == Some(EVENT) {
+                match serde_json::from_value::<EventParams>(msg["params"].clone()) {
+                    Ok(p) => self.client.pending.push_back(p),
+                    Err(e) => return Some(Err(e.into())),
+                }
+            }
+        }
+    }
+}
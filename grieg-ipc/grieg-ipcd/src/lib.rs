@@ -0,0 +1,26 @@
+//! `grieg-ipcd`: a long-running evaluator on a Unix domain socket, speaking JSON-RPC 2.0.
+//!
+//! Methods:
+//!
+//! | method        | params                                   | result                          |
+//! |---------------|------------------------------------------|---------------------------------|
+//! | `eval`        | `{expr, ast?, as_of?, known_at?}`        | `{input, [ast], value, phase, sheet, error}` |
+//! | `batch`       | `{items: [expr \| {expr, ast?}], ast?}`  | array of `eval` results         |
+//! | `trace`       | `{expr, ast?}`                           | `{result, steps}`               |
+//! | `facts.set`   | `{facts: {name: bool \| null}, replace?}`| `{facts}` (null retracts)       |
+//! | `facts.get`   | `{names?}`                               | `{facts}`                       |
+//! | `facts.history` | `{name}`                               | `{name, history}`               |
+//! | `facts.compact` | `{before}`                             | `{dropped}`                     |
+//! | `subscribe`   | —                                        | `{subscription}`, then `event`s |
+//! | `unsubscribe` | `{subscription}`                         | `{unsubscribed}`                |
+//! | `telemetry.recent` | `{n?}`                              | `{events, ring, jsonl, subscriber_dropped}` |
+//!
+//! All connections share one evaluator (and so one MEM store) and one telemetry stream. See
+//! [`protocol`] for framing and error codes.
+
+pub mod client;
+pub mod protocol;
+pub mod server;
+
+pub use client::{Client, ClientError, Subscription};
+pub use server::{bind, default_socket_path, Daemon, Options};
@@ -0,0 +1,172 @@
+use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use grieg_ipcd::{bind, default_socket_path, Daemon, Options};
+use grieg_telemetry::load_signing_key;
+use signal_hook::consts::{SIGINT, SIGTERM};
+use signal_hook::iterator::Signals;
+
+fn print_usage() {
+    eprintln!(
+        r#"Usage:
+  grieg-ipcd [--socket <path>] [--mem] [--logic <logic>] [--jsonl <file>] [--rotate-bytes <n>]
+             [--rotate-secs <n>] [--keep <n>] [--chain] [--sign-key <file>]
+             [--checkpoint-every <n>] [--overflow <policy>] [--ring <n>] [--queue <n>]
+             [--max-items <n>]
+
+Listens on a Unix domain socket (mode 0600) and answers newline-delimited JSON-RPC 2.0.
+Methods: eval, batch, trace, facts.set, facts.get, facts.history, facts.compact, subscribe,
+unsubscribe, telemetry.recent.
+
+  --socket        socket path (default: $GRIEG_IPC_SOCKET, else
+                  $XDG_RUNTIME_DIR/grieg-ipcd.sock, else /tmp/grieg-ipcd.sock)
+  --mem           enable MEM semantics and the facts.* methods
+  --logic         truth domain of requests without a `logic` param: k3 (default), l3, four
+                  or classical
+  --jsonl         append one telemetry record per evaluation to <file>
+  --rotate-bytes  rotate <file> to <file>.1 before it grows past <n> bytes
+  --rotate-secs   rotate <file> after writing to it for <n> seconds
+  --keep          rotated files to keep (default 5)
+  --chain         hash-chain the records so that edits are detectable (grieg-telemetry verify)
+  --sign-key      sign a checkpoint with this ed25519 key (grieg-telemetry keygen); implies
+                  --chain
+  --checkpoint-every
+                  records between signed checkpoints (default 1000; one is also written at
+                  shutdown)
+  --overflow      when the file writer is <queue> events behind: block (default), drop-newest
+                  or drop-oldest
+  --ring          recent events kept in memory for telemetry.recent (default 1000)
+  --queue         lines buffered per client before its telemetry is dropped, and events
+                  buffered for the file writer (default 1024)
+  --max-items     most expressions per batch call (default 10000)
+
+Example:
+  grieg-ipcd --mem --jsonl ./ipc.jsonl &
+  echo '{{"jsonrpc":"2.0","id":1,"method":"eval","params":{{"expr":"A -> B"}}}}' \
+    | socat - UNIX-CONNECT:/tmp/grieg-ipcd.sock
+"#
+    );
+}
+
+fn number(flag: &str, v: Option<String>) -> usize {
+    match v.map(|s| s.parse::<usize>()) {
+        Some(Ok(n)) if n > 0 => n,
+        _ => {
+            eprintln!("{flag} requires a positive number");
+            std::process::exit(2);
+        }
+    }
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let mut socket: Option<PathBuf> = None;
+    let mut opts = Options::default();
+
+    while let Some(a) = args.next() {
+        match a.as_str() {
+            "--socket" => socket = args.next().map(PathBuf::from),
+            "--mem" => opts.mem = true,
+            "--logic" => match args.next().map(|s| s.parse()) {
+                Some(Ok(l)) => opts.logic = l,
+                Some(Err(e)) => {
+                    eprintln!("--logic: {e}");
+                    std::process::exit(2);
+                }
+                None => {
+                    eprintln!("--logic requires a value");
+                    std::process::exit(2);
+                }
+            },
+            "--jsonl" => opts.jsonl = args.next().map(PathBuf::from),
+            "--rotate-bytes" => {
+                opts.rotation.max_bytes = Some(number("--rotate-bytes", args.next()) as u64)
+            }
+            "--rotate-secs" => {
+                let secs = number("--rotate-secs", args.next()) as u64;
+                opts.rotation.max_age = Some(Duration::from_secs(secs));
+            }
+            "--keep" => opts.rotation.keep = number("--keep", args.next()),
+            "--chain" => opts.chain = true,
+            "--sign-key" => {
+                let path = args.next().map(PathBuf::from).unwrap_or_else(|| {
+                    eprintln!("--sign-key requires a file");
+                    std::process::exit(2);
+                });
+                match load_signing_key(&path) {
+                    Ok(k) => opts.signing_key = Some(k),
+                    Err(e) => {
+                        eprintln!("--sign-key {}: {e}", path.display());
+                        std::process::exit(2);
+                    }
+                }
+            }
+            "--checkpoint-every" => {
+                opts.checkpoint_every = number("--checkpoint-every", args.next()) as u64
+            }
+            "--overflow" => match args.next().map(|s| s.parse()) {
+                Some(Ok(o)) => opts.overflow = o,
+                Some(Err(e)) => {
+                    eprintln!("--overflow: {e}");
+                    std::process::exit(2);
+                }
+                None => {
+                    eprintln!("--overflow requires a policy");
+                    std::process::exit(2);
+                }
+            },
+            "--ring" => opts.ring = number("--ring", args.next()),
+            "--queue" => opts.queue = number("--queue", args.next()),
+            "--max-items" => opts.max_items = number("--max-items", args.next()),
+            "-h" | "--help" => {
+                print_usage();
+                return;
+            }
+            _ => {
+                eprintln!("Unknown arg: {}", a);
+                print_usage();
+                std::process::exit(2);
+            }
+        }
+    }
+
+    let path = socket.unwrap_or_else(default_socket_path);
+    let daemon = match Daemon::new(opts) {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("grieg-ipcd: telemetry file: {e}");
+            std::process::exit(1);
+        }
+    };
+    let listener = match bind(&path) {
+        Ok(l) => l,
+        Err(e) => {
+            eprintln!("grieg-ipcd: {}: {e}", path.display());
+            std::process::exit(1);
+        }
+    };
+    eprintln!("grieg-ipcd listening on {}", path.display());
+    // SIGTERM/Ctrl-C: finish the telemetry file (and its final checkpoint) before exiting.
+    let mut signals = match Signals::new([SIGTERM, SIGINT]) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("grieg-ipcd: signals: {e}");
+            std::process::exit(1);
+        }
+    };
+    {
+        let (daemon, path) = (daemon.clone(), path.clone());
+        std::thread::spawn(move || {
+            if signals.forever().next().is_some() {
+                daemon.close_telemetry();
+                let _ = std::fs::remove_file(&path);
+                std::process::exit(0);
+            }
+        });
+    }
+    if let Err(e) = daemon.serve(listener) {
+        eprintln!("grieg-ipcd: {e}");
+        std::process::exit(1);
+    }
+}
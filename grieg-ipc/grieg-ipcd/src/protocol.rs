@@ -0,0 +1,137 @@
+//! Wire format: JSON-RPC 2.0, one JSON value per line in both directions.
+//!
+//! A line holding a JSON array is a JSON-RPC batch and is answered with an array. Requests
+//! without an `id` are notifications and get no answer. The daemon pushes telemetry to
+//! subscribers as `event` notifications:
+//!
+//! ```text
+//! {"jsonrpc":"2.0","method":"event","params":{"subscription":1,"event":{...TelemetryEvent...}}}
+//! ```
+
+use grieg_engine::mem::{Conflict, Fact};
+use grieg_engine::phase::{Phase, Sheet};
+use grieg_telemetry::TelemetryEvent;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+
+/// The expression did not parse; `data` is `{line, col, message}`.
+pub const EXPR_PARSE_ERROR: i64 = 1;
+/// `facts.*` on a daemon started without `--mem`.
+pub const MEM_DISABLED: i64 = 2;
+/// A message over the daemon's size limit.
+pub const TOO_LARGE: i64 = 3;
+
+/// Method name of pushed telemetry.
+pub const EVENT: &str = "event";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Request {
+    pub jsonrpc: String,
+    /// `None` only when the member is absent (a notification); `"id": null` is `Some(Null)`
+    /// and is answered.
+    #[serde(default, deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub id: Option<Value>,
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub params: Value,
+}
+
+impl Request {
+    pub fn new(id: u64, method: &str, params: Value) -> Self {
+        Self { jsonrpc: "2.0".into(), id: Some(id.into()), method: method.into(), params }
+    }
+}
+
+/// A member that is present, even as `null`.
+fn present<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Value>, D::Error> {
+    Value::deserialize(d).map(Some)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self { code, message: message.into(), data: None }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Response {
+    pub jsonrpc: String,
+    pub id: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl Response {
+    pub fn new(id: Value, outcome: Result<Value, RpcError>) -> Self {
+        let (result, error) = match outcome {
+            Ok(v) => (Some(v), None),
+            Err(e) => (None, Some(e)),
+        };
+        Self { jsonrpc: "2.0".into(), id, result, error }
+    }
+}
+
+/// Result of `eval` and one item of `batch`: the `grieg-cli --expr` columns.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Evaluation {
+    pub input: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ast: Option<String>,
+    /// `true`, `false`, `null` (a gap) or `"both"` (Belnap's Both).
+    pub value: Value,
+    pub phase: Option<String>,
+    /// `F` (factual) or `C` (counterfactual).
+    #[serde(default)]
+    pub sheet: Option<String>,
+    pub error: Option<String>,
+    /// Facts whose sources disagree (see `facts.set`'s `source`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<Conflict>,
+    /// MEM facts that resolved identifiers, with their provenance.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub witnesses: Vec<Fact>,
+    /// MEM facts skipped because they had expired (see `facts.set`'s `expires_in_s`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expired: Vec<Fact>,
+}
+
+/// One step of a `trace` result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Step {
+    pub op: String,
+    pub pre: Phase,
+    pub post: Phase,
+    #[serde(default)]
+    pub sink: bool,
+    #[serde(default)]
+    pub sheet: Option<Sheet>,
+}
+
+/// Result of `trace`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Traced {
+    pub result: Evaluation,
+    pub steps: Vec<Step>,
+}
+
+/// Params of an `event` notification.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventParams {
+    pub subscription: u64,
+    pub event: TelemetryEvent,
+}
@@ -0,0 +1,643 @@
+//! The daemon: one evaluator and one telemetry stream shared by every connection.
+//!
+//! Each connection gets a reader thread (requests are answered in order) and a writer thread
+//! fed by a bounded queue. Replies wait for room in the queue; telemetry pushed to a slow
+//! subscriber is dropped instead, so a stalled client never blocks evaluation for the others.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use chrono::{DateTime, Utc};
+use grieg_engine::ast::to_sexpr;
+use grieg_engine::eval::{EvalResult, Evaluator};
+use grieg_engine::logic::Logic;
+use grieg_engine::mem::{AsOf, Fact, DEFAULT_SOURCE};
+use grieg_engine::phase::Phase;
+use grieg_engine::pulse::Pulse;
+use grieg_engine::trace::TraceStep;
+use grieg_parser::parse_expr;
+use grieg_telemetry::{
+    ChainedSink, FanOut, Overflow, QueuedSink, RingSink, RotatingSink, Rotation, SigningKey,
+    TelemetryEvent, TelemetrySink, WitnessRecord,
+};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::protocol::*;
+
+/// Daemon settings (`grieg-ipcd` flags).
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Enable MEM semantics and the `facts.*` methods.
+    pub mem: bool,
+    /// Truth domain of requests that do not name one.
+    pub logic: Logic,
+    /// Append every evaluation to this JSONL file.
+    pub jsonl: Option<PathBuf>,
+    /// When to rotate the JSONL file.
+    pub rotation: Rotation,
+    /// Hash-chain the JSONL records (continuing the chain already in the file).
+    pub chain: bool,
+    /// Sign a checkpoint every `checkpoint_every` records (and at shutdown); implies `chain`.
+    pub signing_key: Option<SigningKey>,
+    pub checkpoint_every: u64,
+    /// What to do when the JSONL writer falls `queue` events behind.
+    pub overflow: Overflow,
+    /// Recent events kept in memory for `telemetry.recent`.
+    pub ring: usize,
+    /// Lines queued per connection before telemetry for it is dropped.
+    pub queue: usize,
+    /// Longest accepted request line, in bytes.
+    pub max_line: usize,
+    /// Most expressions in one `batch` call.
+    pub max_items: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            mem: false,
+            logic: Logic::K3,
+            jsonl: None,
+            rotation: Rotation::default(),
+            chain: false,
+            signing_key: None,
+            checkpoint_every: 1000,
+            overflow: Overflow::Block,
+            ring: 1000,
+            queue: 1024,
+            max_line: 1 << 20,
+            max_items: 10_000,
+        }
+    }
+}
+
+/// Socket path from `GRIEG_IPC_SOCKET`, else `$XDG_RUNTIME_DIR/grieg-ipcd.sock`, else
+/// `/tmp/grieg-ipcd.sock`.
+pub fn default_socket_path() -> PathBuf {
+    if let Some(p) = std::env::var_os("GRIEG_IPC_SOCKET") {
+        return p.into();
+    }
+    let dir = std::env::var_os("XDG_RUNTIME_DIR").map_or_else(|| PathBuf::from("/tmp"), Into::into);
+    dir.join("grieg-ipcd.sock")
+}
+
+/// Bind `path` with mode 0600. A socket file nobody listens on (left by a killed daemon) is
+/// replaced; a live one is an `AddrInUse` error.
+pub fn bind(path: &Path) -> io::Result<UnixListener> {
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            let msg = format!("{} is in use by another daemon", path.display());
+            return Err(io::Error::new(io::ErrorKind::AddrInUse, msg));
+        }
+        fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+/// Continue the chain in `path`, or in the newest rotated file when the live one is empty.
+fn chain_onto(file: RotatingSink, path: &Path) -> io::Result<ChainedSink<RotatingSink>> {
+    let live = fs::metadata(path).map_or(0, |m| m.len());
+    let from = if live > 0 { path.to_path_buf() } else { file.rotated_path(1) };
+    ChainedSink::continuing(file, &from)
+        .map_err(|e| io::Error::other(format!("{}: {e}", path.display())))
+}
+
+struct Subscriber {
+    id: u64,
+    conn: u64,
+    tx: SyncSender<String>,
+}
+
+struct Shared {
+    opts: Options,
+    evaluator: Mutex<Evaluator>,
+    /// The ring, then the JSONL file (behind a queue) when configured.
+    sink: Mutex<FanOut>,
+    ring: RingSink,
+    subscribers: Mutex<Vec<Subscriber>>,
+    /// Connection and subscription ids.
+    next_id: AtomicU64,
+    dropped: AtomicU64,
+}
+
+#[derive(Clone)]
+pub struct Daemon {
+    shared: Arc<Shared>,
+}
+
+#[derive(Deserialize)]
+struct EvalParams {
+    expr: String,
+    #[serde(default)]
+    ast: bool,
+    /// Truth domain; the daemon's (`--logic`) when absent.
+    logic: Option<Logic>,
+    /// Evaluate against the facts valid, and known to the daemon, at this time. Such
+    /// evaluations are reviews of the past and are not published.
+    as_of: Option<DateTime<Utc>>,
+    /// Transaction time, when it should differ from `as_of`: what the daemon knew then.
+    known_at: Option<DateTime<Utc>>,
+}
+
+impl EvalParams {
+    fn view(&self) -> Option<AsOf> {
+        match (self.as_of, self.known_at) {
+            (None, None) => None,
+            (valid, known) => {
+                Some(AsOf { valid: valid.unwrap_or_else(Utc::now), known: known.or(valid) })
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BatchItem {
+    Expr(String),
+    Full { expr: String, ast: Option<bool> },
+}
+
+#[derive(Deserialize)]
+struct BatchParams {
+    items: Vec<BatchItem>,
+    #[serde(default)]
+    ast: bool,
+    logic: Option<Logic>,
+}
+
+#[derive(Deserialize)]
+struct FactsSetParams {
+    facts: BTreeMap<String, Option<bool>>,
+    /// Who asserts them; facts from different sources are kept apart, and disagreeing
+    /// ones evaluate to JAM.
+    source: Option<String>,
+    /// Retract every other fact (of `source`, if given) first.
+    #[serde(default)]
+    replace: bool,
+    /// Seconds until the asserted facts expire and their identifiers are VAC again.
+    expires_in_s: Option<u64>,
+    /// How much `source` vouches for them, in `[0, 1]` (default 1).
+    confidence: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct FactsGetParams {
+    names: Option<Vec<String>>,
+    /// Also return every stored record (expired ones included) with its provenance.
+    #[serde(default)]
+    provenance: bool,
+}
+
+#[derive(Deserialize)]
+struct HistoryParams {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct CompactParams {
+    /// Forget records retracted at or before this time.
+    before: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+struct RecentParams {
+    n: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct UnsubscribeParams {
+    subscription: u64,
+}
+
+fn params<T: DeserializeOwned>(p: Value) -> Result<T, RpcError> {
+    let p = if p.is_null() { json!({}) } else { p };
+    serde_json::from_value(p).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+fn phase_to_str(p: &Phase) -> &'static str {
+    match p {
+        Phase::ALIVE => "ALIVE",
+        Phase::JAM => "JAM",
+        Phase::MEM => "MEM",
+        Phase::VAC => "VAC",
+    }
+}
+
+/// A parsed and evaluated expression, ready to answer and publish.
+struct Done {
+    evaluation: Evaluation,
+    steps: Vec<TraceStep>,
+}
+
+impl Daemon {
+    pub fn new(opts: Options) -> io::Result<Self> {
+        let ring = RingSink::new(opts.ring);
+        let mut sink = FanOut::new().with(ring.clone());
+        if let Some(p) = &opts.jsonl {
+            let file = RotatingSink::open(p, opts.rotation.clone())?;
+            if opts.chain || opts.signing_key.is_some() {
+                let chained = chain_onto(file, p)?;
+                let chained = match &opts.signing_key {
+                    Some(key) => chained.signed(key.clone(), opts.checkpoint_every),
+                    None => chained,
+                };
+                sink.push(QueuedSink::new(chained, opts.queue, opts.overflow));
+            } else {
+                sink.push(QueuedSink::new(file, opts.queue, opts.overflow));
+            }
+        }
+        let shared = Shared {
+            evaluator: Mutex::new(Evaluator::new(opts.mem)),
+            opts,
+            sink: Mutex::new(sink),
+            ring,
+            subscribers: Mutex::new(Vec::new()),
+            next_id: AtomicU64::new(1),
+            dropped: AtomicU64::new(0),
+        };
+        Ok(Self { shared: Arc::new(shared) })
+    }
+
+    /// Telemetry events not delivered because a subscriber's queue was full.
+    pub fn dropped_events(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Write out and close the telemetry file, signing a last checkpoint when configured.
+    /// Later evaluations still reach the ring and subscribers.
+    pub fn close_telemetry(&self) {
+        let ring = FanOut::new().with(self.shared.ring.clone());
+        let old = std::mem::replace(&mut *self.shared.sink.lock().unwrap(), ring);
+        drop(old);
+    }
+
+    /// Accept connections until the listener fails.
+    pub fn serve(&self, listener: UnixListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(s) => {
+                    let daemon = self.clone();
+                    thread::spawn(move || {
+                        if let Err(e) = daemon.connection(s) {
+                            eprintln!("grieg-ipcd: connection: {e}");
+                        }
+                    });
+                }
+                // Running out of descriptors is not a reason to stop serving the others.
+                Err(e) => eprintln!("grieg-ipcd: accept: {e}"),
+            }
+        }
+        Ok(())
+    }
+
+    fn connection(&self, stream: UnixStream) -> io::Result<()> {
+        let conn = self.shared.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = mpsc::sync_channel::<String>(self.shared.opts.queue);
+        let mut out = stream.try_clone()?;
+        let writer = thread::spawn(move || {
+            for line in rx {
+                if out.write_all(line.as_bytes()).and_then(|()| out.write_all(b"\n")).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let result = self.read_requests(stream, conn, &tx);
+        self.shared.subscribers.lock().unwrap().retain(|s| s.conn != conn);
+        drop(tx);
+        let _ = writer.join();
+        result
+    }
+
+    fn read_requests(
+        &self,
+        stream: UnixStream,
+        conn: u64,
+        tx: &SyncSender<String>,
+    ) -> io::Result<()> {
+        let max = self.shared.opts.max_line;
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if (&mut reader).take(max as u64 + 1).read_line(&mut line)? == 0 {
+                return Ok(());
+            }
+            if line.len() > max && !line.ends_with('\n') {
+                // The rest of the line is still in flight; there is no resynchronising.
+                let err = RpcError::new(TOO_LARGE, format!("request over {max} bytes"));
+                let _ = tx.send(serde_json::to_string(&Response::new(Value::Null, Err(err)))?);
+                return Ok(());
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(reply) = self.handle(line.trim(), conn, tx) {
+                if tx.send(reply).is_err() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Answer one line: a request, a notification or a batch. `None` when nothing is owed.
+    fn handle(&self, line: &str, conn: u64, tx: &SyncSender<String>) -> Option<String> {
+        let reply = match serde_json::from_str::<Value>(line) {
+            Err(e) => Some(error_reply(Value::Null, PARSE_ERROR, e.to_string())),
+            Ok(Value::Array(calls)) if calls.is_empty() => {
+                Some(error_reply(Value::Null, INVALID_REQUEST, "empty batch".into()))
+            }
+            Ok(Value::Array(calls)) => {
+                let replies: Vec<Value> =
+                    calls.into_iter().filter_map(|c| self.call(c, conn, tx)).collect();
+                (!replies.is_empty()).then_some(Value::Array(replies))
+            }
+            Ok(call) => self.call(call, conn, tx),
+        };
+        reply.map(|r| r.to_string())
+    }
+
+    fn call(&self, call: Value, conn: u64, tx: &SyncSender<String>) -> Option<Value> {
+        let req: Request = match serde_json::from_value(call) {
+            Ok(r) => r,
+            Err(e) => return Some(error_reply(Value::Null, INVALID_REQUEST, e.to_string())),
+        };
+        if req.jsonrpc != "2.0" {
+            let id = req.id.unwrap_or(Value::Null);
+            return Some(error_reply(id, INVALID_REQUEST, "jsonrpc must be \"2.0\"".into()));
+        }
+        let outcome = self.dispatch(&req.method, req.params, conn, tx);
+        let id = req.id?;
+        Some(serde_json::to_value(Response::new(id, outcome)).unwrap())
+    }
+
+    fn dispatch(
+        &self,
+        method: &str,
+        p: Value,
+        conn: u64,
+        tx: &SyncSender<String>,
+    ) -> Result<Value, RpcError> {
+        match method {
+            "eval" => {
+                let p: EvalParams = params(p)?;
+                let done = self.evaluate(&p.expr, p.ast, p.logic, false, p.view())?;
+                Ok(serde_json::to_value(done.evaluation).unwrap())
+            }
+            "batch" => {
+                let p: BatchParams = params(p)?;
+                self.batch(p).map(Value::Array)
+            }
+            "trace" => {
+                let p: EvalParams = params(p)?;
+                let done = self.evaluate(&p.expr, p.ast, p.logic, true, p.view())?;
+                Ok(json!({ "result": done.evaluation, "steps": done.steps }))
+            }
+            "telemetry.recent" => {
+                let p: RecentParams = params(p)?;
+                let events = self.shared.ring.last(p.n.unwrap_or(self.shared.opts.ring));
+                let stats = self.shared.sink.lock().unwrap().branch_stats();
+                Ok(json!({
+                    "events": events,
+                    "ring": stats[0],
+                    "jsonl": stats.get(1),
+                    "subscriber_dropped": self.dropped_events(),
+                }))
+            }
+            "facts.set" => self.facts_set(params(p)?),
+            "facts.get" => self.facts_get(params(p)?),
+            "facts.consistency" => {
+                self.require_mem()?;
+                let report = self.shared.evaluator.lock().unwrap().consistency();
+                Ok(serde_json::to_value(report).unwrap())
+            }
+            "facts.history" => {
+                self.require_mem()?;
+                let p: HistoryParams = params(p)?;
+                let history = self.shared.evaluator.lock().unwrap().mem_history(&p.name);
+                Ok(json!({ "name": p.name, "history": history }))
+            }
+            "facts.compact" => {
+                self.require_mem()?;
+                let p: CompactParams = params(p)?;
+                let dropped = self.shared.evaluator.lock().unwrap().compact_mem(p.before);
+                Ok(json!({ "dropped": dropped }))
+            }
+            "subscribe" => {
+                let id = self.shared.next_id.fetch_add(1, Ordering::Relaxed);
+                let sub = Subscriber { id, conn, tx: tx.clone() };
+                self.shared.subscribers.lock().unwrap().push(sub);
+                Ok(json!({ "subscription": id }))
+            }
+            "unsubscribe" => {
+                let p: UnsubscribeParams = params(p)?;
+                let mut subs = self.shared.subscribers.lock().unwrap();
+                let before = subs.len();
+                subs.retain(|s| !(s.id == p.subscription && s.conn == conn));
+                Ok(json!({ "unsubscribed": subs.len() < before }))
+            }
+            _ => Err(RpcError::new(METHOD_NOT_FOUND, format!("unknown method {method:?}"))),
+        }
+    }
+
+    /// Parse, evaluate (under the shared evaluator, in `logic` or the daemon's) and publish one
+    /// expression; with `as_of`, evaluate from that point in valid and transaction time and do
+    /// not publish.
+    fn evaluate(
+        &self,
+        input: &str,
+        show_ast: bool,
+        logic: Option<Logic>,
+        trace: bool,
+        as_of: Option<AsOf>,
+    ) -> Result<Done, RpcError> {
+        let expr = parse_expr(input).map_err(|e| {
+            let (line, col) = e.line_col();
+            RpcError {
+                code: EXPR_PARSE_ERROR,
+                message: format!("parse: {e}"),
+                data: Some(json!({ "line": line, "col": col, "message": e.to_string() })),
+            }
+        })?;
+        // Evaluate a snapshot, so other clients' writes neither wait for this evaluation nor
+        // land halfway through it.
+        let mut ev = self.shared.evaluator.lock().unwrap().snapshot();
+        if let Some(v) = as_of {
+            (ev.at, ev.known_at) = (Some(v.valid), v.known);
+        }
+        ev.logic = logic.unwrap_or(self.shared.opts.logic);
+        let (res, steps, pulses) = if trace {
+            ev.eval_traced_pulsed(&expr)
+        } else {
+            let (res, pulses) = ev.eval_pulsed(&expr);
+            (res, Vec::new(), pulses)
+        };
+        let ast = to_sexpr(&expr);
+        if as_of.is_none() {
+            self.publish(input, &ast, ev.logic, &res, &pulses);
+        }
+        let evaluation = Evaluation {
+            input: Some(input.to_string()),
+            ast: show_ast.then_some(ast),
+            value: grieg_output::value(&res.value),
+            phase: Some(phase_to_str(&res.phase).to_string()),
+            sheet: Some(res.sheet.to_string()),
+            error: None,
+            conflicts: res.conflicts,
+            witnesses: res.witnesses,
+            expired: res.expired,
+        };
+        Ok(Done { evaluation, steps })
+    }
+
+    fn batch(&self, p: BatchParams) -> Result<Vec<Value>, RpcError> {
+        let max = self.shared.opts.max_items;
+        if p.items.len() > max {
+            let msg = format!("{} items; limit is {max}", p.items.len());
+            return Err(RpcError::new(TOO_LARGE, msg));
+        }
+        let rows = p.items.into_iter().map(|item| {
+            let (expr, ast) = match item {
+                BatchItem::Expr(e) => (e, p.ast),
+                BatchItem::Full { expr, ast } => (expr, ast.unwrap_or(p.ast)),
+            };
+            let evaluation = match self.evaluate(&expr, ast, p.logic, false, None) {
+                Ok(done) => done.evaluation,
+                Err(e) => Evaluation {
+                    input: Some(expr),
+                    ast: None,
+                    value: Value::Null,
+                    phase: None,
+                    sheet: None,
+                    error: Some(e.message),
+                    conflicts: Vec::new(),
+                    witnesses: Vec::new(),
+                    expired: Vec::new(),
+                },
+            };
+            serde_json::to_value(evaluation).unwrap()
+        });
+        Ok(rows.collect())
+    }
+
+    fn facts_set(&self, p: FactsSetParams) -> Result<Value, RpcError> {
+        self.require_mem()?;
+        let mut ev = self.shared.evaluator.lock().unwrap();
+        // One transaction, so an as-of evaluation never sees part of the update.
+        let mut tx = ev.begin();
+        let retract = p.facts.iter().filter(|(_, v)| v.is_none()).map(|(k, _)| k.as_str());
+        let assert: HashMap<String, bool> =
+            p.facts.iter().filter_map(|(k, v)| v.map(|b| (k.clone(), b))).collect();
+        match &p.source {
+            Some(source) => {
+                if p.replace {
+                    tx.retract_source(source);
+                }
+                for name in retract {
+                    tx.retract_from(source, name);
+                }
+            }
+            None => {
+                if p.replace {
+                    tx.clear();
+                }
+                for name in retract {
+                    tx.retract(name);
+                }
+            }
+        }
+        let source = p.source.as_deref().unwrap_or(DEFAULT_SOURCE);
+        for (name, value) in assert {
+            let mut fact = Fact::new(source, name, value);
+            if let Some(secs) = p.expires_in_s {
+                fact = fact.with_ttl_secs(secs);
+            }
+            if let Some(c) = p.confidence {
+                fact = fact.with_confidence(c);
+            }
+            tx.assert(fact);
+        }
+        ev.commit(tx);
+        let facts: BTreeMap<String, bool> = ev.export_mem().into_iter().collect();
+        Ok(json!({ "facts": facts }))
+    }
+
+    fn facts_get(&self, p: FactsGetParams) -> Result<Value, RpcError> {
+        self.require_mem()?;
+        let ev = self.shared.evaluator.lock().unwrap();
+        let all = ev.export_mem();
+        let mut records = ev.mem_facts();
+        if let Some(names) = &p.names {
+            records.retain(|f| names.contains(&f.ident));
+        }
+        let facts: BTreeMap<String, Option<bool>> = match p.names {
+            Some(names) => names.into_iter().map(|n| (n.clone(), all.get(&n).copied())).collect(),
+            None => all.into_iter().map(|(k, v)| (k, Some(v))).collect(),
+        };
+        if p.provenance {
+            return Ok(json!({ "facts": facts, "records": records }));
+        }
+        Ok(json!({ "facts": facts }))
+    }
+
+    fn require_mem(&self) -> Result<(), RpcError> {
+        if self.shared.opts.mem {
+            Ok(())
+        } else {
+            Err(RpcError::new(MEM_DISABLED, "MEM is disabled; start grieg-ipcd with --mem"))
+        }
+    }
+
+    /// Record the evaluation (with its invariant pulses) in the telemetry sinks and push it to
+    /// every subscriber.
+    fn publish(&self, input: &str, ast: &str, logic: Logic, res: &EvalResult, pulses: &[Pulse]) {
+        let mut tev = TelemetryEvent::new().with_pulses(pulses.iter().map(Into::into).collect());
+        tev.expr = Some(input.to_string());
+        tev.ast = Some(ast.to_string());
+        tev.phase = Some(phase_to_str(&res.phase).to_string());
+        tev.sheet = Some(res.sheet.to_string());
+        tev.logic = Some(logic.to_string());
+        tev.value_bool = res.value.to_bool();
+        tev.value_text = Some(format!("{:?}", res.value));
+        tev.jam |= matches!(res.phase, Phase::JAM);
+        tev.witnesses = WitnessRecord::all_of(res);
+
+        // The JSONL queue flushes on its own whenever it catches up.
+        if let Err(e) = self.shared.sink.lock().unwrap().record(&tev) {
+            eprintln!("grieg-ipcd: telemetry: {e}");
+        }
+
+        let mut subs = self.shared.subscribers.lock().unwrap();
+        if subs.is_empty() {
+            return;
+        }
+        subs.retain(|s| {
+            let params = EventParams { subscription: s.id, event: tev.clone() };
+            let note = json!({ "jsonrpc": "2.0", "method": EVENT, "params": params });
+            match s.tx.try_send(note.to_string()) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => {
+                    self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+                    true
+                }
+                Err(TrySendError::Disconnected(_)) => false,
+            }
+        });
+    }
+}
+
+fn error_reply(id: Value, code: i64, message: String) -> Value {
+    serde_json::to_value(Response::new(id, Err(RpcError::new(code, message)))).unwrap()
+}
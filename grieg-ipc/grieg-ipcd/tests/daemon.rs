@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use chrono::Utc;
+use grieg_engine::logic::Logic;
+use grieg_engine::phase::{Phase, Sheet};
+use grieg_ipcd::protocol::{EXPR_PARSE_ERROR, MEM_DISABLED, METHOD_NOT_FOUND};
+use grieg_ipcd::{bind, Client, ClientError, Daemon, Options};
+use serde_json::{json, Value};
+
+/// Start a daemon on a fresh socket path and return the path.
+fn start(opts: Options) -> PathBuf {
+    static N: AtomicUsize = AtomicUsize::new(0);
+    let name =
+        format!("grieg-ipcd-{}-{}.sock", std::process::id(), N.fetch_add(1, Ordering::SeqCst));
+    let path = std::env::temp_dir().join(name);
+    let listener = bind(&path).unwrap();
+    let daemon = Daemon::new(opts).unwrap();
+    thread::spawn(move || daemon.serve(listener));
+    path
+}
+
+#[test]
+fn eval_batch_and_trace() {
+    let path = start(Options::default());
+    assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    let mut c = Client::connect(&path).unwrap();
+
+    let r = c.eval_with_ast("true -> false").unwrap();
+    assert_eq!(r.value, false);
+    assert_eq!((r.phase.as_deref(), r.sheet.as_deref()), (Some("ALIVE"), Some("F")));
+    assert_eq!(r.ast.as_deref(), Some("(-> true false)"));
+
+    match c.eval("true &&") {
+        Err(ClientError::Rpc { code, data: Some(d), .. }) => {
+            assert_eq!(code, EXPR_PARSE_ERROR);
+            assert_eq!(d["line"], 1);
+        }
+        other => panic!("expected a parse error, got {other:?}"),
+    }
+
+    let rows = c.batch(&["@jam(true)", "(", "~false"]).unwrap();
+    assert_eq!(rows[0].phase.as_deref(), Some("JAM"));
+    assert!(rows[1].error.as_deref().unwrap().starts_with("parse:"));
+    assert_eq!(rows[2].value, true);
+
+    let t = c.trace("~true").unwrap();
+    assert_eq!(t.result.value, false);
+    let ops: Vec<&str> = t.steps.iter().map(|s| s.op.as_str()).collect();
+    assert_eq!(ops, ["bool", "not"]);
+    assert_eq!(t.steps[1].post, Phase::ALIVE);
+    assert_eq!(t.steps[1].sheet, Some(Sheet::F));
+
+    match c.call("nope", Value::Null) {
+        Err(ClientError::Rpc { code, .. }) => assert_eq!(code, METHOD_NOT_FOUND),
+        other => panic!("{other:?}"),
+    }
+}
+
+#[test]
+fn facts_are_shared_between_clients() {
+    let path = start(Options { mem: true, ..Options::default() });
+    let mut a = Client::connect(&path).unwrap();
+    let mut b = Client::connect(&path).unwrap();
+
+    let facts = HashMap::from([("rain".to_string(), true), ("wind".to_string(), false)]);
+    assert_eq!(a.set_facts(&facts).unwrap().len(), 2);
+    assert_eq!(b.eval("rain & ~wind").unwrap().value, true);
+
+    b.retract_facts(&["rain"]).unwrap();
+    let got = a.get_facts(Some(&["rain", "wind"])).unwrap();
+    assert_eq!(got["rain"], None);
+    assert_eq!(got["wind"], Some(false));
+
+    // A second source contradicting the first jams the identifier and names both.
+    a.set_facts_from("erp", &HashMap::from([("paid".to_string(), true)])).unwrap();
+    b.set_facts_from("crm", &HashMap::from([("paid".to_string(), false)])).unwrap();
+    let r = a.eval("paid").unwrap();
+    assert_eq!(r.phase.as_deref(), Some("JAM"));
+    let sources: Vec<&str> = r.conflicts[0].sources().collect();
+    assert_eq!(sources, ["crm", "erp"]);
+    let report = b.consistency().unwrap();
+    assert_eq!((report.facts, report.conflicts.len()), (2, 1));
+    assert_eq!(a.get_facts(Some(&["paid"])).unwrap()["paid"], None);
+
+    // Facts carry provenance; a stale one witnesses nothing.
+    let params = json!({ "facts": { "door": true }, "source": "sensor", "confidence": 0.5 });
+    a.call("facts.set", params).unwrap();
+    let r = b.eval("door").unwrap();
+    assert_eq!(r.phase.as_deref(), Some("MEM"));
+    assert_eq!((r.witnesses[0].source.as_str(), r.witnesses[0].confidence), ("sensor", 0.5));
+    let params = json!({ "facts": { "door": true }, "source": "sensor", "expires_in_s": 0 });
+    a.call("facts.set", params).unwrap();
+    let r = b.eval("door").unwrap();
+    assert_eq!((r.phase.as_deref(), r.expired.len()), (Some("VAC"), 1));
+    let records = a.fact_records(Some(&["door"])).unwrap();
+    assert!(records[0].expires_at.is_some());
+
+    // The earlier claim is still on record, and an as-of evaluation still sees it.
+    let history = b.fact_history("door").unwrap();
+    assert_eq!(history.len(), 2);
+    assert!(history[0].retracted_at.is_some() && history[1].retracted_at.is_none());
+    let r = b.eval_as_of("door", history[0].recorded_at).unwrap();
+    assert_eq!(r.phase.as_deref(), Some("MEM"));
+    let res = a.call("facts.compact", json!({ "before": Utc::now() })).unwrap();
+    assert!(res["dropped"].as_u64().unwrap() >= 1);
+    assert_eq!(b.fact_history("door").unwrap().len(), 1);
+
+    let path = start(Options::default());
+    match Client::connect(&path).unwrap().get_facts(None) {
+        Err(ClientError::Rpc { code, .. }) => assert_eq!(code, MEM_DISABLED),
+        other => panic!("{other:?}"),
+    }
+}
+
+#[test]
+fn subscriber_sees_other_clients_evaluations() {
+    let path = start(Options::default());
+    let mut events = Client::connect(&path).unwrap().subscribe().unwrap();
+    let mut c = Client::connect(&path).unwrap();
+    c.eval("@jam(true)").unwrap();
+    c.eval("true | false").unwrap();
+    c.eval("true -> ~~true").unwrap();
+
+    let first = events.next().unwrap().unwrap();
+    assert_eq!(first.expr.as_deref(), Some("@jam(true)"));
+    assert!(first.jam);
+    let second = events.next().unwrap().unwrap();
+    assert_eq!(second.phase.as_deref(), Some("ALIVE"));
+    assert_eq!(second.value_bool, Some(true));
+    let third = events.next().unwrap().unwrap();
+    assert!(third.sink);
+    let kinds: Vec<&str> = third.pulses.iter().map(|p| p.kind.as_str()).collect();
+    assert_eq!(kinds, ["WINDING", "SINK"]);
+}
+
+#[test]
+fn logic_per_daemon_and_per_call() {
+    let path = start(Options { mem: true, logic: Logic::Four, ..Options::default() });
+    let mut c = Client::connect(&path).unwrap();
+    c.set_facts_from("erp", &HashMap::from([("paid".to_string(), true)])).unwrap();
+    c.set_facts_from("crm", &HashMap::from([("paid".to_string(), false)])).unwrap();
+
+    let r = c.eval("paid").unwrap();
+    assert_eq!((&r.value, r.phase.as_deref()), (&json!("both"), Some("JAM")));
+    let r = c.call("eval", json!({ "expr": "paid", "logic": "k3" })).unwrap();
+    assert_eq!((&r["value"], &r["phase"]), (&Value::Null, &json!("JAM")));
+    let rows = c.call("batch", json!({ "items": ["paid"], "logic": "classical" })).unwrap();
+    assert_eq!(rows[0]["value"], false);
+
+    let recent = c.call("telemetry.recent", json!({})).unwrap();
+    let logics: Vec<&str> =
+        recent["events"].as_array().unwrap().iter().map(|e| e["logic"].as_str().unwrap()).collect();
+    assert_eq!(logics, ["four", "k3", "classical"]);
+}
+
+#[test]
+fn recent_events_and_rotated_jsonl() {
+    let dir = std::env::temp_dir().join(format!("grieg-ipcd-rot-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let jsonl = dir.join("ipc.jsonl");
+    let mut opts = Options { jsonl: Some(jsonl.clone()), ring: 2, ..Options::default() };
+    opts.rotation.max_bytes = Some(1);
+    let path = start(opts);
+    let mut c = Client::connect(&path).unwrap();
+    for e in ["true", "false", "~true"] {
+        c.eval(e).unwrap();
+    }
+
+    let recent = c.call("telemetry.recent", json!({ "n": 5 })).unwrap();
+    let exprs: Vec<&str> =
+        recent["events"].as_array().unwrap().iter().map(|e| e["expr"].as_str().unwrap()).collect();
+    assert_eq!(exprs, ["false", "~true"]);
+    assert_eq!(recent["ring"]["dropped"], 1);
+
+    // One record per file, newest in the live file; the writer catches up asynchronously.
+    let files = [jsonl.clone(), dir.join("ipc.jsonl.1"), dir.join("ipc.jsonl.2")];
+    let read = |p: &PathBuf| std::fs::read_to_string(p).unwrap_or_default();
+    for _ in 0..200 {
+        if files.iter().all(|f| !read(f).is_empty()) {
+            break;
+        }
+        thread::sleep(std::time::Duration::from_millis(10));
+    }
+    let got: Vec<String> = files
+        .iter()
+        .map(|f| serde_json::from_str::<Value>(read(f).trim()).unwrap()["expr"].to_string())
+        .collect();
+    assert_eq!(got, ["\"~true\"", "\"false\"", "\"true\""]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn raw_batches_and_notifications() {
+    let path = start(Options::default());
+    let stream = UnixStream::connect(&path).unwrap();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut w = stream;
+    let mut line = String::new();
+
+    // A notification (no id) is evaluated but not answered; the batch reply comes next.
+    let calls = json!([
+        { "jsonrpc": "2.0", "method": "eval", "params": { "expr": "true" } },
+        { "jsonrpc": "2.0", "id": 7, "method": "eval", "params": { "expr": "false" } },
+        { "jsonrpc": "2.0", "id": 8, "method": "eval" },
+    ]);
+    writeln!(w, "{calls}").unwrap();
+    reader.read_line(&mut line).unwrap();
+    let replies: Value = serde_json::from_str(&line).unwrap();
+    assert_eq!(replies.as_array().unwrap().len(), 2);
+    assert_eq!(replies[0]["id"], 7);
+    assert_eq!(replies[0]["result"]["value"], false);
+    assert_eq!(replies[1]["error"]["code"], -32602);
+
+    // An explicit null id is a request, not a notification, and is answered.
+    line.clear();
+    writeln!(w, r#"{{"jsonrpc":"2.0","id":null,"method":"eval","params":{{"expr":"true"}}}}"#)
+        .unwrap();
+    reader.read_line(&mut line).unwrap();
+    let reply: Value = serde_json::from_str(&line).unwrap();
+    assert_eq!((&reply["id"], &reply["result"]["value"]), (&Value::Null, &json!(true)));
+
+    line.clear();
+    writeln!(w, "{{not json").unwrap();
+    reader.read_line(&mut line).unwrap();
+    let reply: Value = serde_json::from_str(&line).unwrap();
+    assert_eq!(reply["error"]["code"], -32700);
+    assert_eq!(reply["id"], Value::Null);
+}
+
+#[test]
+fn live_socket_is_not_replaced() {
+    let path = start(Options::default());
+    let err = bind(&path).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::AddrInUse);
+}
@@ -0,0 +1,399 @@
+//! Replay a telemetry log against the current engine.
+//!
+//! Every event's `expr` (or, failing that, its s-expression `ast`) is parsed and evaluated
+//! again, under the event's recorded `logic` (K3 when absent); events whose recorded `phase`, `value_bool` or `sheet` no longer come out the same
+//! are reported, so an engine change that alters past conclusions shows up on real traffic.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use grieg_engine::ast::{to_sexpr, Expr};
+use grieg_engine::eval::Evaluator;
+use grieg_engine::logic::Logic;
+use grieg_engine::mem::MemDoc;
+use grieg_engine::phase::Phase;
+use grieg_parser::{parse_expr, parse_sexpr};
+use grieg_telemetry::TelemetryEvent;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Which recorded field to re-parse.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Source {
+    /// `expr` when present, else `ast`; with both, a changed parse of `expr` is also reported.
+    #[default]
+    Auto,
+    Expr,
+    Ast,
+}
+
+impl FromStr for Source {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Source::Auto),
+            "expr" => Ok(Source::Expr),
+            "ast" => Ok(Source::Ast),
+            _ => Err(format!("unknown source {s:?} (auto, expr, ast)")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ReplayOptions {
+    /// Only events at or after this time.
+    pub since: Option<DateTime<Utc>>,
+    /// Only events before this time.
+    pub until: Option<DateTime<Utc>>,
+    /// Only events on these channels (any channel when empty).
+    pub channels: Vec<String>,
+    /// Evaluate with MEM enabled, over `facts`. The log does not say which facts held when an
+    /// event was recorded, so replays of MEM traffic are only as good as these. Each event is
+    /// evaluated as of its `ts`, so facts that had expired by then are VAC.
+    pub mem: bool,
+    pub facts: MemDoc,
+    pub from: Source,
+}
+
+/// `2025-09-10T12:00:00Z` (any RFC 3339 time) or `2025-09-10` (midnight UTC).
+pub fn parse_time(s: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+        return Ok(t.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map(|d| d.and_hms_opt(0, 0, 0).unwrap().and_utc())
+        .map_err(|_| format!("{s:?} is neither an RFC 3339 time nor a YYYY-MM-DD date"))
+}
+
+/// One field that came out differently.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Change {
+    pub field: &'static str,
+    pub recorded: Value,
+    pub replayed: Value,
+}
+
+/// An event whose replay does not match its record.
+#[derive(Debug, Clone, Serialize)]
+pub struct Diff {
+    pub source: String,
+    pub line: usize,
+    pub ts: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expr: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ast: Option<String>,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ReplaySummary {
+    /// Non-empty lines read.
+    pub read: u64,
+    /// Outside the time range or channels.
+    pub filtered: u64,
+    /// No `expr`/`ast` to replay, or no recorded outcome to compare (checkpoints, for one).
+    pub skipped: u64,
+    /// Lines that are not telemetry records.
+    pub unreadable: u64,
+    pub unchanged: u64,
+    pub changed: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReplayReport {
+    pub summary: ReplaySummary,
+    pub diffs: Vec<Diff>,
+}
+
+impl ReplayReport {
+    pub fn ok(&self) -> bool {
+        self.diffs.is_empty()
+    }
+}
+
+fn phase_to_str(p: &Phase) -> &'static str {
+    match p {
+        Phase::ALIVE => "ALIVE",
+        Phase::JAM => "JAM",
+        Phase::MEM => "MEM",
+        Phase::VAC => "VAC",
+    }
+}
+
+/// Replays lines in order, possibly from several files.
+pub struct Replayer {
+    opts: ReplayOptions,
+    evaluator: Evaluator,
+    report: ReplayReport,
+}
+
+impl Replayer {
+    pub fn new(opts: ReplayOptions) -> Self {
+        let mut evaluator = Evaluator::new(opts.mem);
+        evaluator.import_mem_doc(opts.facts.clone());
+        Self { opts, evaluator, report: ReplayReport::default() }
+    }
+
+    pub fn replay_file(&mut self, path: &Path) -> io::Result<()> {
+        let source = path.display().to_string();
+        for (i, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+            self.replay_line(&source, i + 1, &line?);
+        }
+        Ok(())
+    }
+
+    /// Replay one line; `source` and `line` only label the diff.
+    pub fn replay_line(&mut self, source: &str, line: usize, text: &str) {
+        if text.trim().is_empty() {
+            return;
+        }
+        let s = &mut self.report.summary;
+        s.read += 1;
+        let Ok(ev) = serde_json::from_str::<TelemetryEvent>(text) else {
+            s.unreadable += 1;
+            return;
+        };
+        if !self.selected(&ev) {
+            self.report.summary.filtered += 1;
+            return;
+        }
+        match self.changes(&ev) {
+            None => self.report.summary.skipped += 1,
+            Some(changes) if changes.is_empty() => self.report.summary.unchanged += 1,
+            Some(changes) => {
+                self.report.summary.changed += 1;
+                self.report.diffs.push(Diff {
+                    source: source.to_string(),
+                    line,
+                    ts: ev.ts,
+                    expr: ev.expr,
+                    ast: ev.ast,
+                    changes,
+                });
+            }
+        }
+    }
+
+    fn selected(&self, ev: &TelemetryEvent) -> bool {
+        let o = &self.opts;
+        o.since.is_none_or(|t| ev.ts >= t)
+            && o.until.is_none_or(|t| ev.ts < t)
+            && (o.channels.is_empty()
+                || ev.channel.as_ref().is_some_and(|c| o.channels.contains(c)))
+    }
+
+    /// `None` when there is nothing to replay or nothing recorded to compare with.
+    fn changes(&mut self, ev: &TelemetryEvent) -> Option<Vec<Change>> {
+        let recorded_phase = ev.phase.as_deref()?;
+        let mut changes = Vec::new();
+        let parsed: Result<Expr, String> = match (self.opts.from, &ev.expr, &ev.ast) {
+            (Source::Auto | Source::Expr, Some(src), _) => {
+                parse_expr(src).map_err(|e| e.to_string())
+            }
+            (Source::Auto | Source::Ast, _, Some(ast)) => {
+                parse_sexpr(ast).map_err(|e| e.to_string())
+            }
+            _ => return None,
+        };
+        let expr = match parsed {
+            Ok(e) => e,
+            Err(e) => {
+                changes.push(Change {
+                    field: "parse",
+                    recorded: Value::from("ok"),
+                    replayed: Value::from(e),
+                });
+                return Some(changes);
+            }
+        };
+        if let (Source::Auto, Some(_), Some(ast)) = (self.opts.from, &ev.expr, &ev.ast) {
+            let now = to_sexpr(&expr);
+            if &now != ast {
+                changes.push(Change {
+                    field: "ast",
+                    recorded: Value::from(ast.as_str()),
+                    replayed: Value::from(now),
+                });
+            }
+        }
+        self.evaluator.logic = match ev.logic.as_deref().map(str::parse::<Logic>) {
+            None => Logic::K3,
+            Some(Ok(logic)) => logic,
+            Some(Err(e)) => {
+                let recorded = Value::from(ev.logic.clone());
+                changes.push(Change { field: "logic", recorded, replayed: Value::from(e) });
+                return Some(changes);
+            }
+        };
+        self.evaluator.at = Some(ev.ts);
+        let res = self.evaluator.eval(&expr, None);
+        let phase = phase_to_str(&res.phase);
+        if phase != recorded_phase {
+            changes.push(Change {
+                field: "phase",
+                recorded: Value::from(recorded_phase),
+                replayed: Value::from(phase),
+            });
+        }
+        let value = res.value.to_bool();
+        if value != ev.value_bool {
+            changes.push(Change {
+                field: "value_bool",
+                recorded: ev.value_bool.into(),
+                replayed: value.into(),
+            });
+        }
+        if let Some(recorded) = ev.sheet.as_deref() {
+            if recorded != res.sheet.as_str() {
+                changes.push(Change {
+                    field: "sheet",
+                    recorded: Value::from(recorded),
+                    replayed: Value::from(res.sheet.as_str()),
+                });
+            }
+        }
+        Some(changes)
+    }
+
+    pub fn finish(self) -> ReplayReport {
+        self.report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(expr: Option<&str>, ast: Option<&str>, phase: &str, value: Option<bool>) -> String {
+        let ev = TelemetryEvent {
+            expr: expr.map(Into::into),
+            ast: ast.map(Into::into),
+            phase: Some(phase.to_string()),
+            value_bool: value,
+            ..TelemetryEvent::new()
+        };
+        serde_json::to_string(&ev).unwrap()
+    }
+
+    fn replay(opts: ReplayOptions, lines: &[String]) -> ReplayReport {
+        let mut r = Replayer::new(opts);
+        for (i, l) in lines.iter().enumerate() {
+            r.replay_line("log", i + 1, l);
+        }
+        r.finish()
+    }
+
+    #[test]
+    fn reports_outcomes_that_moved() {
+        let lines = [
+            record(Some("true -> false"), None, "ALIVE", Some(false)),
+            // As if an older engine had said otherwise.
+            record(Some("@jam(true)"), None, "ALIVE", Some(true)),
+            record(None, Some("(~ id:x)"), "VAC", None),
+            record(Some("~x"), Some("(~ id:y)"), "VAC", None),
+            record(Some("true &&"), None, "ALIVE", Some(true)),
+            serde_json::to_string(&TelemetryEvent::new()).unwrap(),
+            "not json".to_string(),
+            // An unwitnessed identifier is counterfactual.
+            serde_json::to_string(&TelemetryEvent {
+                expr: Some("x".into()),
+                phase: Some("VAC".into()),
+                sheet: Some("F".into()),
+                ..TelemetryEvent::new()
+            })
+            .unwrap(),
+        ];
+        let r = replay(ReplayOptions::default(), &lines);
+        let s = &r.summary;
+        assert_eq!((s.read, s.unchanged, s.changed, s.skipped, s.unreadable), (8, 2, 4, 1, 1));
+        let fields: Vec<(usize, Vec<&str>)> =
+            r.diffs.iter().map(|d| (d.line, d.changes.iter().map(|c| c.field).collect())).collect();
+        assert_eq!(
+            fields,
+            [(2, vec!["phase"]), (4, vec!["ast"]), (5, vec!["parse"]), (8, vec!["sheet"])]
+        );
+        assert_eq!(r.diffs[0].changes[0].replayed, "JAM");
+
+        let opts = ReplayOptions { from: Source::Ast, ..ReplayOptions::default() };
+        let r = replay(opts, &lines[3..4]);
+        assert!(r.ok());
+    }
+
+    #[test]
+    fn replays_under_the_recorded_logic() {
+        let under = |logic: Option<&str>, value: Option<bool>| {
+            let ev = TelemetryEvent {
+                expr: Some("x | ~x".into()),
+                phase: Some("VAC".into()),
+                logic: logic.map(Into::into),
+                value_bool: value,
+                ..TelemetryEvent::new()
+            };
+            serde_json::to_string(&ev).unwrap()
+        };
+        let lines =
+            [under(None, None), under(Some("k3"), None), under(Some("classical"), Some(true))];
+        let r = replay(ReplayOptions::default(), &lines);
+        assert!(r.ok(), "{:?}", r.diffs);
+        let r = replay(ReplayOptions::default(), &[under(Some("fuzzy"), None)]);
+        assert_eq!(r.diffs[0].changes[0].field, "logic");
+    }
+
+    #[test]
+    fn filters_by_time_and_channel() {
+        let at = |ts: &str, channel: &str| {
+            let ev = TelemetryEvent {
+                ts: parse_time(ts).unwrap(),
+                channel: Some(channel.to_string()),
+                expr: Some("true".into()),
+                phase: Some("JAM".into()),
+                ..TelemetryEvent::new()
+            };
+            serde_json::to_string(&ev).unwrap()
+        };
+        let lines = [
+            at("2025-01-01T00:00:00Z", "ipc.v0"),
+            at("2025-01-02T12:00:00+02:00", "ipc.v0"),
+            at("2025-01-02T12:00:00Z", "http"),
+            at("2025-01-03", "ipc.v0"),
+        ];
+        let opts = ReplayOptions {
+            since: Some(parse_time("2025-01-02").unwrap()),
+            until: Some(parse_time("2025-01-03").unwrap()),
+            channels: vec!["ipc.v0".into()],
+            ..ReplayOptions::default()
+        };
+        let r = replay(opts, &lines);
+        assert_eq!((r.summary.filtered, r.summary.changed), (3, 1));
+        assert_eq!(r.diffs[0].line, 2);
+        assert!(parse_time("yesterday").is_err());
+    }
+
+    #[test]
+    fn facts_are_judged_at_each_event_time() {
+        let doc = r#"{"version": 2, "facts": [{"ident": "door", "value": true, "source": "sensor",
+            "asserted_at": "2025-01-01T00:00:00Z", "expires_at": "2025-01-02T00:00:00Z"}]}"#;
+        let at = |ts: &str, phase: &str| {
+            let ev = TelemetryEvent {
+                ts: parse_time(ts).unwrap(),
+                expr: Some("door".into()),
+                phase: Some(phase.into()),
+                value_bool: (phase == "MEM").then_some(true),
+                ..TelemetryEvent::new()
+            };
+            serde_json::to_string(&ev).unwrap()
+        };
+        let opts = ReplayOptions {
+            mem: true,
+            facts: serde_json::from_str(doc).unwrap(),
+            ..ReplayOptions::default()
+        };
+        let r = replay(opts, &[at("2025-01-01T12:00:00Z", "MEM"), at("2025-01-02", "VAC")]);
+        assert!(r.ok(), "{:?}", r.diffs);
+    }
+}
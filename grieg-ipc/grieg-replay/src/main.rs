@@ -0,0 +1,119 @@
+use std::env;
+use std::path::PathBuf;
+
+use grieg_replay::{parse_time, ReplayOptions, Replayer};
+use serde_json::Value;
+
+fn print_usage() {
+    eprintln!(
+        r#"Usage:
+  grieg-replay [--since <time>] [--until <time>] [--channel <name>]... [--mem] [--facts <file>]
+               [--from auto|expr|ast] [--json] <file>...
+
+Re-evaluates every event of a telemetry JSONL log (rotated files oldest first) with this
+build of the engine and prints a diff for each event whose phase or value_bool changed.
+Exits 1 when anything changed.
+
+  --since, --until  keep events with since <= ts < until (RFC 3339, or YYYY-MM-DD for
+                    midnight UTC)
+  --channel         keep only this channel; repeatable
+  --mem             evaluate with MEM enabled
+  --facts           MEM facts to evaluate over: a JSON object {{"name": true, ...}} or an
+                    exported MEM document {{"version": 2, "facts": [...]}}; each event sees
+                    the facts live at its ts
+  --from            re-parse expr (falling back to ast; the default), only expr, or only ast
+  --json            print the report as JSON
+
+Example:
+  grieg-replay --mem --since 2025-09-01 --channel ipc.v0 ipc.jsonl.1 ipc.jsonl
+"#
+    );
+}
+
+fn fail(msg: impl std::fmt::Display) -> ! {
+    eprintln!("grieg-replay: {msg}");
+    std::process::exit(2);
+}
+
+fn value(flag: &str, v: Option<String>) -> String {
+    v.unwrap_or_else(|| fail(format!("{flag} requires a value")))
+}
+
+fn text(v: &Value) -> String {
+    match v {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let mut opts = ReplayOptions::default();
+    let mut json = false;
+    let mut files: Vec<PathBuf> = Vec::new();
+
+    while let Some(a) = args.next() {
+        match a.as_str() {
+            "--since" => {
+                opts.since = Some(parse_time(&value(&a, args.next())).unwrap_or_else(|e| fail(e)))
+            }
+            "--until" => {
+                opts.until = Some(parse_time(&value(&a, args.next())).unwrap_or_else(|e| fail(e)))
+            }
+            "--channel" => opts.channels.push(value(&a, args.next())),
+            "--mem" => opts.mem = true,
+            "--facts" => {
+                let path = value(&a, args.next());
+                let raw =
+                    std::fs::read_to_string(&path).unwrap_or_else(|e| fail(format!("{path}: {e}")));
+                opts.facts =
+                    serde_json::from_str(&raw).unwrap_or_else(|e| fail(format!("{path}: {e}")));
+            }
+            "--from" => opts.from = value(&a, args.next()).parse().unwrap_or_else(|e| fail(e)),
+            "--json" => json = true,
+            "-h" | "--help" => {
+                print_usage();
+                return;
+            }
+            _ if a.starts_with("--") => {
+                eprintln!("Unknown arg: {}", a);
+                print_usage();
+                std::process::exit(2);
+            }
+            _ => files.push(a.into()),
+        }
+    }
+    if files.is_empty() {
+        print_usage();
+        std::process::exit(2);
+    }
+
+    let mut replayer = Replayer::new(opts);
+    for f in &files {
+        if let Err(e) = replayer.replay_file(f) {
+            fail(format!("{}: {e}", f.display()));
+        }
+    }
+    let report = replayer.finish();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    } else {
+        println!("--- recorded");
+        println!("+++ replayed");
+        for d in &report.diffs {
+            let what = d.expr.as_deref().or(d.ast.as_deref()).unwrap_or("");
+            println!("@@ {}:{} {} {what}", d.source, d.line, d.ts.to_rfc3339());
+            for c in &d.changes {
+                println!("-{}: {}", c.field, text(&c.recorded));
+                println!("+{}: {}", c.field, text(&c.replayed));
+            }
+        }
+        let s = &report.summary;
+        println!(
+            "{} events: {} unchanged, {} changed, {} filtered out, {} skipped, {} unreadable",
+            s.read, s.unchanged, s.changed, s.filtered, s.skipped, s.unreadable
+        );
+    }
+    std::process::exit(if report.ok() { 0 } else { 1 });
+}
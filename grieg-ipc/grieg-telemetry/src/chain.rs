@@ -0,0 +1,243 @@
+//! Hash-chained, optionally signed logs: each record carries the hash of the line before it.
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use ed25519_dalek::{Signer, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::{SinkStats, TelemetryEvent, TelemetrySink};
+
+/// `prev` of the first record of a log.
+pub const GENESIS: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// `channel` of checkpoint records.
+pub const CHECKPOINT_CHANNEL: &str = "grieg.checkpoint";
+
+/// Signature over the log up to a checkpoint record. The record's own `seq` and `prev` say what
+/// is covered: `prev` is the hash of the line before it, which commits to every line before that.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Ed25519 public key, hex.
+    pub key: String,
+    /// Ed25519 signature over [`checkpoint_message`], hex.
+    pub signature: String,
+}
+
+#[derive(Debug, Error)]
+pub enum ChainError {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("last record is not JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("last record is not chained (no seq/prev); start a new log")]
+    Unchained,
+    #[error("bad key: {0}")]
+    Key(String),
+}
+
+/// SHA-256 (hex) of one line, without its newline.
+pub fn line_hash(line: &[u8]) -> String {
+    hex::encode(Sha256::digest(line))
+}
+
+/// What a checkpoint at `seq`, following a line hashing to `prev`, signs.
+pub fn checkpoint_message(seq: u64, prev: &str) -> Vec<u8> {
+    format!("grieg-telemetry checkpoint v1\n{seq}\n{prev}\n").into_bytes()
+}
+
+/// A fresh signing key from the operating system's random source.
+pub fn generate_key() -> io::Result<SigningKey> {
+    let mut seed = [0u8; 32];
+    File::open("/dev/urandom")?.read_exact(&mut seed)?;
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+/// Read a signing key stored as its 32-byte seed in hex.
+pub fn load_signing_key(path: &Path) -> Result<SigningKey, ChainError> {
+    let text = std::fs::read_to_string(path)?;
+    let seed = decode32(text.trim())?;
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+/// A public key in hex.
+pub fn parse_verifying_key(hex_key: &str) -> Result<VerifyingKey, ChainError> {
+    VerifyingKey::from_bytes(&decode32(hex_key.trim())?).map_err(|e| ChainError::Key(e.to_string()))
+}
+
+fn decode32(s: &str) -> Result<[u8; 32], ChainError> {
+    let mut out = [0u8; 32];
+    hex::decode_to_slice(s, &mut out).map_err(|e| ChainError::Key(e.to_string()))?;
+    Ok(out)
+}
+
+/// The last non-empty line of `path`, or `None` when there is none (or no file).
+pub(crate) fn last_line(path: &Path) -> io::Result<Option<String>> {
+    let mut f = match File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let len = f.metadata()?.len();
+    let mut tail = Vec::new();
+    let mut pos = len;
+    // Read backwards until the buffer holds a complete non-empty line.
+    loop {
+        let text = String::from_utf8_lossy(&tail);
+        let trimmed = text.trim_end();
+        if let Some(i) = trimmed.rfind('\n') {
+            return Ok(Some(trimmed[i + 1..].to_string()));
+        }
+        if pos == 0 {
+            return Ok((!trimmed.is_empty()).then(|| trimmed.to_string()));
+        }
+        let step = pos.min(8192);
+        pos -= step;
+        let mut chunk = vec![0u8; step as usize];
+        f.seek(SeekFrom::Start(pos))?;
+        f.read_exact(&mut chunk)?;
+        chunk.extend_from_slice(&tail);
+        tail = chunk;
+    }
+}
+
+/// Sets `seq` and `prev` on every event before handing it to `inner`, so that deleting,
+/// reordering or editing a line breaks the chain (see [`Verifier`](crate::Verifier)).
+///
+/// With a signing key, a checkpoint record is appended every `every` records and when the sink
+/// is dropped. Hashes are over the exact line the inner sink writes, so `inner` must write
+/// compact JSON lines ([`JsonlSink`](crate::JsonlSink), [`RotatingSink`](crate::RotatingSink)).
+pub struct ChainedSink<S: TelemetrySink> {
+    inner: S,
+    seq: u64,
+    head: String,
+    signer: Option<(SigningKey, u64)>,
+    since_checkpoint: u64,
+}
+
+impl<S: TelemetrySink> ChainedSink<S> {
+    /// Start a new log.
+    pub fn new(inner: S) -> Self {
+        Self { inner, seq: 0, head: GENESIS.to_string(), signer: None, since_checkpoint: 0 }
+    }
+
+    /// Continue the log whose last line is `last`.
+    pub fn resume(inner: S, last: &str) -> Result<Self, ChainError> {
+        let ev: TelemetryEvent = serde_json::from_str(last)?;
+        let seq = ev.seq.ok_or(ChainError::Unchained)?;
+        let mut sink = Self::new(inner);
+        sink.seq = seq + 1;
+        sink.head = line_hash(last.as_bytes());
+        Ok(sink)
+    }
+
+    /// Continue the log in `path` if it has records, else start a new one.
+    pub fn continuing(inner: S, path: &Path) -> Result<Self, ChainError> {
+        match last_line(path)? {
+            Some(last) => Self::resume(inner, &last),
+            None => Ok(Self::new(inner)),
+        }
+    }
+
+    /// Sign a checkpoint every `every` records (and on drop).
+    pub fn signed(mut self, key: SigningKey, every: u64) -> Self {
+        self.signer = Some((key, every.max(1)));
+        self
+    }
+
+    /// `seq` the next record gets.
+    pub fn next_seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Hash of the last line written.
+    pub fn head(&self) -> &str {
+        &self.head
+    }
+
+    fn append(&mut self, ev: &TelemetryEvent) -> io::Result<()> {
+        let mut ev = ev.clone();
+        ev.seq = Some(self.seq);
+        ev.prev = Some(self.head.clone());
+        let line = serde_json::to_vec(&ev)?;
+        self.inner.record(&ev)?;
+        self.seq += 1;
+        self.head = line_hash(&line);
+        Ok(())
+    }
+
+    /// Append a signed checkpoint now; a no-op without a key or with nothing new to cover.
+    pub fn checkpoint(&mut self) -> io::Result<()> {
+        let Some((key, _)) = &self.signer else { return Ok(()) };
+        if self.since_checkpoint == 0 {
+            return Ok(());
+        }
+        let signature = key.sign(&checkpoint_message(self.seq, &self.head));
+        let ev = TelemetryEvent {
+            channel: Some(CHECKPOINT_CHANNEL.to_string()),
+            checkpoint: Some(Checkpoint {
+                key: hex::encode(key.verifying_key().as_bytes()),
+                signature: hex::encode(signature.to_bytes()),
+            }),
+            ..TelemetryEvent::new()
+        };
+        self.append(&ev)?;
+        self.since_checkpoint = 0;
+        Ok(())
+    }
+}
+
+impl<S: TelemetrySink> TelemetrySink for ChainedSink<S> {
+    fn record(&mut self, ev: &TelemetryEvent) -> io::Result<()> {
+        self.append(ev)?;
+        self.since_checkpoint += 1;
+        match self.signer {
+            Some((_, every)) if self.since_checkpoint >= every => self.checkpoint(),
+            _ => Ok(()),
+        }
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+    fn stats(&self) -> SinkStats {
+        self.inner.stats()
+    }
+}
+
+impl<S: TelemetrySink> Drop for ChainedSink<S> {
+    fn drop(&mut self) {
+        let _ = self.checkpoint().and_then(|()| self.inner.flush());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JsonlSink;
+
+    #[test]
+    fn resumes_after_the_last_line() {
+        let path = std::env::temp_dir().join(format!("grieg-chain-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(last_line(&path).unwrap(), None);
+
+        let mut sink = ChainedSink::new(JsonlSink::create(&path).unwrap());
+        sink.record(&TelemetryEvent::new()).unwrap();
+        sink.record(&TelemetryEvent::new()).unwrap();
+        let head = sink.head().to_string();
+        drop(sink);
+
+        let last = last_line(&path).unwrap().unwrap();
+        assert_eq!(line_hash(last.as_bytes()), head);
+        let sink = ChainedSink::continuing(JsonlSink::append(&path).unwrap(), &path).unwrap();
+        assert_eq!((sink.next_seq(), sink.head()), (2, head.as_str()));
+
+        std::fs::write(&path, "{\"ts\":\"2025-01-01T00:00:00Z\"}\n").unwrap();
+        let plain = ChainedSink::continuing(JsonlSink::append(&path).unwrap(), &path);
+        assert!(matches!(plain, Err(ChainError::Unchained)));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
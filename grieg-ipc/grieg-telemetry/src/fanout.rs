@@ -0,0 +1,97 @@
+//! One event stream, several sinks.
+
+use std::io;
+
+use crate::{Counters, SinkStats, TelemetryEvent, TelemetrySink};
+
+/// Records every event in each branch, in order. A failing branch does not keep the others
+/// from getting the event; `record` returns the first failure after trying them all.
+///
+/// `stats()` counts events fanned out (`recorded`), branch failures (`errors`) and the sum of
+/// the branches' own `dropped`; [`branch_stats`](Self::branch_stats) has the detail.
+#[derive(Default)]
+pub struct FanOut {
+    sinks: Vec<Box<dyn TelemetrySink + Send>>,
+    counters: Counters,
+}
+
+impl FanOut {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, sink: impl TelemetrySink + Send + 'static) -> Self {
+        self.push(sink);
+        self
+    }
+
+    pub fn push(&mut self, sink: impl TelemetrySink + Send + 'static) {
+        self.sinks.push(Box::new(sink));
+    }
+
+    pub fn len(&self) -> usize {
+        self.sinks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sinks.is_empty()
+    }
+
+    pub fn branch_stats(&self) -> Vec<SinkStats> {
+        self.sinks.iter().map(|s| s.stats()).collect()
+    }
+
+    fn each(
+        &mut self,
+        mut f: impl FnMut(&mut Box<dyn TelemetrySink + Send>) -> io::Result<()>,
+    ) -> io::Result<()> {
+        let mut first = None;
+        for s in &mut self.sinks {
+            if let Err(e) = f(s) {
+                self.counters.error();
+                first.get_or_insert(e);
+            }
+        }
+        first.map_or(Ok(()), Err)
+    }
+}
+
+impl TelemetrySink for FanOut {
+    fn record(&mut self, ev: &TelemetryEvent) -> io::Result<()> {
+        self.counters.recorded();
+        self.each(|s| s.record(ev))
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.each(|s| s.flush())
+    }
+    fn stats(&self) -> SinkStats {
+        let own = self.counters.snapshot();
+        SinkStats { dropped: self.branch_stats().iter().map(|s| s.dropped).sum(), ..own }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RingSink;
+
+    struct Broken;
+
+    impl TelemetrySink for Broken {
+        fn record(&mut self, _: &TelemetryEvent) -> io::Result<()> {
+            Err(io::Error::other("disk full"))
+        }
+    }
+
+    #[test]
+    fn a_failing_branch_does_not_starve_the_others() {
+        let (a, b) = (RingSink::new(1), RingSink::new(4));
+        let mut fan = FanOut::new().with(a.clone()).with(Broken).with(b.clone());
+        for _ in 0..3 {
+            assert!(fan.record(&TelemetryEvent::new()).is_err());
+        }
+        assert_eq!((a.len(), b.len()), (1, 3));
+        assert_eq!(fan.stats(), SinkStats { recorded: 3, dropped: 2, errors: 3 });
+        assert_eq!(fan.branch_stats()[0].dropped, 2);
+    }
+}
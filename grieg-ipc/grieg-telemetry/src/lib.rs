@@ -1,8 +1,32 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::fs::File;
+use std::fs::{File, OpenOptions};
 use std::io::{BufWriter, Write};
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+mod chain;
+mod fanout;
+mod queue;
+mod ring;
+mod rotate;
+#[cfg(unix)]
+mod socket;
+mod verify;
+
+pub use chain::{
+    checkpoint_message, generate_key, line_hash, load_signing_key, parse_verifying_key, Checkpoint,
+    ChainError, ChainedSink, CHECKPOINT_CHANNEL, GENESIS,
+};
+pub use ed25519_dalek::{SigningKey, VerifyingKey};
+
+pub use fanout::FanOut;
+pub use queue::{Overflow, QueuedSink};
+pub use ring::RingSink;
+pub use rotate::{Rotation, RotatingSink};
+#[cfg(unix)]
+pub use socket::UnixSocketSink;
+pub use verify::{Finding, Problem, Verifier, VerifyOptions, VerifyReport};
 
 /// Minimal event schema for the "invariant pulse channel" (v0.1).
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,6 +42,12 @@ pub struct TelemetryEvent {
     /// Phase where the evaluation ended (ALIVE, JAM, MEM, VAC).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub phase: Option<String>,
+    /// Sheet the result lies on: F (factual) or C (counterfactual).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sheet: Option<String>,
+    /// Truth domain of the evaluation (`k3`, `l3`, `four`, `classical`); K3 when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logic: Option<String>,
 
     /// A conservative textual rendering of the value (engine-independent).
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -38,6 +68,114 @@ pub struct TelemetryEvent {
     pub amplitude: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub channel: Option<String>,
+
+    /// Invariant pulses of this evaluation, in step order (empty unless the producer derives
+    /// them; see [`TelemetryEvent::with_pulses`]).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pulses: Vec<PulseRecord>,
+
+    /// MEM facts the evaluation consulted, with their provenance.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub witnesses: Vec<WitnessRecord>,
+
+    /// Position in a hash-chained log, from 0 (set by [`ChainedSink`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u64>,
+    /// SHA-256 (hex) of the previous line of a hash-chained log; [`GENESIS`] for the first.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prev: Option<String>,
+    /// Present on the checkpoint records of a signed log.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checkpoint: Option<Checkpoint>,
+}
+
+/// One invariant pulse (docs/invariant-pulse-channel.md).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PulseRecord {
+    /// WINDING, SINK, BOUNDARY, TRANSPORT or WITNESS.
+    pub kind: String,
+    /// Post-order index of the evaluation step that fired it.
+    pub step: usize,
+    pub op: String,
+    /// Phase after that step.
+    pub phase: String,
+    /// SINK: implication chain number.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain: Option<usize>,
+    /// WINDING: full circuits so far.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub winding: Option<u32>,
+    /// WITNESS: the identifier.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ident: Option<String>,
+}
+
+/// Provenance of one MEM fact an evaluation consulted.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WitnessRecord {
+    pub ident: String,
+    pub value: bool,
+    pub source: String,
+    pub asserted_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    pub confidence: f64,
+    /// The fact had expired, so the identifier was VAC instead.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub expired: bool,
+}
+
+#[cfg(feature = "pulse")]
+impl WitnessRecord {
+    /// Every fact `r` consulted: its witnesses, then the expired ones.
+    pub fn all_of(r: &grieg_engine::eval::EvalResult) -> Vec<Self> {
+        let live = r.witnesses.iter().map(|f| (f, false));
+        live.chain(r.expired.iter().map(|f| (f, true)))
+            .map(|(f, expired)| Self::new(f, expired))
+            .collect()
+    }
+
+    fn new(f: &grieg_engine::mem::Fact, expired: bool) -> Self {
+        Self {
+            ident: f.ident.clone(),
+            value: f.value,
+            source: f.source.clone(),
+            asserted_at: f.asserted_at,
+            expires_at: f.expires_at,
+            confidence: f.confidence,
+            expired,
+        }
+    }
+}
+
+#[cfg(feature = "pulse")]
+impl From<&grieg_engine::pulse::Pulse> for PulseRecord {
+    fn from(p: &grieg_engine::pulse::Pulse) -> Self {
+        use grieg_engine::phase::Phase;
+        use grieg_engine::pulse::PulseKind;
+        let kind = match p.kind {
+            PulseKind::Winding => "WINDING",
+            PulseKind::Sink => "SINK",
+            PulseKind::Boundary => "BOUNDARY",
+            PulseKind::Transport => "TRANSPORT",
+            PulseKind::Witness => "WITNESS",
+        };
+        let phase = match p.phase {
+            Phase::ALIVE => "ALIVE",
+            Phase::JAM => "JAM",
+            Phase::MEM => "MEM",
+            Phase::VAC => "VAC",
+        };
+        Self {
+            kind: kind.to_string(),
+            step: p.step,
+            op: p.op.to_string(),
+            phase: phase.to_string(),
+            chain: p.chain,
+            winding: p.winding,
+            ident: p.ident.clone(),
+        }
+    }
 }
 
 impl TelemetryEvent {
@@ -47,20 +185,97 @@ impl TelemetryEvent {
             expr: None,
             ast: None,
             phase: None,
+            sheet: None,
+            logic: None,
             value_text: None,
             value_bool: None,
             sink: false,
             jam: false,
             amplitude: None,
             channel: Some("ipc.v0".to_string()),
+            pulses: Vec::new(),
+            witnesses: Vec::new(),
+            seq: None,
+            prev: None,
+            checkpoint: None,
+        }
+    }
+
+    /// Attach the evaluation's pulses; `sink` and `jam` are set when a SINK or BOUNDARY fired.
+    pub fn with_pulses(mut self, pulses: Vec<PulseRecord>) -> Self {
+        self.sink |= pulses.iter().any(|p| p.kind == "SINK");
+        self.jam |= pulses.iter().any(|p| p.kind == "BOUNDARY");
+        self.pulses = pulses;
+        self
+    }
+}
+
+impl Default for TelemetryEvent {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Delivery counters of a sink: events written, events given up on (queue or ring full, peer
+/// busy or absent under a dropping policy), and failed writes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SinkStats {
+    pub recorded: u64,
+    pub dropped: u64,
+    pub errors: u64,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    recorded: AtomicU64,
+    dropped: AtomicU64,
+    errors: AtomicU64,
+}
+
+impl Counters {
+    pub(crate) fn recorded(&self) {
+        self.recorded.fetch_add(1, Ordering::Relaxed);
+    }
+    pub(crate) fn dropped(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+    pub(crate) fn error(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+    pub(crate) fn snapshot(&self) -> SinkStats {
+        SinkStats {
+            recorded: self.recorded.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
         }
     }
 }
 
+/// One JSON object per line, newline included.
+pub(crate) fn to_line(ev: &TelemetryEvent) -> std::io::Result<Vec<u8>> {
+    let mut line = serde_json::to_vec(ev)?;
+    line.push(b'\n');
+    Ok(line)
+}
+
 /// A simple trait for sinks that can record telemetry events.
 pub trait TelemetrySink {
     fn record(&mut self, ev: &TelemetryEvent) -> std::io::Result<()>;
     fn flush(&mut self) -> std::io::Result<()> { Ok(()) }
+    /// Delivery counters; sinks that keep none report zeros.
+    fn stats(&self) -> SinkStats { SinkStats::default() }
+}
+
+impl<S: TelemetrySink + ?Sized> TelemetrySink for Box<S> {
+    fn record(&mut self, ev: &TelemetryEvent) -> std::io::Result<()> {
+        (**self).record(ev)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        (**self).flush()
+    }
+    fn stats(&self) -> SinkStats {
+        (**self).stats()
+    }
 }
 
 /// JSON Lines sink: one JSON object per line.
@@ -69,10 +284,17 @@ pub struct JsonlSink {
 }
 
 impl JsonlSink {
+    /// Truncate `path` and write from the start.
     pub fn create<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
         let f = File::create(path)?;
         Ok(Self { writer: BufWriter::new(f) })
     }
+
+    /// Append to `path`, creating it if needed. See [`RotatingSink`] to bound its size.
+    pub fn append<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let f = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { writer: BufWriter::new(f) })
+    }
 }
 
 impl TelemetrySink for JsonlSink {
@@ -86,3 +308,32 @@ impl TelemetrySink for JsonlSink {
         self.writer.flush()
     }
 }
+
+/// JSON Lines on standard output, for piping into a collector.
+#[derive(Debug, Default)]
+pub struct StdoutSink {
+    counters: Counters,
+}
+
+impl StdoutSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl TelemetrySink for StdoutSink {
+    fn record(&mut self, ev: &TelemetryEvent) -> std::io::Result<()> {
+        let res = to_line(ev).and_then(|line| std::io::stdout().lock().write_all(&line));
+        match &res {
+            Ok(()) => self.counters.recorded(),
+            Err(_) => self.counters.error(),
+        }
+        res
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        std::io::stdout().flush()
+    }
+    fn stats(&self) -> SinkStats {
+        self.counters.snapshot()
+    }
+}
@@ -0,0 +1,135 @@
+use std::env;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use grieg_telemetry::{
+    generate_key, load_signing_key, parse_verifying_key, Verifier, VerifyOptions, VerifyingKey,
+};
+
+fn print_usage() {
+    eprintln!(
+        r#"Usage:
+  grieg-telemetry verify [--key <hex|file>]... [--require-signed] [--partial] [--json] <file>...
+  grieg-telemetry keygen <secret-file>
+  grieg-telemetry pubkey <secret-file>
+
+verify   check a hash-chained log (grieg-ipcd --chain, grieg-ipc-cli --chain). Give rotated
+         files oldest first: ipc.jsonl.2 ipc.jsonl.1 ipc.jsonl. Exits 1 on any finding, 3
+         when the log is intact but UNAUTHENTICATED (no --key, or no checkpoint verified by
+         one). A log cut back to a checkpoint still verifies.
+  --key             trusted checkpoint key (hex, or a file holding it); repeatable. Without
+                    one, each checkpoint is checked against the key it names, which anyone
+                    who rewrites the log can also do.
+  --require-signed  records after the last checkpoint, or no checkpoint at all, are findings
+  --partial         the first file starts mid-chain (older rotated files were deleted)
+  --json            print the report as JSON
+keygen   write a new ed25519 secret key (hex seed, mode 0600) and print its public key
+pubkey   print the public key of a secret key file
+"#
+    );
+}
+
+fn fail(msg: impl std::fmt::Display) -> ! {
+    eprintln!("grieg-telemetry: {msg}");
+    std::process::exit(2);
+}
+
+fn trusted_key(arg: &str) -> VerifyingKey {
+    let path = Path::new(arg);
+    let text = if path.exists() {
+        std::fs::read_to_string(path).unwrap_or_else(|e| fail(format!("{arg}: {e}")))
+    } else {
+        arg.to_string()
+    };
+    parse_verifying_key(&text).unwrap_or_else(|e| fail(format!("--key {arg}: {e}")))
+}
+
+fn verify(mut args: impl Iterator<Item = String>) {
+    let mut opts = VerifyOptions::default();
+    let mut json = false;
+    let mut files: Vec<PathBuf> = Vec::new();
+    while let Some(a) = args.next() {
+        match a.as_str() {
+            "--key" => match args.next() {
+                Some(k) => opts.keys.push(trusted_key(&k)),
+                None => fail("--key requires a value"),
+            },
+            "--require-signed" => opts.require_signed = true,
+            "--partial" => opts.partial = true,
+            "--json" => json = true,
+            _ if a.starts_with("--") => fail(format!("unknown arg: {a}")),
+            _ => files.push(a.into()),
+        }
+    }
+    if files.is_empty() {
+        print_usage();
+        std::process::exit(2);
+    }
+
+    let mut verifier = Verifier::new(opts);
+    for f in &files {
+        if let Err(e) = verifier.check_file(f) {
+            fail(format!("{}: {e}", f.display()));
+        }
+    }
+    let report = verifier.finish();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    } else {
+        for f in &report.problems {
+            match f.line {
+                Some(line) => println!("{}:{line}: {}", f.source, f.problem),
+                None => println!("{}: {}", f.source, f.problem),
+            }
+        }
+        let range = match (report.first_seq, report.last_seq) {
+            (Some(a), Some(b)) => format!(", seq {a}..={b}"),
+            _ => String::new(),
+        };
+        let status = match (report.ok(), report.authenticated) {
+            (false, _) => "TAMPERED OR DAMAGED",
+            (true, false) => "UNAUTHENTICATED",
+            (true, true) => "OK",
+        };
+        println!(
+            "{} records{range}, {} signed checkpoints, {} records after the last: {status}",
+            report.records, report.checkpoints, report.unsigned_tail,
+        );
+    }
+    std::process::exit(match (report.ok(), report.authenticated) {
+        (false, _) => 1,
+        (true, false) => 3,
+        (true, true) => 0,
+    });
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    match (args.next().as_deref(), args) {
+        (Some("verify"), rest) => verify(rest),
+        (Some("keygen"), mut rest) => {
+            let path = rest.next().unwrap_or_else(|| fail("keygen requires a file"));
+            let key = generate_key().unwrap_or_else(|e| fail(e));
+            let mut f = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .mode(0o600)
+                .open(&path)
+                .unwrap_or_else(|e| fail(format!("{path}: {e}")));
+            writeln!(f, "{}", hex::encode(key.to_bytes())).unwrap_or_else(|e| fail(e));
+            println!("{}", hex::encode(key.verifying_key().as_bytes()));
+        }
+        (Some("pubkey"), mut rest) => {
+            let path = rest.next().unwrap_or_else(|| fail("pubkey requires a file"));
+            let key = load_signing_key(Path::new(&path)).unwrap_or_else(|e| fail(e));
+            println!("{}", hex::encode(key.verifying_key().as_bytes()));
+        }
+        (Some("-h" | "--help"), _) => print_usage(),
+        _ => {
+            print_usage();
+            std::process::exit(2);
+        }
+    }
+}
@@ -0,0 +1,257 @@
+//! Bounded queue in front of a slow sink, drained by a background thread.
+
+use std::collections::VecDeque;
+use std::io;
+use std::str::FromStr;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crate::{Counters, SinkStats, TelemetryEvent, TelemetrySink};
+
+/// What `record` does when the queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// Wait for room: back-pressure on the producer, nothing is lost.
+    Block,
+    /// Discard the incoming event.
+    DropNewest,
+    /// Discard the oldest queued event to make room.
+    DropOldest,
+}
+
+impl FromStr for Overflow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "block" => Ok(Overflow::Block),
+            "drop-newest" => Ok(Overflow::DropNewest),
+            "drop-oldest" => Ok(Overflow::DropOldest),
+            _ => Err(format!("unknown overflow policy {s:?} (block, drop-newest, drop-oldest)")),
+        }
+    }
+}
+
+struct State {
+    queue: VecDeque<TelemetryEvent>,
+    closed: bool,
+    flush_requested: u64,
+    flushed: u64,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    changed: Condvar,
+    capacity: usize,
+    overflow: Overflow,
+    counters: Counters,
+}
+
+/// Hands events to `inner` on a worker thread so that a slow disk or peer does not stall the
+/// producer. `recorded` and `errors` count what the inner sink did; `dropped` counts events the
+/// overflow policy discarded. The inner sink is flushed whenever the queue runs empty, and
+/// dropping the sink drains the queue and flushes.
+pub struct QueuedSink {
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl QueuedSink {
+    pub fn new<S>(inner: S, capacity: usize, overflow: Overflow) -> Self
+    where
+        S: TelemetrySink + Send + 'static,
+    {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                queue: VecDeque::new(),
+                closed: false,
+                flush_requested: 0,
+                flushed: 0,
+            }),
+            changed: Condvar::new(),
+            capacity: capacity.max(1),
+            overflow,
+            counters: Counters::default(),
+        });
+        let worker = {
+            let shared = shared.clone();
+            thread::spawn(move || drain(inner, &shared))
+        };
+        Self { shared, worker: Some(worker) }
+    }
+
+    /// Events waiting for the inner sink.
+    pub fn queued(&self) -> usize {
+        self.shared.state.lock().unwrap().queue.len()
+    }
+}
+
+fn drain<S: TelemetrySink>(mut inner: S, shared: &Shared) {
+    let mut dirty = false;
+    loop {
+        let mut st = shared.state.lock().unwrap();
+        if st.queue.is_empty() && dirty {
+            drop(st);
+            dirty = false;
+            if inner.flush().is_err() {
+                shared.counters.error();
+            }
+            continue;
+        }
+        while st.queue.is_empty() && !st.closed && st.flushed == st.flush_requested {
+            st = shared.changed.wait(st).unwrap();
+        }
+        if let Some(ev) = st.queue.pop_front() {
+            shared.changed.notify_all();
+            drop(st);
+            match inner.record(&ev) {
+                Ok(()) => shared.counters.recorded(),
+                Err(_) => shared.counters.error(),
+            }
+            dirty = true;
+            continue;
+        }
+        let (target, closed) = (st.flush_requested, st.closed);
+        drop(st);
+        if inner.flush().is_err() {
+            shared.counters.error();
+        }
+        let mut st = shared.state.lock().unwrap();
+        st.flushed = target;
+        shared.changed.notify_all();
+        if closed {
+            return;
+        }
+    }
+}
+
+impl TelemetrySink for QueuedSink {
+    fn record(&mut self, ev: &TelemetryEvent) -> io::Result<()> {
+        let shared = &self.shared;
+        let mut st = shared.state.lock().unwrap();
+        if st.queue.len() >= shared.capacity {
+            match shared.overflow {
+                Overflow::Block => {
+                    while st.queue.len() >= shared.capacity {
+                        st = shared.changed.wait(st).unwrap();
+                    }
+                }
+                Overflow::DropNewest => {
+                    shared.counters.dropped();
+                    return Ok(());
+                }
+                Overflow::DropOldest => {
+                    st.queue.pop_front();
+                    shared.counters.dropped();
+                }
+            }
+        }
+        st.queue.push_back(ev.clone());
+        shared.changed.notify_all();
+        Ok(())
+    }
+
+    /// Wait until everything queued so far has reached the inner sink and it has flushed.
+    fn flush(&mut self) -> io::Result<()> {
+        let shared = &self.shared;
+        let mut st = shared.state.lock().unwrap();
+        st.flush_requested += 1;
+        let target = st.flush_requested;
+        shared.changed.notify_all();
+        while st.flushed < target {
+            st = shared.changed.wait(st).unwrap();
+        }
+        Ok(())
+    }
+
+    fn stats(&self) -> SinkStats {
+        self.shared.counters.snapshot()
+    }
+}
+
+impl Drop for QueuedSink {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().closed = true;
+        self.shared.changed.notify_all();
+        if let Some(w) = self.worker.take() {
+            let _ = w.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+    use crate::RingSink;
+
+    /// Records only when the test lets it, one event per message.
+    struct Gate {
+        open: mpsc::Receiver<()>,
+        out: RingSink,
+    }
+
+    impl TelemetrySink for Gate {
+        fn record(&mut self, ev: &TelemetryEvent) -> io::Result<()> {
+            self.open.recv().map_err(|_| io::Error::other("gate closed"))?;
+            self.out.record(ev)
+        }
+    }
+
+    fn event(i: usize) -> TelemetryEvent {
+        TelemetryEvent { expr: Some(format!("e{i}")), ..TelemetryEvent::new() }
+    }
+
+    fn run(overflow: Overflow) -> (Vec<String>, SinkStats) {
+        let (open, gate) = mpsc::channel();
+        let ring = RingSink::new(16);
+        let mut q = QueuedSink::new(Gate { open: gate, out: ring.clone() }, 2, overflow);
+        // The worker takes e0 and blocks on the gate; e1, e2 fill the queue; e3, e4 overflow.
+        q.record(&event(0)).unwrap();
+        while q.queued() > 0 {
+            thread::yield_now();
+        }
+        for i in 1..5 {
+            q.record(&event(i)).unwrap();
+        }
+        for _ in 0..5 {
+            open.send(()).unwrap();
+        }
+        drop(open);
+        q.flush().unwrap();
+        let stats = q.stats();
+        drop(q);
+        (ring.last(16).into_iter().map(|e| e.expr.unwrap()).collect(), stats)
+    }
+
+    #[test]
+    fn drop_policies_count_what_they_discard() {
+        let (got, stats) = run(Overflow::DropNewest);
+        assert_eq!(got, ["e0", "e1", "e2"]);
+        assert_eq!((stats.recorded, stats.dropped), (3, 2));
+
+        let (got, stats) = run(Overflow::DropOldest);
+        assert_eq!(got, ["e0", "e3", "e4"]);
+        assert_eq!((stats.recorded, stats.dropped), (3, 2));
+    }
+
+    #[test]
+    fn block_loses_nothing() {
+        let (open, gate) = mpsc::channel();
+        let ring = RingSink::new(16);
+        let mut q = QueuedSink::new(Gate { open: gate, out: ring.clone() }, 1, Overflow::Block);
+        let feeder = thread::spawn(move || {
+            for _ in 0..6 {
+                open.send(()).unwrap();
+            }
+        });
+        for i in 0..6 {
+            q.record(&event(i)).unwrap();
+        }
+        q.flush().unwrap();
+        feeder.join().unwrap();
+        assert_eq!(ring.len(), 6);
+        assert_eq!(q.stats(), SinkStats { recorded: 6, dropped: 0, errors: 0 });
+    }
+}
@@ -0,0 +1,94 @@
+//! In-memory ring of the most recent events.
+
+use std::collections::VecDeque;
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use crate::{SinkStats, TelemetryEvent, TelemetrySink};
+
+struct Ring {
+    events: VecDeque<TelemetryEvent>,
+    capacity: usize,
+    recorded: u64,
+    overwritten: u64,
+}
+
+/// Keeps the last `capacity` events; older ones are overwritten (counted as dropped).
+///
+/// Clones share the buffer, so one clone can sit inside a [`FanOut`](crate::FanOut) while
+/// another answers queries.
+#[derive(Clone)]
+pub struct RingSink {
+    inner: Arc<Mutex<Ring>>,
+}
+
+impl RingSink {
+    /// A ring holding up to `capacity` events (at least one).
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        let ring = Ring {
+            events: VecDeque::with_capacity(capacity),
+            capacity,
+            recorded: 0,
+            overwritten: 0,
+        };
+        Self { inner: Arc::new(Mutex::new(ring)) }
+    }
+
+    /// The last `n` events, oldest first.
+    pub fn last(&self, n: usize) -> Vec<TelemetryEvent> {
+        let ring = self.inner.lock().unwrap();
+        let skip = ring.events.len().saturating_sub(n);
+        ring.events.iter().skip(skip).cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.inner.lock().unwrap().capacity
+    }
+}
+
+impl TelemetrySink for RingSink {
+    fn record(&mut self, ev: &TelemetryEvent) -> io::Result<()> {
+        let mut ring = self.inner.lock().unwrap();
+        if ring.events.len() == ring.capacity {
+            ring.events.pop_front();
+            ring.overwritten += 1;
+        }
+        ring.events.push_back(ev.clone());
+        ring.recorded += 1;
+        Ok(())
+    }
+    fn stats(&self) -> SinkStats {
+        let ring = self.inner.lock().unwrap();
+        SinkStats { recorded: ring.recorded, dropped: ring.overwritten, errors: 0 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_last_events() {
+        let ring = RingSink::new(3);
+        let mut sink = ring.clone();
+        for i in 0..5 {
+            let ev = TelemetryEvent { expr: Some(format!("e{i}")), ..TelemetryEvent::new() };
+            sink.record(&ev).unwrap();
+        }
+        let exprs = |evs: Vec<TelemetryEvent>| -> Vec<String> {
+            evs.into_iter().map(|e| e.expr.unwrap()).collect()
+        };
+        assert_eq!(exprs(ring.last(2)), ["e3", "e4"]);
+        assert_eq!(exprs(ring.last(10)), ["e2", "e3", "e4"]);
+        assert_eq!(ring.stats(), SinkStats { recorded: 5, dropped: 2, errors: 0 });
+    }
+}
@@ -0,0 +1,173 @@
+//! Appending JSONL file that rotates by size and/or age and keeps a bounded history.
+
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::{to_line, Counters, SinkStats, TelemetryEvent, TelemetrySink};
+
+/// When to start a new file and how many old ones to keep.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rotation {
+    /// Rotate before a record would take the file past this many bytes.
+    pub max_bytes: Option<u64>,
+    /// Rotate once the current file has been written to for this long (measured from when
+    /// this sink opened or last rotated it).
+    pub max_age: Option<Duration>,
+    /// Rotated files kept as `<path>.1` (newest) … `<path>.<keep>`; older ones are deleted.
+    pub keep: usize,
+}
+
+impl Default for Rotation {
+    fn default() -> Self {
+        Self { max_bytes: None, max_age: None, keep: 5 }
+    }
+}
+
+/// Appends to `path`, rotating `path` → `path.1` → `path.2` … per [`Rotation`].
+///
+/// A single record larger than `max_bytes` still goes into a file of its own: records are
+/// never split.
+pub struct RotatingSink {
+    path: PathBuf,
+    rotation: Rotation,
+    writer: BufWriter<File>,
+    size: u64,
+    opened: Instant,
+    counters: Counters,
+}
+
+fn open_append(path: &Path) -> io::Result<(BufWriter<File>, u64)> {
+    let f = OpenOptions::new().create(true).append(true).open(path)?;
+    let size = f.metadata()?.len();
+    Ok((BufWriter::new(f), size))
+}
+
+impl RotatingSink {
+    pub fn open(path: impl Into<PathBuf>, rotation: Rotation) -> io::Result<Self> {
+        let path = path.into();
+        let (writer, size) = open_append(&path)?;
+        let opened = Instant::now();
+        Ok(Self { path, rotation, writer, size, opened, counters: Counters::default() })
+    }
+
+    /// `<path>.<n>`; `n == 0` is the live file.
+    pub fn rotated_path(&self, n: usize) -> PathBuf {
+        if n == 0 {
+            return self.path.clone();
+        }
+        let mut name: OsString = self.path.as_os_str().to_owned();
+        name.push(format!(".{n}"));
+        PathBuf::from(name)
+    }
+
+    /// Close the live file, shift the history by one and start an empty file.
+    pub fn rotate(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+        let keep = self.rotation.keep;
+        if keep == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            let oldest = self.rotated_path(keep);
+            if oldest.exists() {
+                fs::remove_file(&oldest)?;
+            }
+            for n in (1..keep).rev() {
+                let from = self.rotated_path(n);
+                if from.exists() {
+                    fs::rename(&from, self.rotated_path(n + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        let (writer, size) = open_append(&self.path)?;
+        self.writer = writer;
+        self.size = size;
+        self.opened = Instant::now();
+        Ok(())
+    }
+
+    fn due(&self, next: u64) -> bool {
+        let r = &self.rotation;
+        self.size > 0
+            && (r.max_bytes.is_some_and(|max| self.size + next > max)
+                || r.max_age.is_some_and(|age| self.opened.elapsed() >= age))
+    }
+
+    fn write(&mut self, ev: &TelemetryEvent) -> io::Result<()> {
+        let line = to_line(ev)?;
+        if self.due(line.len() as u64) {
+            self.rotate()?;
+        }
+        self.writer.write_all(&line)?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+}
+
+impl TelemetrySink for RotatingSink {
+    fn record(&mut self, ev: &TelemetryEvent) -> io::Result<()> {
+        let res = self.write(ev);
+        match &res {
+            Ok(()) => self.counters.recorded(),
+            Err(_) => self.counters.error(),
+        }
+        res
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+    fn stats(&self) -> SinkStats {
+        self.counters.snapshot()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Same-size records: fixed timestamp, same-length expressions.
+    fn event(expr: &str) -> TelemetryEvent {
+        let ts = chrono::DateTime::from_timestamp(0, 0).unwrap();
+        TelemetryEvent { ts, expr: Some(expr.to_string()), ..TelemetryEvent::new() }
+    }
+
+    #[test]
+    fn rotates_by_size_and_keeps_the_newest_files() {
+        let dir = std::env::temp_dir().join(format!("grieg-rotate-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("ipc.jsonl");
+
+        let line = to_line(&event("e0")).unwrap().len() as u64;
+        let rotation = Rotation { max_bytes: Some(2 * line), max_age: None, keep: 2 };
+        let mut sink = RotatingSink::open(&path, rotation).unwrap();
+        for i in 0..7 {
+            sink.record(&event(&format!("e{i}"))).unwrap();
+        }
+        sink.flush().unwrap();
+
+        let exprs = |p: PathBuf| -> Vec<String> {
+            let text = fs::read_to_string(p).unwrap();
+            text.lines()
+                .map(|l| serde_json::from_str::<TelemetryEvent>(l).unwrap().expr.unwrap())
+                .collect()
+        };
+        // e0 e1 | e2 e3 | e4 e5 | e6: the oldest file fell off the end.
+        assert_eq!(exprs(sink.rotated_path(0)), ["e6"]);
+        assert_eq!(exprs(sink.rotated_path(1)), ["e4", "e5"]);
+        assert_eq!(exprs(sink.rotated_path(2)), ["e2", "e3"]);
+        assert!(!sink.rotated_path(3).exists());
+        assert_eq!(sink.stats().recorded, 7);
+
+        // Reopening appends instead of truncating.
+        drop(sink);
+        let mut sink = RotatingSink::open(&path, Rotation::default()).unwrap();
+        sink.record(&event("e7")).unwrap();
+        sink.flush().unwrap();
+        assert_eq!(exprs(path.clone()), ["e6", "e7"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,182 @@
+//! Events to a local collector over a Unix domain socket.
+
+use std::io::{self, ErrorKind, Write};
+use std::os::unix::net::{UnixDatagram, UnixStream};
+use std::path::PathBuf;
+
+use crate::{to_line, Counters, SinkStats, TelemetryEvent, TelemetrySink};
+
+enum Conn {
+    Datagram(UnixDatagram),
+    Stream(UnixStream),
+}
+
+/// Sends each event as one JSON line, either as a datagram or over a stream connection.
+///
+/// The socket is connected on first use and again after a failure, so the collector may start
+/// after the producer. By default a missing or busy collector is an error (and a full socket
+/// buffer blocks); with [`drop_when_unavailable`](Self::drop_when_unavailable) such events are
+/// counted as dropped instead and `record` succeeds.
+pub struct UnixSocketSink {
+    path: PathBuf,
+    datagram: bool,
+    drop_when_unavailable: bool,
+    conn: Option<Conn>,
+    counters: Counters,
+}
+
+impl UnixSocketSink {
+    /// One datagram per event (`SOCK_DGRAM`); a record must fit the socket's size limit.
+    pub fn datagram(path: impl Into<PathBuf>) -> Self {
+        Self::new(path.into(), true)
+    }
+
+    /// Newline-delimited JSON over a stream connection (`SOCK_STREAM`).
+    pub fn stream(path: impl Into<PathBuf>) -> Self {
+        Self::new(path.into(), false)
+    }
+
+    fn new(path: PathBuf, datagram: bool) -> Self {
+        Self {
+            path,
+            datagram,
+            drop_when_unavailable: false,
+            conn: None,
+            counters: Counters::default(),
+        }
+    }
+
+    /// Never wait for or fail on the collector: drop (and count) what it cannot take now.
+    pub fn drop_when_unavailable(mut self, yes: bool) -> Self {
+        self.drop_when_unavailable = yes;
+        self.conn = None;
+        self
+    }
+
+    fn connect(&self) -> io::Result<Conn> {
+        let conn = if self.datagram {
+            let s = UnixDatagram::unbound()?;
+            s.connect(&self.path)?;
+            s.set_nonblocking(self.drop_when_unavailable)?;
+            Conn::Datagram(s)
+        } else {
+            let s = UnixStream::connect(&self.path)?;
+            s.set_nonblocking(self.drop_when_unavailable)?;
+            Conn::Stream(s)
+        };
+        Ok(conn)
+    }
+
+    /// `Ok(true)` when sent, `Ok(false)` when the collector could not take it without waiting.
+    fn send(&mut self, line: &[u8]) -> io::Result<bool> {
+        if self.conn.is_none() {
+            self.conn = Some(self.connect()?);
+        }
+        match self.conn.as_mut().unwrap() {
+            Conn::Datagram(s) => match s.send(line) {
+                Ok(_) => Ok(true),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(false),
+                Err(e) => Err(e),
+            },
+            Conn::Stream(s) => {
+                let mut written = 0;
+                while written < line.len() {
+                    match s.write(&line[written..]) {
+                        Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                        Ok(n) => written += n,
+                        Err(e) if e.kind() == ErrorKind::WouldBlock && written == 0 => {
+                            return Ok(false)
+                        }
+                        // Half a line went out; only a new connection restores the framing.
+                        Err(e) => return Err(e),
+                    }
+                }
+                Ok(true)
+            }
+        }
+    }
+}
+
+impl TelemetrySink for UnixSocketSink {
+    fn record(&mut self, ev: &TelemetryEvent) -> io::Result<()> {
+        let line = to_line(ev)?;
+        let line = if self.datagram { &line[..line.len() - 1] } else { &line[..] };
+        match self.send(line) {
+            Ok(true) => {
+                self.counters.recorded();
+                Ok(())
+            }
+            Ok(false) => {
+                self.counters.dropped();
+                Ok(())
+            }
+            Err(e) => {
+                self.conn = None;
+                if self.drop_when_unavailable {
+                    self.counters.dropped();
+                    Ok(())
+                } else {
+                    self.counters.error();
+                    Err(e)
+                }
+            }
+        }
+    }
+    fn stats(&self) -> SinkStats {
+        self.counters.snapshot()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader};
+    use std::os::unix::net::UnixListener;
+
+    use super::*;
+
+    fn socket_path(name: &str) -> PathBuf {
+        let p = std::env::temp_dir().join(format!("grieg-tel-{name}-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&p);
+        p
+    }
+
+    fn event(expr: &str) -> TelemetryEvent {
+        TelemetryEvent { expr: Some(expr.to_string()), ..TelemetryEvent::new() }
+    }
+
+    #[test]
+    fn datagrams_reach_the_collector_and_absence_is_dropped_on_request() {
+        let path = socket_path("dgram");
+        let mut strict = UnixSocketSink::datagram(&path);
+        assert!(strict.record(&event("early")).is_err());
+        let mut lenient = UnixSocketSink::datagram(&path).drop_when_unavailable(true);
+        lenient.record(&event("early")).unwrap();
+        assert_eq!(lenient.stats(), SinkStats { recorded: 0, dropped: 1, errors: 0 });
+
+        let collector = UnixDatagram::bind(&path).unwrap();
+        strict.record(&event("a")).unwrap();
+        let mut buf = [0u8; 4096];
+        let n = collector.recv(&mut buf).unwrap();
+        let got: TelemetryEvent = serde_json::from_slice(&buf[..n]).unwrap();
+        assert_eq!(got.expr.as_deref(), Some("a"));
+        assert_eq!(strict.stats(), SinkStats { recorded: 1, dropped: 0, errors: 1 });
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn stream_sends_json_lines() {
+        let path = socket_path("stream");
+        let listener = UnixListener::bind(&path).unwrap();
+        let mut sink = UnixSocketSink::stream(&path);
+        sink.record(&event("a")).unwrap();
+        sink.record(&event("b")).unwrap();
+        let (conn, _) = listener.accept().unwrap();
+        let lines: Vec<String> = BufReader::new(conn).lines().take(2).map(Result::unwrap).collect();
+        let exprs: Vec<String> = lines
+            .iter()
+            .map(|l| serde_json::from_str::<TelemetryEvent>(l).unwrap().expr.unwrap())
+            .collect();
+        assert_eq!(exprs, ["a", "b"]);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,323 @@
+//! Checking a hash-chained log written by [`ChainedSink`](crate::ChainedSink).
+//!
+//! A log is only authenticated by checkpoints signed with a trusted key; without one, anyone
+//! can re-chain (and re-sign) an edited log. Even then, cutting a log back to one of its
+//! checkpoints leaves a valid prefix and cannot be detected here: compare `last_seq` or `head`
+//! with a copy kept elsewhere.
+
+use std::collections::BTreeSet;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::chain::{checkpoint_message, line_hash, parse_verifying_key};
+use crate::{TelemetryEvent, GENESIS};
+
+#[derive(Debug, Clone, Default)]
+pub struct VerifyOptions {
+    /// Checkpoints must be signed by one of these. Empty accepts the key each checkpoint names,
+    /// which only shows the log is consistent with itself (see [`VerifyReport::authenticated`]).
+    pub keys: Vec<VerifyingKey>,
+    /// The log may start mid-chain (older rotated files deleted): trust the first record's
+    /// `seq` and `prev` instead of requiring 0 and [`GENESIS`].
+    pub partial: bool,
+    /// Records after the last valid checkpoint are a problem, as is a log without checkpoints.
+    pub require_signed: bool,
+}
+
+/// Something wrong with one line (or, for `UnsignedTail`, with the end of the log).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Error)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Problem {
+    #[error("not a telemetry record: {message}")]
+    Malformed { message: String },
+    #[error("record is not chained (no seq/prev)")]
+    Unchained,
+    #[error("expected seq {expected}, found {found}: records were deleted, reordered or inserted")]
+    Sequence { expected: u64, found: u64 },
+    #[error("prev does not match the line before: that line was modified, or one was removed")]
+    Link,
+    #[error("checkpoint is malformed: {message}")]
+    BadCheckpoint { message: String },
+    #[error("checkpoint signature does not verify")]
+    Signature,
+    #[error("checkpoint signed by untrusted key {key}")]
+    UntrustedKey { key: String },
+    #[error("{records} records after the last signed checkpoint")]
+    UnsignedTail { records: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    pub source: String,
+    /// 1-based; `None` for problems with the log as a whole.
+    pub line: Option<usize>,
+    #[serde(flatten)]
+    pub problem: Problem,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VerifyReport {
+    /// Records read, checkpoints included.
+    pub records: u64,
+    /// Checkpoints whose signature verified.
+    pub checkpoints: u64,
+    pub first_seq: Option<u64>,
+    pub last_seq: Option<u64>,
+    /// Hash of the last line.
+    pub head: Option<String>,
+    /// Records after the last valid checkpoint (all of them when there is none).
+    pub unsigned_tail: u64,
+    /// Keys of the valid checkpoints, hex.
+    pub keys: Vec<String>,
+    /// A checkpoint verified against a trusted key ([`VerifyOptions::keys`]).
+    pub authenticated: bool,
+    pub problems: Vec<Finding>,
+}
+
+impl VerifyReport {
+    pub fn ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Feeds on the lines of a log, in order, possibly spread over several files (oldest first).
+pub struct Verifier {
+    opts: VerifyOptions,
+    report: VerifyReport,
+    expected: Option<(u64, String)>,
+    keys: BTreeSet<String>,
+    last_source: String,
+}
+
+impl Verifier {
+    pub fn new(opts: VerifyOptions) -> Self {
+        Self {
+            opts,
+            report: VerifyReport::default(),
+            expected: None,
+            keys: BTreeSet::new(),
+            last_source: String::new(),
+        }
+    }
+
+    /// Check every line of `path`.
+    pub fn check_file(&mut self, path: &Path) -> io::Result<()> {
+        let source = path.display().to_string();
+        let reader = BufReader::new(std::fs::File::open(path)?);
+        for (i, line) in reader.lines().enumerate() {
+            self.check_line(&source, i + 1, &line?);
+        }
+        Ok(())
+    }
+
+    /// Check the next line of the log; `source` and `line` only label findings.
+    pub fn check_line(&mut self, source: &str, line: usize, text: &str) {
+        if text.trim().is_empty() {
+            return;
+        }
+        if self.last_source != source {
+            self.last_source = source.to_string();
+        }
+        for problem in self.problems_of(text) {
+            let source = source.to_string();
+            self.report.problems.push(Finding { source, line: Some(line), problem });
+        }
+    }
+
+    fn problems_of(&mut self, text: &str) -> Vec<Problem> {
+        let hash = line_hash(text.as_bytes());
+        let mut problems = Vec::new();
+        self.report.records += 1;
+        self.report.head = Some(hash.clone());
+
+        let ev = match serde_json::from_str::<TelemetryEvent>(text) {
+            Ok(ev) => ev,
+            Err(e) => {
+                problems.push(Problem::Malformed { message: e.to_string() });
+                self.resync(None, hash);
+                return problems;
+            }
+        };
+        let (Some(seq), Some(prev)) = (ev.seq, ev.prev.as_deref()) else {
+            problems.push(Problem::Unchained);
+            self.resync(None, hash);
+            return problems;
+        };
+        self.report.first_seq.get_or_insert(seq);
+        self.report.last_seq = Some(seq);
+
+        let expected = match &self.expected {
+            Some(e) => Some(e.clone()),
+            None if self.opts.partial => None,
+            None => Some((0, GENESIS.to_string())),
+        };
+        if let Some((want_seq, want_prev)) = expected {
+            if seq != want_seq {
+                problems.push(Problem::Sequence { expected: want_seq, found: seq });
+            } else if prev != want_prev {
+                problems.push(Problem::Link);
+            }
+        }
+
+        match &ev.checkpoint {
+            Some(cp) => match self.check_signature(seq, prev, &cp.key, &cp.signature) {
+                Ok(()) => {
+                    self.report.checkpoints += 1;
+                    self.report.unsigned_tail = 0;
+                    self.keys.insert(cp.key.to_lowercase());
+                }
+                Err(p) => {
+                    problems.push(p);
+                    self.report.unsigned_tail += 1;
+                }
+            },
+            None => self.report.unsigned_tail += 1,
+        }
+        self.resync(Some(seq), hash);
+        problems
+    }
+
+    fn check_signature(&self, seq: u64, prev: &str, key: &str, sig: &str) -> Result<(), Problem> {
+        let bad = |message: String| Problem::BadCheckpoint { message };
+        let vk = parse_verifying_key(key).map_err(|e| bad(e.to_string()))?;
+        if !self.opts.keys.is_empty() && !self.opts.keys.contains(&vk) {
+            return Err(Problem::UntrustedKey { key: key.to_string() });
+        }
+        let mut bytes = [0u8; 64];
+        hex::decode_to_slice(sig, &mut bytes).map_err(|e| bad(format!("signature: {e}")))?;
+        let signature = Signature::from_bytes(&bytes);
+        vk.verify_strict(&checkpoint_message(seq, prev), &signature).map_err(|_| Problem::Signature)
+    }
+
+    /// Carry on from this line whatever was wrong with it, so one edit is reported once.
+    fn resync(&mut self, seq: Option<u64>, hash: String) {
+        let next = match (seq, &self.expected) {
+            (Some(s), _) => Some(s + 1),
+            (None, Some((s, _))) => Some(s + 1),
+            (None, None) => None,
+        };
+        self.expected = next.map(|s| (s, hash));
+    }
+
+    pub fn finish(mut self) -> VerifyReport {
+        let r = &mut self.report;
+        if self.opts.require_signed && r.records > 0 && (r.unsigned_tail > 0 || r.checkpoints == 0)
+        {
+            let problem = Problem::UnsignedTail { records: r.unsigned_tail };
+            r.problems.push(Finding { source: self.last_source, line: None, problem });
+        }
+        r.keys = self.keys.into_iter().collect();
+        r.authenticated = !self.opts.keys.is_empty() && r.checkpoints > 0;
+        self.report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChainedSink, SigningKey, TelemetrySink};
+
+    /// Lines of a signed log with a checkpoint every 3 records: e0 e1 e2 CP e3 e4 e5 CP.
+    fn signed_log(key: &SigningKey) -> Vec<String> {
+        let ring = crate::RingSink::new(64);
+        let mut sink = ChainedSink::new(ring.clone()).signed(key.clone(), 3);
+        for i in 0..6 {
+            let ev = TelemetryEvent { expr: Some(format!("e{i}")), ..TelemetryEvent::new() };
+            sink.record(&ev).unwrap();
+        }
+        drop(sink);
+        ring.last(64).iter().map(|ev| serde_json::to_string(ev).unwrap()).collect()
+    }
+
+    fn verify(lines: &[String], opts: VerifyOptions) -> VerifyReport {
+        let mut v = Verifier::new(opts);
+        for (i, l) in lines.iter().enumerate() {
+            v.check_line("log", i + 1, l);
+        }
+        v.finish()
+    }
+
+    fn kinds(r: &VerifyReport) -> Vec<(Option<usize>, Problem)> {
+        r.problems.iter().map(|f| (f.line, f.problem.clone())).collect()
+    }
+
+    #[test]
+    fn intact_log_verifies_against_its_key() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let lines = signed_log(&key);
+        assert_eq!(lines.len(), 8);
+        let opts = VerifyOptions {
+            keys: vec![key.verifying_key()],
+            require_signed: true,
+            ..VerifyOptions::default()
+        };
+        let r = verify(&lines, opts);
+        assert!(r.ok(), "{:?}", r.problems);
+        assert!(r.authenticated);
+        assert_eq!((r.records, r.checkpoints, r.unsigned_tail), (8, 2, 0));
+
+        let r = verify(&lines, VerifyOptions::default());
+        assert!(r.ok() && !r.authenticated, "a checkpoint's own key proves nothing");
+
+        let other = SigningKey::from_bytes(&[8; 32]).verifying_key();
+        let r = verify(&lines, VerifyOptions { keys: vec![other], ..VerifyOptions::default() });
+        assert!(matches!(r.problems[0].problem, Problem::UntrustedKey { .. }));
+    }
+
+    #[test]
+    fn deletion_reordering_and_edits_are_caught() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let lines = signed_log(&key);
+
+        let mut deleted = lines.clone();
+        deleted.remove(1);
+        let r = verify(&deleted, VerifyOptions::default());
+        assert_eq!(kinds(&r), [(Some(2), Problem::Sequence { expected: 1, found: 2 })]);
+
+        let mut swapped = lines.clone();
+        swapped.swap(4, 5);
+        let r = verify(&swapped, VerifyOptions::default());
+        assert_eq!(r.problems.len(), 3);
+        assert!(matches!(r.problems[0].problem, Problem::Sequence { expected: 4, found: 5 }));
+
+        let mut edited = lines.clone();
+        edited[1] = edited[1].replace("\"e1\"", "\"e9\"");
+        let r = verify(&edited, VerifyOptions::default());
+        assert_eq!(kinds(&r), [(Some(3), Problem::Link)]);
+
+        // Re-linking every later line hides the edit from the chain but not from the checkpoint.
+        for i in 2..edited.len() {
+            let mut ev: TelemetryEvent = serde_json::from_str(&edited[i]).unwrap();
+            ev.prev = Some(line_hash(edited[i - 1].as_bytes()));
+            edited[i] = serde_json::to_string(&ev).unwrap();
+        }
+        let r = verify(&edited, VerifyOptions::default());
+        assert_eq!(kinds(&r), [(Some(4), Problem::Signature), (Some(8), Problem::Signature)]);
+
+        // Without checkpoints, a re-linked log is consistent but not authenticated.
+        let unsigned: Vec<_> = edited.iter().filter(|l| !l.contains("grieg.checkpoint")).collect();
+        let mut relinked: Vec<String> = Vec::new();
+        for (i, l) in unsigned.iter().enumerate() {
+            let mut ev: TelemetryEvent = serde_json::from_str(l).unwrap();
+            ev.seq = Some(i as u64);
+            ev.prev =
+                Some(relinked.last().map_or(GENESIS.to_string(), |p| line_hash(p.as_bytes())));
+            relinked.push(serde_json::to_string(&ev).unwrap());
+        }
+        let r = verify(&relinked, VerifyOptions::default());
+        assert!(r.ok() && !r.authenticated);
+
+        // Cutting the log after the last checkpoint is only visible as an unsigned tail; cutting
+        // it at a checkpoint is not visible at all.
+        let r = verify(&lines[..6], VerifyOptions { require_signed: true, ..Default::default() });
+        assert_eq!(kinds(&r), [(None, Problem::UnsignedTail { records: 2 })]);
+        let r = verify(&lines[..4], VerifyOptions { require_signed: true, ..Default::default() });
+        assert!(r.ok());
+        let r = verify(&lines[2..], VerifyOptions { partial: true, ..Default::default() });
+        assert!(r.ok());
+    }
+}
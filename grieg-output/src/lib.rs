@@ -0,0 +1,313 @@
+//! Shared result output for `grieg-cli` and `grieg-ipc-cli`.
+//!
+//! Every binary builds the same [`Row`] and picks its [`Column`]s from the invocation (never
+//! from the data), so NDJSON keys, CSV headers, table columns and YAML keys are identical for
+//! the same flags, whichever binary or format produced them.
+
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use grieg_engine::value::V;
+use serde_json::{Map, Value};
+
+/// One evaluation result.
+#[derive(Debug, Clone, Default)]
+pub struct Row {
+    pub id: Option<String>,
+    /// 1-based input line (batch/JSONL modes).
+    pub line: Option<usize>,
+    pub input: Option<String>,
+    /// S-expression AST (`grieg_engine::ast::to_sexpr`).
+    pub ast: Option<String>,
+    /// `true`, `false`, `null` or `"both"` (see [`value`]).
+    pub value: Value,
+    pub phase: Option<String>,
+    /// `F` (factual) or `C` (counterfactual).
+    pub sheet: Option<String>,
+    pub ok: Option<bool>,
+    pub expect_phase: Option<String>,
+    pub note: Option<String>,
+    pub rule_id: Option<String>,
+    pub tags: Vec<String>,
+    pub error: Option<String>,
+}
+
+impl Row {
+    /// The row as a JSON object with exactly `columns`, in order (absent values are `null`).
+    pub fn to_object(&self, columns: &[Column]) -> Map<String, Value> {
+        columns.iter().map(|c| (c.name().to_string(), c.get(self))).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Id,
+    Line,
+    Input,
+    Ast,
+    Value,
+    Phase,
+    Sheet,
+    Ok,
+    ExpectPhase,
+    Note,
+    RuleId,
+    Tags,
+    Error,
+}
+
+impl Column {
+    pub fn name(self) -> &'static str {
+        match self {
+            Column::Id => "id",
+            Column::Line => "line",
+            Column::Input => "input",
+            Column::Ast => "ast",
+            Column::Value => "value",
+            Column::Phase => "phase",
+            Column::Sheet => "sheet",
+            Column::Ok => "ok",
+            Column::ExpectPhase => "expect_phase",
+            Column::Note => "note",
+            Column::RuleId => "rule_id",
+            Column::Tags => "tags",
+            Column::Error => "error",
+        }
+    }
+
+    fn get(self, r: &Row) -> Value {
+        let s = |o: &Option<String>| o.clone().map_or(Value::Null, Value::String);
+        match self {
+            Column::Id => s(&r.id),
+            Column::Line => r.line.map_or(Value::Null, Value::from),
+            Column::Input => s(&r.input),
+            Column::Ast => s(&r.ast),
+            Column::Value => r.value.clone(),
+            Column::Phase => s(&r.phase),
+            Column::Sheet => s(&r.sheet),
+            Column::Ok => r.ok.map_or(Value::Null, Value::Bool),
+            Column::ExpectPhase => s(&r.expect_phase),
+            Column::Note => s(&r.note),
+            Column::RuleId => s(&r.rule_id),
+            Column::Tags => r.tags.iter().cloned().map(Value::String).collect(),
+            Column::Error => s(&r.error),
+        }
+    }
+}
+
+/// The `value` column for an engine value: a boolean, `null` for a gap, or `"both"` for
+/// Belnap's Both, so FOUR results stay distinguishable from gaps.
+pub fn value(v: &V) -> Value {
+    match v {
+        V::Bool(b) => Value::Bool(*b),
+        V::Unknown => Value::Null,
+        V::Both => Value::from("both"),
+    }
+}
+
+/// Column set for an invocation: single expressions, or case-driven (JSONL/batch) runs.
+pub fn columns(with_ast: bool, cases: bool) -> Vec<Column> {
+    let mut c = Vec::new();
+    if cases {
+        c.extend([Column::Line, Column::Id]);
+    }
+    c.push(Column::Input);
+    if with_ast {
+        c.push(Column::Ast);
+    }
+    c.extend([Column::Value, Column::Phase, Column::Sheet]);
+    if cases {
+        c.extend([Column::Ok, Column::ExpectPhase, Column::Note, Column::RuleId, Column::Tags]);
+    }
+    c.push(Column::Error);
+    c
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// One compact JSON object per line.
+    Ndjson,
+    /// Indented JSON objects.
+    Pretty,
+    /// Aligned terminal table (buffered until `finish`).
+    Table,
+    /// RFC 4180 CSV with a header row; `tags` joined with `;`.
+    Csv,
+    /// A YAML sequence, one item per row.
+    Yaml,
+}
+
+impl FromStr for Format {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "ndjson" | "json" | "jsonl" => Ok(Format::Ndjson),
+            "pretty" => Ok(Format::Pretty),
+            "table" => Ok(Format::Table),
+            "csv" => Ok(Format::Csv),
+            "yaml" | "yml" => Ok(Format::Yaml),
+            other => Err(format!("unknown format '{other}' (ndjson|pretty|table|csv|yaml)")),
+        }
+    }
+}
+
+/// Writes rows in one format with a fixed column set.
+pub struct RowWriter<W: Write> {
+    out: W,
+    format: Format,
+    columns: Vec<Column>,
+    header_done: bool,
+    pending: Vec<Vec<String>>,
+}
+
+impl<W: Write> RowWriter<W> {
+    pub fn new(out: W, format: Format, columns: Vec<Column>) -> Self {
+        Self { out, format, columns, header_done: false, pending: Vec::new() }
+    }
+
+    /// Don't emit a CSV header (appending to a file that already has one).
+    pub fn skip_header(&mut self) {
+        self.header_done = true;
+    }
+
+    pub fn write(&mut self, row: &Row) -> io::Result<()> {
+        match self.format {
+            Format::Ndjson => {
+                let obj = self.object(row);
+                serde_json::to_writer(&mut self.out, &obj)?;
+                self.out.write_all(b"\n")
+            }
+            Format::Pretty => {
+                let obj = self.object(row);
+                serde_json::to_writer_pretty(&mut self.out, &obj)?;
+                self.out.write_all(b"\n")
+            }
+            Format::Yaml => {
+                let doc = serde_yaml::to_string(&[self.object(row)]).map_err(io::Error::other)?;
+                self.out.write_all(doc.as_bytes())
+            }
+            Format::Csv => {
+                let mut w = csv::WriterBuilder::new().from_writer(&mut self.out);
+                if !self.header_done {
+                    w.write_record(self.columns.iter().map(|c| c.name()))?;
+                    self.header_done = true;
+                }
+                w.write_record(self.columns.iter().map(|c| cell(&c.get(row))))?;
+                w.flush()
+            }
+            Format::Table => {
+                // Multi-line values (parse diagnostics) would break the alignment.
+                let one_line = |s: String| s.lines().map(str::trim).collect::<Vec<_>>().join(" ");
+                self.pending
+                    .push(self.columns.iter().map(|c| one_line(cell(&c.get(row)))).collect());
+                Ok(())
+            }
+        }
+    }
+
+    /// Emit anything buffered (the table) and flush.
+    pub fn finish(&mut self) -> io::Result<()> {
+        if self.format == Format::Table && !self.pending.is_empty() {
+            let heads: Vec<&str> = self.columns.iter().map(|c| c.name()).collect();
+            let widths: Vec<usize> = (0..heads.len())
+                .map(|i| {
+                    self.pending
+                        .iter()
+                        .map(|r| r[i].chars().count())
+                        .chain([heads[i].len()])
+                        .max()
+                        .unwrap_or(0)
+                })
+                .collect();
+            let line = |cells: &[&str]| {
+                let padded: Vec<String> = cells
+                    .iter()
+                    .zip(&widths)
+                    .map(|(c, w)| format!("{c}{}", " ".repeat(w - c.chars().count())))
+                    .collect();
+                format!("{}\n", padded.join("  ").trim_end())
+            };
+            self.out.write_all(line(&heads).as_bytes())?;
+            let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+            let rule: Vec<&str> = rule.iter().map(String::as_str).collect();
+            self.out.write_all(line(&rule).as_bytes())?;
+            for r in std::mem::take(&mut self.pending) {
+                let cells: Vec<&str> = r.iter().map(String::as_str).collect();
+                self.out.write_all(line(&cells).as_bytes())?;
+            }
+        }
+        self.out.flush()
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.out
+    }
+
+    fn object(&self, row: &Row) -> Map<String, Value> {
+        row.to_object(&self.columns)
+    }
+}
+
+/// Flat text for CSV/table cells: null → empty, arrays joined with `;`.
+fn cell(v: &Value) -> String {
+    match v {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(a) => a.iter().map(cell).collect::<Vec<_>>().join(";"),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row() -> Row {
+        Row {
+            input: Some("a, b".into()),
+            value: Value::Null,
+            phase: Some("VAC".into()),
+            sheet: Some("C".into()),
+            tags: vec!["sap".into(), "smoke".into()],
+            ..Default::default()
+        }
+    }
+
+    fn render(format: Format, cols: Vec<Column>) -> String {
+        let mut w = RowWriter::new(Vec::new(), format, cols);
+        w.write(&row()).unwrap();
+        w.write(&Row { input: Some("true".into()), value: Value::Bool(true), ..row() }).unwrap();
+        w.finish().unwrap();
+        String::from_utf8(w.out).unwrap()
+    }
+
+    #[test]
+    fn same_fields_in_every_format() {
+        let cols = columns(false, true);
+        let names: Vec<&str> = cols.iter().map(|c| c.name()).collect();
+
+        let nd = render(Format::Ndjson, cols.clone());
+        let first: Map<String, Value> = serde_json::from_str(nd.lines().next().unwrap()).unwrap();
+        assert_eq!(first.keys().map(String::as_str).collect::<Vec<_>>(), names);
+
+        let csv = render(Format::Csv, cols.clone());
+        let mut lines = csv.lines();
+        assert_eq!(lines.next().unwrap(), names.join(","));
+        assert_eq!(lines.next().unwrap(), ",,\"a, b\",,VAC,C,,,,,sap;smoke,");
+
+        let table = render(Format::Table, cols.clone());
+        let head: Vec<&str> = table.lines().next().unwrap().split_whitespace().collect();
+        assert_eq!(head, names);
+
+        let yaml: Vec<Map<String, Value>> =
+            serde_yaml::from_str(&render(Format::Yaml, cols)).unwrap();
+        assert_eq!(yaml.len(), 2);
+        assert_eq!(yaml[1].keys().map(String::as_str).collect::<Vec<_>>(), names);
+    }
+
+    #[test]
+    fn both_is_not_a_gap() {
+        let rendered = [V::Bool(false), V::Unknown, V::Both].map(|v| value(&v));
+        assert_eq!(rendered, [Value::Bool(false), Value::Null, Value::from("both")]);
+    }
+}
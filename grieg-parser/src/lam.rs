@@ -0,0 +1,189 @@
+//! λ-term parser (feature `lam`).
+//!
+//! Numerals parse to Church numerals; free names that match the prelude
+//! (`Y`, `if`, `iszero`, `mul`, `pred`, …) are replaced by their closed definitions.
+
+use pest::error::{Error, ErrorVariant};
+use pest::iterators::Pair;
+use pest::Parser;
+use pest_derive::Parser;
+
+use grieg_engine::lam::ast::L;
+
+use crate::ParseError;
+
+#[derive(Parser)]
+#[grammar = "lam.pest"]
+pub struct LamParser;
+
+/// Largest numeral accepted; a Church numeral is a term of that many applications.
+pub const MAX_NUMERAL: u64 = 1000;
+
+/// Church encodings, in dependency order (later entries may use earlier ones).
+const PRELUDE: &[(&str, &str)] = &[
+    ("I", "\\x. x"),
+    ("K", "\\x y. x"),
+    ("S", "\\x y z. x z (y z)"),
+    ("Y", "\\f. (\\x. f (x x)) (\\x. f (x x))"),
+    ("true", "\\t f. t"),
+    ("false", "\\t f. f"),
+    ("if", "\\p a b. p a b"),
+    ("not", "\\p. p false true"),
+    ("and", "\\p q. p q p"),
+    ("or", "\\p q. p p q"),
+    ("iszero", "\\n. n (\\x. false) true"),
+    ("succ", "\\n f x. f (n f x)"),
+    ("pred", "\\n f x. n (\\g h. h (g f)) (\\u. x) (\\u. u)"),
+    ("add", "\\m n f x. m f (n f x)"),
+    ("sub", "\\m n. n pred m"),
+    ("mul", "\\m n f. m (n f)"),
+];
+
+/// Parse a λ-term, expanding numerals and prelude names.
+pub fn parse_lam(input: &str) -> Result<L, ParseError> {
+    let raw = parse_raw(input)?;
+    let mut defs: Vec<(&str, L)> = Vec::with_capacity(PRELUDE.len());
+    for (name, src) in PRELUDE {
+        let body = resolve(parse_raw(src)?, &defs, &mut Vec::new());
+        defs.push((name, body));
+    }
+    Ok(resolve(raw, &defs, &mut Vec::new()))
+}
+
+fn parse_raw(input: &str) -> Result<L, ParseError> {
+    let mut pairs = LamParser::parse(Rule::lam_program, input).map_err(Box::new)?;
+    let term = pairs.next().unwrap().into_inner().next().unwrap();
+    build_term(term)
+}
+
+/// Replace free prelude names with their definitions (bound names shadow the prelude).
+fn resolve(t: L, defs: &[(&str, L)], bound: &mut Vec<String>) -> L {
+    match t {
+        L::Var(v) => {
+            if bound.contains(&v) {
+                return L::Var(v);
+            }
+            match defs.iter().rev().find(|(n, _)| *n == v) {
+                Some((_, d)) => d.clone(),
+                None => L::Var(v),
+            }
+        }
+        L::Lam(x, b) => {
+            bound.push(x.clone());
+            let b = resolve(*b, defs, bound);
+            bound.pop();
+            L::Lam(x, Box::new(b))
+        }
+        L::App(f, a) => {
+            L::App(Box::new(resolve(*f, defs, bound)), Box::new(resolve(*a, defs, bound)))
+        }
+    }
+}
+
+fn build_term(pair: Pair<Rule>) -> Result<L, ParseError> {
+    let inner = pair.into_inner().next().unwrap();
+    match inner.as_rule() {
+        Rule::abs => build_abs(inner),
+        Rule::app => build_app(inner),
+        _ => unreachable!(),
+    }
+}
+
+fn build_abs(pair: Pair<Rule>) -> Result<L, ParseError> {
+    let mut vars = Vec::new();
+    let mut body = None;
+    for p in pair.into_inner() {
+        match p.as_rule() {
+            Rule::var => vars.push(p.as_str().to_string()),
+            Rule::term => body = Some(build_term(p)?),
+            _ => unreachable!(),
+        }
+    }
+    Ok(vars.into_iter().rev().fold(body.unwrap(), |b, x| L::Lam(x, Box::new(b))))
+}
+
+fn build_app(pair: Pair<Rule>) -> Result<L, ParseError> {
+    let mut parts = pair.into_inner().map(|p| match p.as_rule() {
+        Rule::atom => build_atom(p),
+        Rule::abs => build_abs(p),
+        _ => unreachable!(),
+    });
+    let first = parts.next().unwrap()?;
+    parts.try_fold(first, |f, a| Ok(L::App(Box::new(f), Box::new(a?))))
+}
+
+fn build_atom(pair: Pair<Rule>) -> Result<L, ParseError> {
+    let inner = pair.into_inner().next().unwrap();
+    match inner.as_rule() {
+        Rule::numeral => match inner.as_str().parse::<u64>() {
+            Ok(n) if n <= MAX_NUMERAL => Ok(church(n)),
+            _ => {
+                let message = format!("numeral exceeds the maximum of {MAX_NUMERAL}");
+                let variant = ErrorVariant::<Rule>::CustomError { message };
+                Err(Box::new(Error::new_from_span(variant, inner.as_span())).into())
+            }
+        },
+        Rule::var => Ok(L::Var(inner.as_str().to_string())),
+        Rule::term => build_term(inner),
+        _ => unreachable!(),
+    }
+}
+
+fn church(n: u64) -> L {
+    let mut body = L::Var("x".into());
+    for _ in 0..n {
+        body = L::App(Box::new(L::Var("f".into())), Box::new(body));
+    }
+    L::Lam("f".into(), Box::new(L::Lam("x".into(), Box::new(body))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use grieg_engine::lam::eval::{church_numeral, eval_normal_order};
+    use grieg_engine::phase::Phase;
+
+    #[test]
+    fn factorial_reaches_normal_form() {
+        let t = parse_lam("Y (\\f. \\n. if (iszero n) 1 (mul n (f (pred n)))) 5").unwrap();
+        let r = eval_normal_order(t, 50_000);
+        assert_eq!(r.phase(), Phase::ALIVE);
+        assert_eq!(church_numeral(&r.term), Some(120));
+        assert_eq!(r.steps, 27_876);
+    }
+
+    #[test]
+    fn substitution_avoids_capture() {
+        let t = parse_lam("(\\x. \\y. x) y").unwrap();
+        let r = eval_normal_order(t, 10);
+        assert_eq!(r.term, L::Lam("y1".into(), Box::new(L::Var("y".into()))));
+        let t = parse_lam("(\\x. \\y. x y) (\\y. y)").unwrap();
+        let r = eval_normal_order(t, 10);
+        assert_eq!(r.term, L::Lam("y".into(), Box::new(L::Var("y".into()))));
+    }
+
+    #[test]
+    fn self_application_exhausts_fuel() {
+        let t = parse_lam("Y (\\x. x x)").unwrap();
+        let r = eval_normal_order(t, 1000);
+        assert!(r.diverged);
+        assert_eq!(r.phase(), Phase::JAM);
+    }
+
+    #[test]
+    fn bound_names_shadow_prelude() {
+        let t = parse_lam("(\\mul. mul) 2").unwrap();
+        let r = eval_normal_order(t, 100);
+        assert_eq!(church_numeral(&r.term), Some(2));
+    }
+
+    #[test]
+    fn oversized_numerals_are_rejected() {
+        let e = parse_lam("succ 1234567890123456789012345").unwrap_err();
+        assert!(e.to_string().contains("numeral exceeds the maximum"), "{e}");
+        assert_eq!(e.line_col(), (1, 6));
+        assert!(parse_lam(&(MAX_NUMERAL + 1).to_string()).is_err());
+        let t = parse_lam(&MAX_NUMERAL.to_string()).unwrap();
+        assert_eq!(church_numeral(&t), Some(MAX_NUMERAL));
+    }
+}
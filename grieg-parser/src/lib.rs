@@ -5,6 +5,12 @@ use thiserror::Error;
 
 use grieg_engine::ast::{Expr, PhaseOp};
 
+#[cfg(feature = "lam")]
+pub mod lam;
+mod sexpr;
+
+pub use sexpr::{parse_sexpr, SexprError};
+
 #[derive(Parser)]
 #[grammar = "grammar.pest"]
 pub struct GriegParser;
@@ -12,11 +18,28 @@ pub struct GriegParser;
 #[derive(Debug, Error)]
 pub enum ParseError {
     #[error("parse error: {0}")]
-    Pest(#[from] pest::error::Error<Rule>),
+    Pest(#[from] Box<pest::error::Error<Rule>>),
+    #[cfg(feature = "lam")]
+    #[error("parse error: {0}")]
+    Lam(#[from] Box<pest::error::Error<lam::Rule>>),
+}
+
+impl ParseError {
+    /// 1-based `(line, column)` where parsing stopped.
+    pub fn line_col(&self) -> (usize, usize) {
+        let lc = match self {
+            ParseError::Pest(e) => &e.line_col,
+            #[cfg(feature = "lam")]
+            ParseError::Lam(e) => &e.line_col,
+        };
+        match lc {
+            pest::error::LineColLocation::Pos(p) | pest::error::LineColLocation::Span(p, _) => *p,
+        }
+    }
 }
 
 pub fn parse_expr(input: &str) -> Result<Expr, ParseError> {
-    let mut pairs = GriegParser::parse(Rule::program, input)?;
+    let mut pairs = GriegParser::parse(Rule::program, input).map_err(Box::new)?;
     let expr_pair = pairs.next().unwrap().into_inner().next().unwrap(); // expr
     Ok(build_any(expr_pair))
 }
@@ -159,6 +182,11 @@ mod tests {
         }
     }
 
+    #[test]
+    fn error_reports_line_col() {
+        assert_eq!(parse_expr("a &\n  )").unwrap_err().line_col(), (2, 3));
+    }
+
     #[test]
     fn precedence_not_and_or() {
         // ~ binds tighter than &, which binds tighter than |
@@ -0,0 +1,119 @@
+//! Reader for the s-expression form written by `grieg_engine::ast::to_sexpr`
+//! (`(-> id:A (~ true))`), as found in telemetry records.
+
+use thiserror::Error;
+
+use grieg_engine::ast::{Expr, PhaseOp};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("s-expression error at byte {pos}: {message}")]
+pub struct SexprError {
+    pub pos: usize,
+    pub message: String,
+}
+
+struct Reader<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn err<T>(&self, message: impl Into<String>) -> Result<T, SexprError> {
+        Err(SexprError { pos: self.pos, message: message.into() })
+    }
+
+    fn skip_ws(&mut self) {
+        let rest = &self.src[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// The next run of characters up to whitespace or a parenthesis.
+    fn token(&mut self) -> &'a str {
+        self.skip_ws();
+        let rest = &self.src[self.pos..];
+        let end =
+            rest.find(|c: char| c.is_whitespace() || c == '(' || c == ')').unwrap_or(rest.len());
+        self.pos += end;
+        &rest[..end]
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), SexprError> {
+        self.skip_ws();
+        if self.src[self.pos..].starts_with(c) {
+            self.pos += c.len_utf8();
+            Ok(())
+        } else {
+            self.err(format!("expected {c:?}"))
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, SexprError> {
+        self.skip_ws();
+        if self.src[self.pos..].starts_with('(') {
+            self.pos += 1;
+            let start = self.pos;
+            let op = self.token();
+            let e = match op {
+                "~" => Expr::Not(Box::new(self.expr()?)),
+                "&" => Expr::And(Box::new(self.expr()?), Box::new(self.expr()?)),
+                "|" => Expr::Or(Box::new(self.expr()?), Box::new(self.expr()?)),
+                "->" => Expr::Imp(Box::new(self.expr()?), Box::new(self.expr()?)),
+                "@mem" => Expr::PhaseOp(PhaseOp::Mem, Box::new(self.expr()?)),
+                "@jam" => Expr::PhaseOp(PhaseOp::Jam, Box::new(self.expr()?)),
+                "@alive" => Expr::PhaseOp(PhaseOp::Alive, Box::new(self.expr()?)),
+                "@vac" => Expr::PhaseOp(PhaseOp::Vac, Box::new(self.expr()?)),
+                _ => {
+                    self.pos = start;
+                    return self.err(format!("unknown operator {op:?}"));
+                }
+            };
+            self.expect(')')?;
+            return Ok(e);
+        }
+        let start = self.pos;
+        match self.token() {
+            "true" => Ok(Expr::Bool(true)),
+            "false" => Ok(Expr::Bool(false)),
+            t => match t.strip_prefix("id:") {
+                Some(name) if !name.is_empty() => Ok(Expr::Ident(name.to_string())),
+                _ => {
+                    self.pos = start;
+                    self.err(if t.is_empty() {
+                        "expected an expression".into()
+                    } else {
+                        format!("unexpected {t:?}")
+                    })
+                }
+            },
+        }
+    }
+}
+
+/// Parse one s-expression; the inverse of `to_sexpr`.
+pub fn parse_sexpr(input: &str) -> Result<Expr, SexprError> {
+    let mut r = Reader { src: input, pos: 0 };
+    let e = r.expr()?;
+    r.skip_ws();
+    if r.pos < input.len() {
+        return r.err("trailing input");
+    }
+    Ok(e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_expr;
+    use grieg_engine::ast::to_sexpr;
+
+    #[test]
+    fn reads_what_to_sexpr_writes() {
+        for src in ["true", "~a & (b | false)", "@mem(a -> b -> ~c)", "@vac(@jam(x)) | @alive(y)"] {
+            let e = parse_expr(src).unwrap();
+            assert_eq!(parse_sexpr(&to_sexpr(&e)).unwrap(), e, "{src}");
+        }
+        assert_eq!(parse_sexpr("(-> true").unwrap_err().message, "expected an expression");
+        assert_eq!(parse_sexpr("(=> a b)").unwrap_err().pos, 1);
+        assert!(parse_sexpr("true false").is_err());
+    }
+}
@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod props {
     use grieg_engine::eval::Evaluator;
+    use grieg_engine::mem::Fact;
     use grieg_engine::phase::Phase;
     use grieg_parser::parse_expr;
 
@@ -47,6 +48,78 @@ mod props {
         assert_eq!(r.value.to_bool(), None);
     }
 
+    #[test]
+    fn conflicting_witnesses_jam() {
+        let mut ev = Evaluator::new(true);
+        ev.import_mem_from("erp", [("paid".to_string(), true)].into());
+        ev.import_mem_from("crm", [("paid".to_string(), false)].into());
+        let e = parse_expr("paid | true").unwrap();
+        let r = ev.eval(&e, None);
+        assert_eq!(r.phase, Phase::JAM);
+        assert_eq!(r.conflicts[0].sources().collect::<Vec<_>>(), ["crm", "erp"]);
+
+        let (_, steps) = ev.eval_traced(&e);
+        assert_eq!(steps[0].note.as_deref(), Some("conflict paid: false (crm), true (erp)"));
+        assert_eq!(ev.consistency().conflicts.len(), 1);
+
+        ev.retract_mem_from("crm", ["paid"]);
+        assert_eq!(ev.eval(&e, None).phase, Phase::MEM);
+    }
+
+    #[test]
+    fn stale_witnesses_fall_back_to_vac() {
+        let mut ev = Evaluator::new(true);
+        let fact = Fact::new("sensor", "door", true).with_confidence(0.8);
+        ev.assert_mem(fact.clone());
+        let e = parse_expr("door").unwrap();
+        let r = ev.eval(&e, None);
+        assert_eq!((r.phase, r.witnesses), (Phase::MEM, vec![fact.clone()]));
+
+        ev.assert_mem(fact.with_ttl_secs(0));
+        let (r, steps) = ev.eval_traced(&e);
+        assert_eq!((r.phase, r.value.to_bool(), r.expired.len()), (Phase::VAC, None, 1));
+        let note = steps[0].note.as_deref().unwrap();
+        assert!(note.starts_with("expired door = true (sensor, asserted "), "{note}");
+        assert!(note.ends_with(", confidence 0.8)"), "{note}");
+        assert_eq!((ev.mem_facts().len(), ev.mem_history("door").len()), (1, 2));
+        assert!(ev.export_mem().is_empty());
+    }
+
+    #[test]
+    fn readers_never_see_half_a_batch() {
+        use std::sync::{Arc, Mutex};
+        use std::thread;
+
+        let ev = Arc::new(Mutex::new(Evaluator::new(true)));
+        let e = parse_expr("(debit -> credit) & (credit -> debit)").unwrap();
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let (ev, e) = (ev.clone(), e.clone());
+                thread::spawn(move || {
+                    for _ in 0..200 {
+                        let mut view = ev.lock().unwrap().snapshot();
+                        let r = view.eval(&e, None);
+                        assert_ne!(r.value.to_bool(), Some(false), "half-applied batch");
+                    }
+                })
+            })
+            .collect();
+        for i in 0..200 {
+            let mut ev = ev.lock().unwrap();
+            let mut tx = ev.begin();
+            tx.assert(Fact::new("sap", "debit", i % 2 == 0));
+            tx.assert(Fact::new("sap", "credit", i % 2 == 0));
+            if i % 3 == 0 {
+                tx.rollback();
+            } else {
+                assert_eq!(ev.commit(tx), 2);
+            }
+        }
+        for r in readers {
+            r.join().unwrap();
+        }
+    }
+
     #[test]
     fn jam_dominance_in_join() {
         let mut ev = Evaluator::new(false);
@@ -60,7 +133,7 @@ mod props {
 
 #[test]
 fn mem_transport_preserves_truth() {
-    use grieg_engine::{eval::Evaluator, value::V};
+    use grieg_engine::{eval::Evaluator, phase::Sheet};
     use grieg_parser::parse_expr;
 
     let mut ev = Evaluator::new(true); // mem enabled
@@ -70,12 +143,13 @@ fn mem_transport_preserves_truth() {
         let e_mem = parse_expr(&format!("@mem({})", s)).unwrap();
         let r1 = ev.eval(&e_mem, None);
         assert_eq!(r0.value.to_bool(), r1.value.to_bool(), "MEM must preserve boolean truth");
+        assert_eq!((r0.sheet, r1.sheet), (Sheet::F, Sheet::F), "MEM returns to the caller's sheet");
     }
 }
 
 #[test]
 fn vac_projects_to_counterfactual() {
-    use grieg_engine::{eval::Evaluator, phase::Phase};
+    use grieg_engine::{eval::Evaluator, phase::Phase, phase::Sheet};
     use grieg_parser::parse_expr;
 
     let mut ev = Evaluator::new(true);
@@ -83,5 +157,303 @@ fn vac_projects_to_counterfactual() {
     let r = ev.eval(&e, None);
     assert!(r.value.to_bool().is_none(), "VAC => no total boolean");
     assert_eq!(r.phase, Phase::VAC, "VAC phase marks counterfactual sheet");
+    assert_eq!(r.sheet, Sheet::C);
 }
 
+
+#[cfg(test)]
+mod trace_props {
+    use grieg_engine::ast::{Expr, PhaseOp};
+    use grieg_engine::eval::Evaluator;
+    use grieg_engine::phase::{Phase, Sheet};
+    use grieg_engine::value::V;
+    use proptest::prelude::*;
+
+    /// Random expressions over a few identifiers, all phase operators included.
+    pub fn arb_expr() -> impl Strategy<Value = Expr> {
+        let leaf = prop_oneof![
+            any::<bool>().prop_map(Expr::Bool),
+            prop::sample::select(vec!["a", "b", "c"]).prop_map(|s| Expr::Ident(s.into())),
+        ];
+        leaf.prop_recursive(5, 48, 2, |inner| {
+            let op = prop::sample::select(vec![
+                PhaseOp::Mem,
+                PhaseOp::Jam,
+                PhaseOp::Alive,
+                PhaseOp::Vac,
+            ]);
+            prop_oneof![
+                inner.clone().prop_map(|x| Expr::Not(Box::new(x))),
+                (inner.clone(), inner.clone()).prop_map(|(a, b)| Expr::And(a.into(), b.into())),
+                (inner.clone(), inner.clone()).prop_map(|(a, b)| Expr::Or(a.into(), b.into())),
+                (inner.clone(), inner.clone()).prop_map(|(a, b)| Expr::Imp(a.into(), b.into())),
+                (op, inner).prop_map(|(op, x)| Expr::PhaseOp(op, x.into())),
+            ]
+        })
+    }
+
+    fn size(e: &Expr) -> usize {
+        match e {
+            Expr::Bool(_) | Expr::Ident(_) => 1,
+            Expr::Not(x) | Expr::PhaseOp(_, x) => 1 + size(x),
+            Expr::And(a, b) | Expr::Or(a, b) | Expr::Imp(a, b) => 1 + size(a) + size(b),
+        }
+    }
+
+    /// No identifiers and no phase operators: a classical run on F.
+    fn ground(e: &Expr) -> bool {
+        match e {
+            Expr::Bool(_) => true,
+            Expr::Ident(_) | Expr::PhaseOp(..) => false,
+            Expr::Not(x) => ground(x),
+            Expr::And(a, b) | Expr::Or(a, b) | Expr::Imp(a, b) => ground(a) && ground(b),
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn tracing_does_not_change_outcomes(e in arb_expr(), mem in any::<bool>()) {
+            let plain = Evaluator::new(mem).eval(&e, None);
+            let (traced, steps) = Evaluator::new(mem).eval_traced(&e);
+            prop_assert_eq!(&plain.value, &traced.value);
+            prop_assert_eq!(plain.phase, traced.phase);
+            prop_assert_eq!(steps.len(), size(&e));
+            prop_assert_eq!(steps.last().unwrap().post, traced.phase);
+        }
+
+        #[test]
+        fn sheets_follow_the_transport_rules(e in arb_expr(), mem in any::<bool>()) {
+            let (r, steps) = Evaluator::new(mem).eval_traced(&e);
+            prop_assert_eq!(steps.last().unwrap().sheet, Some(r.sheet.as_str()));
+            prop_assert!(steps.iter().all(|s| s.sheet.is_some()));
+            // VAC projects into C; a run without witnesses or phase operators stays on F.
+            if r.value == V::Unknown || r.phase == Phase::VAC {
+                prop_assert_eq!(r.sheet, Sheet::C);
+            }
+            if ground(&e) {
+                prop_assert_eq!(r.sheet, Sheet::F);
+            }
+            // `@mem` transports a definite value to the other sheet and back.
+            let twice = Expr::PhaseOp(PhaseOp::Mem, Expr::PhaseOp(PhaseOp::Mem, e.into()).into());
+            let r2 = Evaluator::new(mem).eval(&twice, None);
+            if r2.value != V::Unknown {
+                prop_assert_eq!(r2.sheet, r.sheet);
+            }
+        }
+
+        #[test]
+        fn sexpr_round_trips(e in arb_expr()) {
+            let text = grieg_engine::ast::to_sexpr(&e);
+            prop_assert_eq!(grieg_parser::parse_sexpr(&text).unwrap(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod pulse_props {
+    use std::collections::HashMap;
+
+    use grieg_engine::ast::{Expr, PhaseOp};
+    use grieg_engine::eval::Evaluator;
+    use grieg_engine::phase::{Phase, Sheet};
+    use grieg_engine::pulse::PulseKind;
+    use grieg_engine::value::V;
+    use proptest::prelude::*;
+
+    use crate::trace_props::arb_expr;
+
+    fn evaluator(mem: bool, facts: (bool, bool)) -> Evaluator {
+        let mut ev = Evaluator::new(mem);
+        ev.import_mem(HashMap::from([("a".to_string(), facts.0), ("b".to_string(), facts.1)]));
+        ev
+    }
+
+    /// The links of one implication chain (a run of `->` linked through consequents), in
+    /// evaluation order, with their post-order step indices.
+    struct Chain<'e> {
+        /// The chain is evaluated on F: it sits under an even number of `@mem`.
+        on_f: bool,
+        links: Vec<(usize, &'e Expr)>,
+    }
+
+    fn implication_chains(e: &Expr) -> Vec<Chain<'_>> {
+        fn walk<'e>(
+            e: &'e Expr,
+            on_f: bool,
+            chain: Option<usize>,
+            next: &mut usize,
+            chains: &mut Vec<Chain<'e>>,
+        ) {
+            match e {
+                Expr::Imp(a, b) => {
+                    let c = chain.unwrap_or_else(|| {
+                        chains.push(Chain { on_f, links: Vec::new() });
+                        chains.len() - 1
+                    });
+                    walk(a, on_f, None, next, chains);
+                    walk(b, on_f, Some(c), next, chains);
+                    chains[c].links.push((*next, e));
+                }
+                Expr::Bool(_) | Expr::Ident(_) => {}
+                Expr::PhaseOp(PhaseOp::Mem, x) => walk(x, !on_f, None, next, chains),
+                Expr::Not(x) | Expr::PhaseOp(_, x) => walk(x, on_f, None, next, chains),
+                Expr::And(a, b) | Expr::Or(a, b) => {
+                    walk(a, on_f, None, next, chains);
+                    walk(b, on_f, None, next, chains);
+                }
+            }
+            *next += 1;
+        }
+        let mut chains = Vec::new();
+        walk(e, true, None, &mut 0, &mut chains);
+        chains
+    }
+
+    /// Where `chain` sinks, by I2 read off the links alone: the first link in evaluation order
+    /// whose antecedent is `true` and consequent definite, with the link itself on F.
+    fn expected_sink(ev: &mut Evaluator, chain: &Chain) -> Option<usize> {
+        if !chain.on_f {
+            return None;
+        }
+        chain.links.iter().position(|&(_, link)| {
+            let Expr::Imp(a, b) = link else { unreachable!("chain link is not `->`") };
+            ev.eval(a, None).value == V::Bool(true)
+                && ev.eval(b, None).value.to_bool().is_some()
+                && ev.eval(link, None).sheet == Sheet::F
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn pulses_do_not_change_outcomes(
+            e in arb_expr(),
+            mem in any::<bool>(),
+            f in any::<(bool, bool)>(),
+        ) {
+            let plain = evaluator(mem, f).eval(&e, None);
+            let (pulsed, _) = evaluator(mem, f).eval_pulsed(&e);
+            prop_assert_eq!(plain.value, pulsed.value);
+            prop_assert_eq!(plain.phase, pulsed.phase);
+        }
+
+        #[test]
+        fn at_most_one_sink_per_implication_chain(e in arb_expr(), f in any::<(bool, bool)>()) {
+            let (_, pulses) = evaluator(true, f).eval_pulsed(&e);
+            for chain in implication_chains(&e) {
+                let sinks = pulses
+                    .iter()
+                    .filter(|p| p.kind == PulseKind::Sink)
+                    .filter(|p| chain.links.iter().any(|&(i, _)| i == p.step))
+                    .count();
+                prop_assert!(sinks <= 1, "{} sinks on chain {:?}: {:?}", sinks, chain.links, pulses);
+            }
+        }
+
+        #[test]
+        fn sinks_are_absorbing_from_the_first_modus_ponens_link(
+            e in arb_expr(),
+            f in any::<(bool, bool)>(),
+        ) {
+            let mut ev = evaluator(true, f);
+            let (r, steps) = ev.eval_traced(&e);
+            let chains = implication_chains(&e);
+            let mut counts = Vec::new();
+            for chain in &chains {
+                let sink = expected_sink(&mut ev, chain);
+                for (k, &(i, _)) in chain.links.iter().enumerate() {
+                    let sunk = sink.is_some_and(|s| k >= s);
+                    prop_assert_eq!(steps[i].sink, sunk, "link {} of {:?}", k, chain.links);
+                }
+                counts.push(usize::from(sink.is_some()));
+            }
+            prop_assert_eq!(r.sinks, counts);
+            // Only `->` steps sink.
+            let links = chains.iter().map(|c| c.links.len()).sum::<usize>();
+            prop_assert!(steps.iter().filter(|s| s.sink).count() <= links);
+            prop_assert!(steps.iter().filter(|s| s.sink).all(|s| s.op == "implies"));
+        }
+
+        #[test]
+        fn pulses_match_their_steps(e in arb_expr(), f in any::<(bool, bool)>()) {
+            let mut ev = evaluator(true, f);
+            let (_, pulses) = ev.eval_pulsed(&e);
+            let (_, steps) = ev.eval_traced(&e);
+            // One pass records the same steps and pulses as two.
+            let (_, both, both_pulses) = ev.eval_traced_pulsed(&e);
+            prop_assert_eq!(format!("{both:?}"), format!("{steps:?}"));
+            prop_assert_eq!(&both_pulses, &pulses);
+            for p in &pulses {
+                let s = &steps[p.step];
+                prop_assert_eq!(p.op, s.op);
+                prop_assert_eq!(p.phase, s.post);
+                match p.kind {
+                    PulseKind::Boundary => prop_assert!(s.pre != Phase::JAM),
+                    PulseKind::Transport => prop_assert_eq!(s.op, "@mem"),
+                    PulseKind::Witness => prop_assert!(s.op == "ident" && s.post != Phase::VAC),
+                    PulseKind::Sink => prop_assert!(s.sink),
+                    PulseKind::Winding => prop_assert_eq!(s.op, "not"),
+                }
+            }
+            // Every entry into JAM is reported, once.
+            let entries = steps.iter().filter(|s| s.pre != Phase::JAM && s.post == Phase::JAM);
+            let boundaries = pulses.iter().filter(|p| p.kind == PulseKind::Boundary);
+            prop_assert_eq!(entries.count(), boundaries.count());
+            // Each sunk chain fires one Sink, at its first sunk link.
+            let sinks = pulses.iter().filter(|p| p.kind == PulseKind::Sink).count();
+            let sunk = implication_chains(&e)
+                .iter()
+                .filter(|c| c.links.iter().any(|&(i, _)| steps[i].sink))
+                .count();
+            prop_assert_eq!(sinks, sunk);
+        }
+    }
+}
+
+#[cfg(test)]
+mod logic_props {
+    use grieg_engine::ast::{Expr, PhaseOp};
+    use grieg_engine::eval::Evaluator;
+    use grieg_engine::value::V;
+    use grieg_engine::Logic;
+    use proptest::prelude::*;
+
+    use crate::trace_props::arb_expr;
+
+    fn eval(logic: Logic, e: &Expr) -> V {
+        Evaluator::new(false).with_logic(logic).eval(e, None).value
+    }
+
+    fn has_jam(e: &Expr) -> bool {
+        match e {
+            Expr::Bool(_) | Expr::Ident(_) => false,
+            Expr::PhaseOp(PhaseOp::Jam, _) => true,
+            Expr::Not(x) | Expr::PhaseOp(_, x) => has_jam(x),
+            Expr::And(a, b) | Expr::Or(a, b) | Expr::Imp(a, b) => has_jam(a) || has_jam(b),
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn self_implication(e in arb_expr()) {
+            let imp = Expr::Imp(e.clone().into(), e.clone().into());
+            prop_assert_eq!(eval(Logic::L3, &imp), V::Bool(true));
+            prop_assert_eq!(eval(Logic::Classical, &imp), V::Bool(true));
+            let k3 = eval(Logic::K3, &imp);
+            prop_assert_eq!(k3 == V::Unknown, eval(Logic::K3, &e) == V::Unknown);
+        }
+
+        #[test]
+        fn domains_agree_where_they_overlap(e in arb_expr()) {
+            let k3 = eval(Logic::K3, &e);
+            prop_assert!(eval(Logic::Classical, &e).to_bool().is_some());
+            prop_assert!(k3 != V::Both && eval(Logic::L3, &e) != V::Both);
+            if !has_jam(&e) {
+                prop_assert_eq!(eval(Logic::Four, &e), k3.clone());
+            }
+            if let V::Bool(b) = k3 {
+                // A gap that K3 can decide around decides the same way in Ł3.
+                prop_assert_eq!(eval(Logic::L3, &e), V::Bool(b));
+            }
+        }
+    }
+}
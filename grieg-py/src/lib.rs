@@ -1,34 +1,42 @@
+// pyo3 0.22 `#[pyfunction]` expansion trips this lint on `PyResult` returns.
+#![allow(clippy::useless_conversion)]
+
 use pyo3::prelude::*;
 use pyo3::types::{PyDict, PyModule};
 
 use grieg_parser::parse_expr;
-use grieg_engine::Evaluator;
+use grieg_engine::{Evaluator, Logic};
 
 // --- internal impl shared by both Python entry points ---
-fn eval_impl(py: Python, expr: &str, mem: bool) -> PyResult<PyObject> {
+fn eval_impl(py: Python, expr: &str, mem: bool, logic: &str) -> PyResult<PyObject> {
+    let logic: Logic = logic.parse().map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
     let parsed = parse_expr(expr)
         .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("{e}")))?;
-    let mut ev = Evaluator::new(mem);
+    let mut ev = Evaluator::new(mem).with_logic(logic);
     let res = ev.eval(&parsed, None);
 
     let out = PyDict::new_bound(py);
     out.set_item("expr", expr)?;
     out.set_item("mem", mem)?;
+    out.set_item("logic", logic.name())?;
     out.set_item("phase", format!("{:?}", res.phase))?;
+    out.set_item("sheet", res.sheet.as_str())?;
     out.set_item("value", format!("{:?}", res.value))?;
     Ok(out.into_py(py))
 }
 
 // Preferred API: expr(...)
 #[pyfunction(name = "expr")]
-fn expr_py(py: Python, expr: &str, mem: bool) -> PyResult<PyObject> {
-    eval_impl(py, expr, mem)
+#[pyo3(signature = (expr, mem, logic = "k3"))]
+fn expr_py(py: Python, expr: &str, mem: bool, logic: &str) -> PyResult<PyObject> {
+    eval_impl(py, expr, mem, logic)
 }
 
 // Back-compat alias: eval(...)
 #[pyfunction(name = "eval")]
-fn eval_py(py: Python, expr: &str, mem: bool) -> PyResult<PyObject> {
-    eval_impl(py, expr, mem)
+#[pyo3(signature = (expr, mem, logic = "k3"))]
+fn eval_py(py: Python, expr: &str, mem: bool, logic: &str) -> PyResult<PyObject> {
+    eval_impl(py, expr, mem, logic)
 }
 
 #[pymodule]